@@ -112,6 +112,25 @@ export function useDictation() {
     }
   }
 
+  async function cancelDictation() {
+    if (!isRecording.value || isStopping.value) return
+    try {
+      error.value = null
+      await tauriInvoke('cancel_dictation')
+    } catch (e) {
+      error.value = e
+    }
+  }
+
+  function resetAfterCancel() {
+    stopDurationTimer()
+    isRecording.value = false
+    streamingText.value = ''
+    partialChunks.value = []
+    audioLevel.value = 0
+    silenceCountdown.value = null
+  }
+
   async function toggleDictation() {
     if (isProcessing.value || isStopping.value) return
     if (isRecording.value) {
@@ -121,6 +140,16 @@ export function useDictation() {
     }
   }
 
+  // A tap only discards a recording its own key-down started.
+  function releasePushToTalk() {
+    if (!isRecording.value || isStopping.value) return
+    if (pushToTalk === 'up') {
+      stopDictation()
+    } else if (pushToTalkStarted) {
+      cancelDictation()
+    }
+  }
+
   async function getStatus() {
     try {
       const status = await tauriInvoke('get_dictation_status')
@@ -137,12 +166,17 @@ export function useDictation() {
   let unlistenStatus = null
   let unlistenResult = null
   let unlistenToggle = null
+  let unlistenPushToTalk = null
+  // Last push-to-talk event: 'down', 'up' (held long enough) or 'tap' (released too early)
+  let pushToTalk = null
+  let pushToTalkStarted = false
   let unlistenPartial = null
   let unlistenAudioLevel = null
   let unlistenRefineChunk = null
   let unlistenRefineStatus = null
   let unlistenSilenceCountdown = null
   let unlistenAutoStop = null
+  let unlistenCancelled = null
 
   onMounted(async () => {
     unlistenStatus = await tauriListen('dictation-status', (event) => {
@@ -160,6 +194,22 @@ export function useDictation() {
       }
     })
 
+    unlistenPushToTalk = await tauriListen('push-to-talk', async (event) => {
+      pushToTalk = event.payload
+      if (pushToTalk === 'down') {
+        pushToTalkStarted = false
+        if (!isRecording.value && !isProcessing.value && !isStopping.value) {
+          await startDictation()
+          pushToTalkStarted = isRecording.value
+          if (pushToTalk !== 'down') {
+            releasePushToTalk()
+          }
+        }
+      } else {
+        releasePushToTalk()
+      }
+    })
+
     unlistenPartial = await tauriListen('dictation-partial', (event) => {
       const { text, is_final } = event.payload
       if (is_final) {
@@ -205,6 +255,10 @@ export function useDictation() {
       }
     })
 
+    unlistenCancelled = await tauriListen('dictation-cancelled', () => {
+      resetAfterCancel()
+    })
+
     unlistenAutoStop = await tauriListen('dictation-auto-stop', () => {
       if (isRecording.value && !isStopping.value) {
         stopDictation()
@@ -263,12 +317,14 @@ export function useDictation() {
     if (unlistenStatus) unlistenStatus()
     if (unlistenResult) unlistenResult()
     if (unlistenToggle) unlistenToggle()
+    if (unlistenPushToTalk) unlistenPushToTalk()
     if (unlistenPartial) unlistenPartial()
     if (unlistenAudioLevel) unlistenAudioLevel()
     if (unlistenRefineChunk) unlistenRefineChunk()
     if (unlistenRefineStatus) unlistenRefineStatus()
     if (unlistenSilenceCountdown) unlistenSilenceCountdown()
     if (unlistenAutoStop) unlistenAutoStop()
+    if (unlistenCancelled) unlistenCancelled()
   })
 
   const pipelineStage = computed(() => {
@@ -299,6 +355,7 @@ export function useDictation() {
     sessionActive,
     startDictation,
     stopDictation,
+    cancelDictation,
     toggleDictation,
     getStatus
   }
@@ -2,13 +2,18 @@ import { tauriInvoke } from '~/utils/tauri'
 
 export function useHistory() {
   const history = ref([])
+  const total = ref(0)
   const loading = ref(false)
   const error = ref(null)
 
-  async function fetchHistory(search = null, limit = 50, offset = 0) {
+  async function fetchHistory(search = null, limit = 50, offset = 0, filters = {}) {
     loading.value = true
     try {
-      history.value = await tauriInvoke('get_history', { search, limit, offset }) || []
+      const page = await tauriInvoke('query_history', {
+        query: { ...filters, search, limit, offset }
+      })
+      history.value = page?.items || []
+      total.value = page?.total || 0
     } catch (e) {
       error.value = e
     } finally {
@@ -16,10 +21,23 @@ export function useHistory() {
     }
   }
 
+  async function updateItem(id, text) {
+    const suggestions = await tauriInvoke('update_history_item', { id, text })
+    const item = history.value.find(item => item.id === id)
+    if (item && item.text !== text.trim()) {
+      const original = item.original_text ?? item.text
+      item.text = text.trim()
+      item.original_text = original === item.text ? null : original
+      item.snippet = []
+    }
+    return suggestions || []
+  }
+
   async function deleteItem(id) {
     try {
       await tauriInvoke('delete_history_item', { id })
       history.value = history.value.filter(item => item.id !== id)
+      total.value = Math.max(0, total.value - 1)
     } catch (e) {
       error.value = e
     }
@@ -29,6 +47,7 @@ export function useHistory() {
     try {
       await tauriInvoke('clear_history')
       history.value = []
+      total.value = 0
     } catch (e) {
       error.value = e
     }
@@ -36,9 +55,11 @@ export function useHistory() {
 
   return {
     history,
+    total,
     loading,
     error,
     fetchHistory,
+    updateItem,
     deleteItem,
     clearAll
   }
@@ -0,0 +1,47 @@
+import { tauriInvoke } from '~/utils/tauri'
+
+export function useProfiles() {
+  const profiles = ref([])
+  const loading = ref(false)
+  const error = ref(null)
+
+  async function fetchProfiles() {
+    loading.value = true
+    try {
+      profiles.value = await tauriInvoke('list_profiles') || []
+    } catch (e) {
+      error.value = e
+    } finally {
+      loading.value = false
+    }
+  }
+
+  async function saveProfile(profile) {
+    const id = await tauriInvoke('save_profile', { profile })
+    await fetchProfiles()
+    return id
+  }
+
+  async function deleteProfile(id) {
+    try {
+      await tauriInvoke('delete_profile', { id })
+      profiles.value = profiles.value.filter(p => p.id !== id)
+    } catch (e) {
+      error.value = e
+    }
+  }
+
+  async function getActiveWindow() {
+    return await tauriInvoke('get_active_window')
+  }
+
+  return {
+    profiles,
+    loading,
+    error,
+    fetchProfiles,
+    saveProfile,
+    deleteProfile,
+    getActiveWindow
+  }
+}
@@ -26,8 +26,15 @@ export function useSettings() {
   }
 
   async function updateSetting(key, value) {
-    await tauriInvoke('update_setting', { key, value: String(value) })
+    const failures = await tauriInvoke('update_setting', { key, value: String(value) })
     await fetchSettings()
+    return failures
+  }
+
+  async function updateSettings(values) {
+    const failures = await tauriInvoke('update_settings', { values })
+    await fetchSettings()
+    return failures || []
   }
 
   function getSettingValue(key, defaultValue = null) {
@@ -47,6 +54,7 @@ export function useSettings() {
     fetchSettings,
     getSetting,
     updateSetting,
+    updateSettings,
     getSettingValue
   }
 }
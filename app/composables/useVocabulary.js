@@ -0,0 +1,82 @@
+import { tauriInvoke } from '~/utils/tauri'
+
+export function useVocabulary() {
+  const terms = ref([])
+  const preview = ref(null)
+  const suggestions = ref([])
+  const loading = ref(false)
+  const error = ref(null)
+
+  async function fetchPreview() {
+    try {
+      preview.value = await tauriInvoke('preview_vocabulary_prompt')
+    } catch (e) {
+      error.value = e
+    }
+  }
+
+  async function fetchTerms() {
+    loading.value = true
+    try {
+      terms.value = await tauriInvoke('list_vocabulary') || []
+      await fetchPreview()
+    } catch (e) {
+      error.value = e
+    } finally {
+      loading.value = false
+    }
+  }
+
+  async function fetchSuggestions() {
+    try {
+      suggestions.value = await tauriInvoke('list_correction_suggestions') || []
+    } catch (e) {
+      error.value = e
+    }
+  }
+
+  async function acceptSuggestion(id) {
+    await tauriInvoke('accept_correction_suggestion', { id })
+    suggestions.value = suggestions.value.filter(s => s.id !== id)
+    await fetchTerms()
+  }
+
+  async function dismissSuggestion(id) {
+    try {
+      await tauriInvoke('dismiss_correction_suggestion', { id })
+      suggestions.value = suggestions.value.filter(s => s.id !== id)
+    } catch (e) {
+      error.value = e
+    }
+  }
+
+  async function saveTerm(term) {
+    const id = await tauriInvoke('save_vocabulary_term', { term })
+    await fetchTerms()
+    return id
+  }
+
+  async function deleteTerm(id) {
+    try {
+      await tauriInvoke('delete_vocabulary_term', { id })
+      terms.value = terms.value.filter(t => t.id !== id)
+      await fetchPreview()
+    } catch (e) {
+      error.value = e
+    }
+  }
+
+  return {
+    terms,
+    preview,
+    suggestions,
+    loading,
+    error,
+    fetchTerms,
+    fetchSuggestions,
+    acceptSuggestion,
+    dismissSuggestion,
+    saveTerm,
+    deleteTerm
+  }
+}
@@ -0,0 +1,66 @@
+import { tauriInvoke } from '~/utils/tauri'
+
+export function useVoiceCommands() {
+  const commands = ref([])
+  const conflicts = ref([])
+  const loading = ref(false)
+  const error = ref(null)
+
+  async function fetchConflicts() {
+    try {
+      conflicts.value = await tauriInvoke('get_voice_command_conflicts') || []
+    } catch (e) {
+      error.value = e
+    }
+  }
+
+  async function fetchCommands() {
+    loading.value = true
+    try {
+      commands.value = await tauriInvoke('list_voice_commands') || []
+      await fetchConflicts()
+    } catch (e) {
+      error.value = e
+    } finally {
+      loading.value = false
+    }
+  }
+
+  async function saveCommand(command) {
+    const id = await tauriInvoke('save_voice_command', { command })
+    await fetchCommands()
+    return id
+  }
+
+  async function deleteCommand(id) {
+    try {
+      await tauriInvoke('delete_voice_command', { id })
+      commands.value = commands.value.filter(c => c.id !== id)
+      await fetchConflicts()
+    } catch (e) {
+      error.value = e
+    }
+  }
+
+  async function exportCommands() {
+    return await tauriInvoke('export_voice_commands')
+  }
+
+  async function importCommands(json, replace) {
+    const summary = await tauriInvoke('import_voice_commands', { json, replace })
+    await fetchCommands()
+    return summary
+  }
+
+  return {
+    commands,
+    conflicts,
+    loading,
+    error,
+    fetchCommands,
+    saveCommand,
+    deleteCommand,
+    exportCommands,
+    importCommands
+  }
+}
@@ -32,6 +32,8 @@ export async function tauriInvoke(command, args) {
     ]
     if (command === 'detect_gpu') return { cuda_available: false, recommended: 'cpu' }
     if (command === 'get_history') return []
+    if (command === 'search_history') return []
+    if (command === 'query_history') return { items: [], total: 0 }
     if (command === 'get_usage_stats') return []
     if (command === 'get_summary_stats') return { total_dictations: 0, total_words: 0, total_duration: 0, days_active: 0 }
     if (command === 'get_audio_devices') return []
@@ -1,13 +1,14 @@
 use async_trait::async_trait;
 use crate::error::AppError;
+use crate::events::EventSink;
 
 pub const SYSTEM_PROMPT: &str = "\
 You are a text processing engine. You execute text transformation functions and return ONLY the raw output. \
 You never explain, comment, or add anything beyond the function output. \
 You never use tools. You never write code. You only return the processed result.";
 
-pub fn build_user_message(raw_text: &str, language: &str) -> String {
-    match language {
+pub fn build_user_message(raw_text: &str, language: &str, instructions: &str) -> String {
+    let message = match language {
         "en" => format!(
             r#"Execute this text processing function and return ONLY its output, nothing else:
 
@@ -49,6 +50,16 @@ correctArabicText("{}")
 Output:"#,
             raw_text
         ),
+    };
+
+    // Per-app instructions (e.g. "keep code identifiers as-is") go before the call they constrain.
+    match instructions.trim() {
+        "" => message,
+        extra => message.replacen(
+            "\n```\n\n",
+            &format!("\n```\n\nAdditional rules for this function:\n{}\n\n", extra),
+            1,
+        ),
     }
 }
 
@@ -80,7 +91,8 @@ pub trait AIRefiner: Send + Sync {
         &self,
         text: &str,
         language: &str,
-        app: &tauri::AppHandle,
+        instructions: &str,
+        sink: &dyn EventSink,
     ) -> Result<String, AppError>;
 
     fn provider_name(&self) -> &'static str;
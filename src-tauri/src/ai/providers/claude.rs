@@ -1,11 +1,11 @@
 use crate::ai::provider::{build_user_message, clean_refined_text, AIRefiner, SYSTEM_PROMPT};
 use crate::constants::ai::CLAUDE_API_URL;
 use crate::error::AppError;
+use crate::events::{EventSink, PipelineEvent, RefineStatus};
 use async_trait::async_trait;
 use futures_util::StreamExt;
 use reqwest::Client;
 use serde_json::json;
-use tauri::Emitter;
 
 const CLAUDE_MODEL: &str = "claude-sonnet-4-20250514";
 const CLAUDE_API_PATH: &str = "/v1/messages";
@@ -64,16 +64,17 @@ impl AIRefiner for ClaudeRefiner {
         &self,
         text: &str,
         language: &str,
-        app: &tauri::AppHandle,
+        instructions: &str,
+        sink: &dyn EventSink,
     ) -> Result<String, AppError> {
         if text.trim().is_empty() {
             return Ok(text.to_string());
         }
 
         tracing::debug!("[claude] Sending text for refinement: '{}'", text);
-        let _ = app.emit("ai-refine-status", json!({ "status": "started" }));
+        sink.send(PipelineEvent::RefineStatus { status: RefineStatus::Started });
 
-        let user_message = build_user_message(text, language);
+        let user_message = build_user_message(text, language, instructions);
 
         let response = self
             .client
@@ -96,7 +97,7 @@ impl AIRefiner for ClaudeRefiner {
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            let _ = app.emit("ai-refine-status", json!({ "status": "error" }));
+            sink.send(PipelineEvent::RefineStatus { status: RefineStatus::Error });
             return Err(AppError::AIError(format!("Claude API error {}: {}", status, body)));
         }
 
@@ -126,10 +127,10 @@ impl AIRefiner for ClaudeRefiner {
                         if let Some(text_chunk) = content {
                             if !text_chunk.is_empty() {
                                 full_text.push_str(text_chunk);
-                                let _ = app.emit("ai-refine-chunk", json!({
-                                    "chunk": text_chunk,
-                                    "accumulated": &full_text
-                                }));
+                                sink.send(PipelineEvent::RefineChunk {
+                                    chunk: text_chunk.to_string(),
+                                    accumulated: full_text.clone(),
+                                });
                             }
                         }
                     }
@@ -138,7 +139,7 @@ impl AIRefiner for ClaudeRefiner {
         }
 
         let refined = clean_refined_text(&full_text);
-        let _ = app.emit("ai-refine-status", json!({ "status": "done" }));
+        sink.send(PipelineEvent::RefineStatus { status: RefineStatus::Done });
         tracing::debug!("[claude] Refinement complete: '{}'", refined);
         Ok(refined)
     }
@@ -1,10 +1,10 @@
 use crate::ai::provider::{build_user_message, clean_refined_text, AIRefiner, SYSTEM_PROMPT};
 use crate::error::AppError;
+use crate::events::{EventSink, PipelineEvent, RefineStatus};
 use async_trait::async_trait;
 use futures_util::StreamExt;
 use reqwest::Client;
 use serde_json::json;
-use tauri::Emitter;
 
 const DEFAULT_GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models";
 const GEMINI_MODEL: &str = "gemini-2.0-flash";
@@ -74,16 +74,17 @@ impl AIRefiner for GeminiRefiner {
         &self,
         text: &str,
         language: &str,
-        app: &tauri::AppHandle,
+        instructions: &str,
+        sink: &dyn EventSink,
     ) -> Result<String, AppError> {
         if text.trim().is_empty() {
             return Ok(text.to_string());
         }
 
         tracing::debug!("[gemini] Sending text for refinement: '{}'", text);
-        let _ = app.emit("ai-refine-status", json!({ "status": "started" }));
+        sink.send(PipelineEvent::RefineStatus { status: RefineStatus::Started });
 
-        let user_message = build_user_message(text, language);
+        let user_message = build_user_message(text, language, instructions);
         let url = self.build_url(true);
 
         let response = self
@@ -105,7 +106,7 @@ impl AIRefiner for GeminiRefiner {
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            let _ = app.emit("ai-refine-status", json!({ "status": "error" }));
+            sink.send(PipelineEvent::RefineStatus { status: RefineStatus::Error });
             return Err(AppError::AIError(format!("Gemini API error {}: {}", status, body)));
         }
 
@@ -152,10 +153,10 @@ impl AIRefiner for GeminiRefiner {
                         if let Some(text_chunk) = content {
                             if !text_chunk.is_empty() {
                                 full_text.push_str(text_chunk);
-                                let _ = app.emit("ai-refine-chunk", json!({
-                                    "chunk": text_chunk,
-                                    "accumulated": &full_text
-                                }));
+                                sink.send(PipelineEvent::RefineChunk {
+                                    chunk: text_chunk.to_string(),
+                                    accumulated: full_text.clone(),
+                                });
                             }
                         }
                     }
@@ -166,7 +167,7 @@ impl AIRefiner for GeminiRefiner {
         }
 
         let refined = clean_refined_text(&full_text);
-        let _ = app.emit("ai-refine-status", json!({ "status": "done" }));
+        sink.send(PipelineEvent::RefineStatus { status: RefineStatus::Done });
         tracing::debug!("[gemini] Refinement complete: '{}'", refined);
         Ok(refined)
     }
@@ -1,11 +1,11 @@
 use crate::ai::provider::{build_user_message, clean_refined_text, AIRefiner, SYSTEM_PROMPT};
 use crate::constants::ai::GROK_API_URL;
 use crate::error::AppError;
+use crate::events::{EventSink, PipelineEvent, RefineStatus};
 use async_trait::async_trait;
 use futures_util::StreamExt;
 use reqwest::Client;
 use serde_json::json;
-use tauri::Emitter;
 
 const GROK_MODEL: &str = "grok-3-mini";
 const GROK_API_PATH: &str = "/v1/chat/completions";
@@ -63,16 +63,17 @@ impl AIRefiner for GrokRefiner {
         &self,
         text: &str,
         language: &str,
-        app: &tauri::AppHandle,
+        instructions: &str,
+        sink: &dyn EventSink,
     ) -> Result<String, AppError> {
         if text.trim().is_empty() {
             return Ok(text.to_string());
         }
 
         tracing::debug!("[grok] Sending text for refinement: '{}'", text);
-        let _ = app.emit("ai-refine-status", json!({ "status": "started" }));
+        sink.send(PipelineEvent::RefineStatus { status: RefineStatus::Started });
 
-        let user_message = build_user_message(text, language);
+        let user_message = build_user_message(text, language, instructions);
 
         let response = self
             .client
@@ -96,7 +97,7 @@ impl AIRefiner for GrokRefiner {
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            let _ = app.emit("ai-refine-status", json!({ "status": "error" }));
+            sink.send(PipelineEvent::RefineStatus { status: RefineStatus::Error });
             return Err(AppError::AIError(format!("Grok API error {}: {}", status, body)));
         }
 
@@ -130,10 +131,10 @@ impl AIRefiner for GrokRefiner {
                         if let Some(text_chunk) = content {
                             if !text_chunk.is_empty() {
                                 full_text.push_str(text_chunk);
-                                let _ = app.emit("ai-refine-chunk", json!({
-                                    "chunk": text_chunk,
-                                    "accumulated": &full_text
-                                }));
+                                sink.send(PipelineEvent::RefineChunk {
+                                    chunk: text_chunk.to_string(),
+                                    accumulated: full_text.clone(),
+                                });
                             }
                         }
                     }
@@ -142,7 +143,7 @@ impl AIRefiner for GrokRefiner {
         }
 
         let refined = clean_refined_text(&full_text);
-        let _ = app.emit("ai-refine-status", json!({ "status": "done" }));
+        sink.send(PipelineEvent::RefineStatus { status: RefineStatus::Done });
         tracing::debug!("[grok] Refinement complete: '{}'", refined);
         Ok(refined)
     }
@@ -1,11 +1,11 @@
 use crate::ai::provider::{build_user_message, clean_refined_text, AIRefiner, SYSTEM_PROMPT};
 use crate::constants::ai::LOCAL_API_URL;
 use crate::error::AppError;
+use crate::events::{EventSink, PipelineEvent, RefineStatus};
 use async_trait::async_trait;
 use futures_util::StreamExt;
 use reqwest::Client;
 use serde_json::json;
-use tauri::Emitter;
 
 const LOCAL_MODEL: &str = "claude-sonnet-4-5-20250929";
 const LOCAL_API_PATH: &str = "/v1/chat/completions";
@@ -79,16 +79,17 @@ impl AIRefiner for LocalRefiner {
         &self,
         text: &str,
         language: &str,
-        app: &tauri::AppHandle,
+        instructions: &str,
+        sink: &dyn EventSink,
     ) -> Result<String, AppError> {
         if text.trim().is_empty() {
             return Ok(text.to_string());
         }
 
         tracing::debug!("[local] Sending text for refinement: '{}'", text);
-        let _ = app.emit("ai-refine-status", json!({ "status": "started" }));
+        sink.send(PipelineEvent::RefineStatus { status: RefineStatus::Started });
 
-        let user_message = build_user_message(text, language);
+        let user_message = build_user_message(text, language, instructions);
 
         let mut req = self
             .client
@@ -118,7 +119,7 @@ impl AIRefiner for LocalRefiner {
         if !response.status().is_success() {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            let _ = app.emit("ai-refine-status", json!({ "status": "error" }));
+            sink.send(PipelineEvent::RefineStatus { status: RefineStatus::Error });
             return Err(AppError::AIError(format!("Local API error {}: {}", status, body)));
         }
 
@@ -153,10 +154,10 @@ impl AIRefiner for LocalRefiner {
                         if let Some(text_chunk) = content {
                             if !text_chunk.is_empty() {
                                 full_text.push_str(text_chunk);
-                                let _ = app.emit("ai-refine-chunk", json!({
-                                    "chunk": text_chunk,
-                                    "accumulated": &full_text
-                                }));
+                                sink.send(PipelineEvent::RefineChunk {
+                                    chunk: text_chunk.to_string(),
+                                    accumulated: full_text.clone(),
+                                });
                             }
                         }
                     }
@@ -170,12 +171,12 @@ impl AIRefiner for LocalRefiner {
             || refined.contains("Unauthorized")
             || refined.contains("Please run /login")
         {
-            let _ = app.emit("ai-refine-status", json!({ "status": "error" }));
+            sink.send(PipelineEvent::RefineStatus { status: RefineStatus::Error });
             tracing::error!("[local] Auth error in response: '{}'", refined);
             return Err(AppError::AIError(format!("خطأ في المصادقة: {}", refined)));
         }
 
-        let _ = app.emit("ai-refine-status", json!({ "status": "done" }));
+        sink.send(PipelineEvent::RefineStatus { status: RefineStatus::Done });
         tracing::debug!("[local] Refinement complete: '{}'", refined);
         Ok(refined)
     }
@@ -0,0 +1,151 @@
+use crate::audio::recorder::{resample, stereo_to_mono};
+use crate::constants::audio::SAMPLE_RATE;
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["wav", "flac", "ogg", "mp3"];
+
+pub fn is_supported(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| SUPPORTED_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+pub fn decode_file(path: &Path) -> Result<Vec<f32>, anyhow::Error> {
+    if !is_supported(path) {
+        return Err(anyhow::anyhow!(
+            "صيغة الملف غير مدعومة (المدعوم: {})",
+            SUPPORTED_EXTENSIONS.join(", ")
+        ));
+    }
+
+    let file = File::open(path).map_err(|e| anyhow::anyhow!("فشل فتح الملف الصوتي: {}", e))?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| anyhow::anyhow!("فشل التعرف على صيغة الملف: {}", e))?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| anyhow::anyhow!("لا يوجد مسار صوتي في الملف"))?;
+    let track_id = track.id;
+    let source_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| anyhow::anyhow!("معدل العينات غير معروف"))?;
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| anyhow::anyhow!("فشل تهيئة فك الترميز: {}", e))?;
+
+    let mut channels: u16 = track
+        .codec_params
+        .channels
+        .map(|c| c.count() as u16)
+        .unwrap_or(1);
+    let mut interleaved: Vec<f32> = Vec::new();
+    let mut sample_buf: Option<SampleBuffer<f32>> = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(p) => p,
+            Err(SymphoniaError::IoError(ref e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(anyhow::anyhow!("فشل قراءة الملف الصوتي: {}", e)),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(d) => d,
+            Err(SymphoniaError::DecodeError(e)) => {
+                tracing::warn!("[decoder] Skipping corrupt packet: {}", e);
+                continue;
+            }
+            Err(e) => return Err(anyhow::anyhow!("فشل فك ترميز الصوت: {}", e)),
+        };
+
+        if sample_buf.is_none() {
+            let spec = *decoded.spec();
+            channels = spec.channels.count() as u16;
+            sample_buf = Some(SampleBuffer::<f32>::new(decoded.capacity() as u64, spec));
+        }
+
+        if let Some(buf) = sample_buf.as_mut() {
+            buf.copy_interleaved_ref(decoded);
+            interleaved.extend_from_slice(buf.samples());
+        }
+    }
+
+    tracing::info!(
+        "[decoder] Decoded {:?}: {} samples, {}Hz, {}ch",
+        path.file_name().unwrap_or_default(),
+        interleaved.len(),
+        source_rate,
+        channels
+    );
+
+    let mono = if channels > 1 {
+        stereo_to_mono(&interleaved, channels)
+    } else {
+        interleaved
+    };
+
+    Ok(resample(&mono, source_rate, SAMPLE_RATE))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PcmFormat {
+    S16Le,
+    F32Le,
+}
+
+impl PcmFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "s16le" | "s16" | "i16" => Some(PcmFormat::S16Le),
+            "f32le" | "f32" => Some(PcmFormat::F32Le),
+            _ => None,
+        }
+    }
+}
+
+pub fn decode_pcm(bytes: &[u8], format: PcmFormat, sample_rate: u32, channels: u16) -> Vec<f32> {
+    let interleaved: Vec<f32> = match format {
+        PcmFormat::S16Le => bytes
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+            .collect(),
+        PcmFormat::F32Le => bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+    };
+
+    let mono = if channels > 1 {
+        stereo_to_mono(&interleaved, channels)
+    } else {
+        interleaved
+    };
+
+    resample(&mono, sample_rate, SAMPLE_RATE)
+}
@@ -2,3 +2,4 @@ pub mod recorder;
 pub mod preprocessor;
 pub mod vad;
 pub mod noise_suppressor;
+pub mod decoder;
@@ -0,0 +1,273 @@
+use kateb_lib::ai::{AIFactory, AIProvider};
+use kateb_lib::audio::decoder::{self, PcmFormat};
+use kateb_lib::commands::voice_commands::VoiceCommandProcessor;
+use kateb_lib::constants::audio::SAMPLE_RATE;
+use kateb_lib::events::{EventSink, NullSink, PipelineEvent};
+use kateb_lib::pipeline::file::transcribe_samples;
+use kateb_lib::pipeline::postprocess::post_process;
+use kateb_lib::pipeline::refine::refine_with_retries;
+use kateb_lib::pipeline::vocabulary::Glossary;
+use kateb_lib::whisper::transcriber::{vocabulary_budget, Decoding, WhisperTranscriber, DEFAULT_BEAM_SIZE};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing_subscriber::EnvFilter;
+
+const USAGE: &str = "\
+kateb-cli - transcribe audio with the Kateb dictation pipeline
+
+USAGE:
+    kateb-cli [OPTIONS] --model <PATH> <INPUT>
+
+INPUT:
+    Path to a WAV/FLAC/OGG/MP3 file, or '-' to read raw PCM from stdin
+
+OPTIONS:
+    -m, --model <PATH>          Whisper ggml model file (or KATEB_MODEL)
+    -l, --language <LANG>       Transcription language: ar, en [default: ar]
+        --gpu                   Load the model on the GPU
+        --noise-suppression     Run noise suppression before transcription
+        --no-voice-commands     Keep spoken commands as plain text
+        --vocabulary <TEXT>     Comma-separated terms added to the Whisper prompt
+        --decoding <PROFILE>    Whisper decoding: auto, fast, accurate, fallback [default: fallback]
+        --beam-size <N>         Beams for --decoding accurate [default: 5]
+        --refine <PROVIDER>     Refine with AI: local, claude, openai, gemini, grok
+        --api-key <KEY>         API key for the refine provider (or KATEB_API_KEY)
+        --api-url <URL>         Base URL override for the refine provider
+        --pcm-format <FMT>      Stdin sample format: s16le, f32le [default: s16le]
+        --sample-rate <HZ>      Stdin sample rate [default: 16000]
+        --channels <N>          Stdin channel count [default: 1]
+        --json                  Print text, raw text and segments as JSON
+    -q, --quiet                 Do not print progress events to stderr
+    -h, --help                  Print this help
+";
+
+struct StderrSink;
+
+impl EventSink for StderrSink {
+    fn send(&self, event: PipelineEvent) {
+        eprintln!(
+            "{} {}",
+            event.name(),
+            serde_json::to_string(&event).unwrap_or_default()
+        );
+    }
+}
+
+struct Options {
+    input: String,
+    model: PathBuf,
+    language: String,
+    use_gpu: bool,
+    noise_suppression: bool,
+    voice_commands: bool,
+    vocabulary: String,
+    decoding: Option<Decoding>,
+    refine: Option<String>,
+    api_key: Option<String>,
+    api_url: Option<String>,
+    pcm_format: PcmFormat,
+    sample_rate: u32,
+    channels: u16,
+    json: bool,
+    quiet: bool,
+}
+
+fn parse_args(args: Vec<String>) -> Result<Options, String> {
+    let mut input: Option<String> = None;
+    let mut model = std::env::var("KATEB_MODEL").ok().map(PathBuf::from);
+    let mut language = "ar".to_string();
+    let mut use_gpu = false;
+    let mut noise_suppression = false;
+    let mut voice_commands = true;
+    let mut vocabulary = String::new();
+    let mut decoding = "fallback".to_string();
+    let mut beam_size = DEFAULT_BEAM_SIZE;
+    let mut refine = None;
+    let mut api_key = std::env::var("KATEB_API_KEY").ok().filter(|k| !k.is_empty());
+    let mut api_url = None;
+    let mut pcm_format = PcmFormat::S16Le;
+    let mut sample_rate = SAMPLE_RATE;
+    let mut channels: u16 = 1;
+    let mut json = false;
+    let mut quiet = false;
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| iter.next().ok_or_else(|| format!("{} requires a value", name));
+        match arg.as_str() {
+            "-m" | "--model" => model = Some(PathBuf::from(value(&arg)?)),
+            "-l" | "--language" => language = value(&arg)?,
+            "--gpu" => use_gpu = true,
+            "--noise-suppression" => noise_suppression = true,
+            "--no-voice-commands" => voice_commands = false,
+            "--vocabulary" => vocabulary = value(&arg)?,
+            "--decoding" => decoding = value(&arg)?,
+            "--beam-size" => {
+                beam_size = value(&arg)?.parse().map_err(|_| "invalid --beam-size".to_string())?;
+            }
+            "--refine" => refine = Some(value(&arg)?),
+            "--api-key" => api_key = Some(value(&arg)?),
+            "--api-url" => api_url = Some(value(&arg)?),
+            "--pcm-format" => {
+                let v = value(&arg)?;
+                pcm_format = PcmFormat::parse(&v).ok_or_else(|| format!("unknown PCM format '{}'", v))?;
+            }
+            "--sample-rate" => {
+                sample_rate = value(&arg)?.parse().map_err(|_| "invalid --sample-rate".to_string())?;
+            }
+            "--channels" => {
+                channels = value(&arg)?.parse().map_err(|_| "invalid --channels".to_string())?;
+            }
+            "--json" => json = true,
+            "-q" | "--quiet" => quiet = true,
+            _ if arg.starts_with('-') && arg != "-" => return Err(format!("unknown option '{}'", arg)),
+            _ => {
+                if input.is_some() {
+                    return Err(format!("unexpected argument '{}'", arg));
+                }
+                input = Some(arg);
+            }
+        }
+    }
+
+    Ok(Options {
+        input: input.ok_or("missing INPUT")?,
+        model: model.ok_or("missing --model (or KATEB_MODEL)")?,
+        language,
+        use_gpu,
+        noise_suppression,
+        voice_commands,
+        vocabulary,
+        decoding: Decoding::parse(&decoding, beam_size.max(1))
+            .map_err(|_| format!("unknown decoding profile '{}'", decoding))?,
+        refine,
+        api_key,
+        api_url,
+        pcm_format,
+        sample_rate,
+        channels: channels.max(1),
+        json,
+        quiet,
+    })
+}
+
+fn read_input(opts: &Options) -> Result<Vec<f32>, String> {
+    if opts.input == "-" {
+        let mut bytes = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("failed to read stdin: {}", e))?;
+        Ok(decoder::decode_pcm(&bytes, opts.pcm_format, opts.sample_rate, opts.channels))
+    } else {
+        decoder::decode_file(Path::new(&opts.input)).map_err(|e| e.to_string())
+    }
+}
+
+fn run(opts: Options) -> Result<(), String> {
+    let samples = read_input(&opts)?;
+    if samples.is_empty() {
+        return Err("input contains no audio".to_string());
+    }
+
+    let mut transcriber = WhisperTranscriber::new();
+    transcriber
+        .load_model(&opts.model, opts.use_gpu)
+        .map_err(|e| e.to_string())?;
+    transcriber.set_language(&opts.language);
+    transcriber.set_decoding(opts.decoding);
+    let mut glossary = Glossary::new();
+    glossary.set_language(&opts.language);
+    glossary.set_extra_terms(&opts.vocabulary);
+    transcriber.set_vocabulary_prompt(&glossary.prompt(vocabulary_budget(&opts.language)));
+    let transcriber = Mutex::new(transcriber);
+
+    let sink: Box<dyn EventSink> = if opts.quiet {
+        Box::new(NullSink)
+    } else {
+        Box::new(StderrSink)
+    };
+
+    let mut segments = transcribe_samples(
+        &transcriber,
+        &samples,
+        opts.noise_suppression,
+        &opts.input,
+        sink.as_ref(),
+    )?;
+
+    let processor = opts.voice_commands.then(|| {
+        let mut processor = VoiceCommandProcessor::new(true);
+        processor.set_language(&opts.language);
+        processor
+    });
+    let text = post_process(&mut segments, &glossary, processor.as_ref(), &opts.language);
+
+    let raw_text = text.clone();
+    let text = match opts.refine.as_deref() {
+        Some(provider) if !text.trim().is_empty() => {
+            let refiner = AIFactory::create(
+                AIProvider::from_str(provider),
+                opts.api_key.clone(),
+                opts.api_url.clone(),
+            )
+            .map_err(|e| e.to_string())?;
+            let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+            runtime.block_on(refine_with_retries(
+                refiner.as_ref(),
+                &text,
+                &opts.language,
+                "",
+                sink.as_ref(),
+            ))
+        }
+        _ => text,
+    };
+
+    if opts.json {
+        let output = serde_json::json!({
+            "text": text,
+            "raw_text": raw_text,
+            "language": opts.language,
+            "duration": samples.len() as f64 / SAMPLE_RATE as f64,
+            "segments": segments
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&output).map_err(|e| e.to_string())?
+        );
+    } else {
+        println!("{}", text);
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        return;
+    }
+
+    let opts = match parse_args(args) {
+        Ok(o) => o,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let default_filter = if opts.quiet { "error" } else { "warn" };
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter)),
+        )
+        .init();
+
+    if let Err(e) = run(opts) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
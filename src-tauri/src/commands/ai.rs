@@ -1,12 +1,12 @@
 use crate::ai::{AIFactory, AIProvider};
-use crate::db::Database;
+use crate::settings::SettingsState;
 use tauri::State;
 
 #[tauri::command]
 pub async fn test_ai_connection(
-    db: State<'_, Database>,
+    settings: State<'_, SettingsState>,
 ) -> Result<serde_json::Value, String> {
-    let refiner = AIFactory::create_from_settings(&db).map_err(|e| e.to_string())?;
+    let refiner = AIFactory::create_from_settings(&settings.get()).map_err(|e| e.to_string())?;
     let provider_name = refiner.provider_name();
 
     match refiner.test_connection().await {
@@ -89,27 +89,11 @@ pub fn detect_gpu() -> serde_json::Value {
 }
 
 #[tauri::command]
-pub fn get_current_ai_provider(db: State<'_, Database>) -> Result<serde_json::Value, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-
-    let provider = conn
-        .query_row(
-            "SELECT value FROM settings WHERE key = 'ai_provider'",
-            [],
-            |row| row.get::<_, String>(0),
-        )
-        .unwrap_or_else(|_| "local".to_string());
-
-    let ai_enabled = conn
-        .query_row(
-            "SELECT value FROM settings WHERE key = 'ai_refinement'",
-            [],
-            |row| row.get::<_, String>(0),
-        )
-        .unwrap_or_else(|_| "false".to_string());
+pub fn get_current_ai_provider(settings: State<'_, SettingsState>) -> Result<serde_json::Value, String> {
+    let settings = settings.get();
 
     Ok(serde_json::json!({
-        "provider": provider,
-        "enabled": ai_enabled == "true"
+        "provider": settings.ai_provider,
+        "enabled": settings.ai_refinement
     }))
 }
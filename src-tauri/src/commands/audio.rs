@@ -1,6 +1,6 @@
 use crate::audio::recorder::AudioRecorder;
-use crate::commands::dictation::DictationState;
 use crate::db::Database;
+use crate::settings::SettingsState;
 use tauri::State;
 
 #[tauri::command]
@@ -20,21 +20,15 @@ pub fn get_audio_devices() -> Result<serde_json::Value, String> {
 
 #[tauri::command]
 pub fn set_audio_device(
-    state: State<'_, DictationState>,
     db: State<'_, Database>,
+    settings: State<'_, SettingsState>,
     device_name: Option<String>,
 ) -> Result<(), String> {
-    let recorder = state.recorder.lock().map_err(|e| e.to_string())?;
-    recorder.set_device(device_name.clone());
-
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
     let value = device_name.unwrap_or_default();
-    conn.execute(
-        "INSERT INTO settings (key, value) VALUES ('audio_device', ?1)
-         ON CONFLICT(key) DO UPDATE SET value = ?1",
-        rusqlite::params![&value],
-    )
-    .map_err(|e| e.to_string())?;
+    let update = settings.set(&db, "audio_device", &value)?;
+    if let Some(message) = update.failure_message() {
+        return Err(message);
+    }
 
     tracing::info!("[audio] Device set to: '{}'", if value.is_empty() { "default" } else { &value });
     Ok(())
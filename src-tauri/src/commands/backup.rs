@@ -1,7 +1,31 @@
+use crate::commands::dictation::DictationState;
+use crate::commands::history::load_segments;
+use crate::commands::settings::on_blocking_pool;
+use crate::commands::{vocabulary, voice_commands};
+use crate::constants::model::{self, AVAILABLE_MODELS};
+use crate::db::models::{AppProfile, DictationEntry, DictationSegment, UsageStat, VocabularyTerm, VoiceCommand};
 use crate::db::Database;
+use crate::db::{migrations, search};
+use crate::models::ModelDownloader;
+use crate::pipeline::corrections;
 use crate::security::keychain;
+use crate::settings::{profiles, schema};
+use crate::settings::{SettingFailure, SettingsState};
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
-use tauri::State;
+use tauri::{Manager, State};
+
+const BACKUP_FORMAT: &str = "kateb-backup";
+const BACKUP_FORMAT_VERSION: u32 = 1;
+
+fn write_setting(conn: &rusqlite::Connection, key: &str, value: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, datetime('now'))
+         ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = datetime('now')",
+        [key, value],
+    )?;
+    Ok(())
+}
 
 #[tauri::command]
 pub fn export_settings(db: State<'_, Database>) -> Result<String, String> {
@@ -29,7 +53,11 @@ pub fn export_settings(db: State<'_, Database>) -> Result<String, String> {
 }
 
 #[tauri::command]
-pub fn import_settings(db: State<'_, Database>, json: String) -> Result<u32, String> {
+pub async fn import_settings(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+    json: String,
+) -> Result<u32, String> {
     let parsed: Map<String, Value> =
         serde_json::from_str(&json).map_err(|e| format!("Invalid JSON: {}", e))?;
 
@@ -41,22 +69,543 @@ pub fn import_settings(db: State<'_, Database>, json: String) -> Result<u32, Str
             continue;
         }
 
-        let val_str = match value {
-            Value::String(s) => s.clone(),
-            Value::Bool(b) => b.to_string(),
-            Value::Number(n) => n.to_string(),
-            _ => continue,
+        let val_str = match schema::validate_json(key, value) {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!("[backup] Skipping setting: {}", e);
+                continue;
+            }
         };
 
-        conn.execute(
-            "INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, datetime('now'))
-             ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = datetime('now')",
-            [&key, &val_str],
-        )
-        .map_err(|e| e.to_string())?;
-
+        write_setting(&conn, key, &val_str).map_err(|e| e.to_string())?;
         count += 1;
     }
+    drop(conn);
 
+    on_blocking_pool(&app, |app| app.state::<SettingsState>().reload(&app.state::<Database>())).await?;
     Ok(count)
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupManifest {
+    format: String,
+    format_version: u32,
+    app_version: String,
+    schema_version: i64,
+    created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupHistoryEntry {
+    #[serde(flatten)]
+    entry: DictationEntry,
+    #[serde(default)]
+    segments: Vec<DictationSegment>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupModelRef {
+    id: String,
+    filename: String,
+    size_bytes: u64,
+    active: bool,
+}
+
+// heard_key is left out and recomputed on restore.
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupCorrection {
+    heard: String,
+    corrected: String,
+    language: String,
+    count: i64,
+    status: String,
+    last_seen_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupArchive {
+    manifest: BackupManifest,
+    settings: Map<String, Value>,
+    #[serde(default)]
+    history: Vec<BackupHistoryEntry>,
+    #[serde(default)]
+    usage_stats: Vec<UsageStat>,
+    // None in archives made before profiles were backed up, so replacing keeps the current ones.
+    #[serde(default)]
+    app_profiles: Option<Vec<AppProfile>>,
+    #[serde(default)]
+    voice_commands: Option<Vec<VoiceCommand>>,
+    #[serde(default)]
+    vocabulary: Option<Vec<VocabularyTerm>>,
+    #[serde(default)]
+    correction_candidates: Option<Vec<BackupCorrection>>,
+    #[serde(default)]
+    models: Vec<BackupModelRef>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RestoreMode {
+    Merge,
+    Replace,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct RestoreSummary {
+    pub settings_restored: u32,
+    pub settings_skipped: Vec<String>,
+    pub history_restored: u32,
+    pub history_skipped: u32,
+    pub usage_stats_restored: u32,
+    pub profiles_restored: u32,
+    pub profiles_skipped: u32,
+    pub voice_commands_restored: u32,
+    pub voice_commands_skipped: u32,
+    pub vocabulary_restored: u32,
+    pub vocabulary_skipped: u32,
+    pub corrections_restored: u32,
+    pub missing_models: Vec<String>,
+    pub apply_failures: Vec<SettingFailure>,
+}
+
+fn read_history(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<BackupHistoryEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, text, raw_text, duration, language, ai_provider, processing_time_ms, source, created_at, original_text
+         FROM dictation_history
+         ORDER BY id ASC",
+    )?;
+    let entries: Vec<DictationEntry> = stmt
+        .query_map([], |row| {
+            Ok(DictationEntry {
+                id: row.get(0)?,
+                text: row.get(1)?,
+                raw_text: row.get(2)?,
+                duration: row.get(3)?,
+                language: row.get(4)?,
+                ai_provider: row.get(5)?,
+                processing_time_ms: row.get(6)?,
+                source: row.get(7)?,
+                created_at: row.get(8)?,
+                original_text: row.get(9)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let segments = load_segments(conn, entry.id)?;
+            Ok(BackupHistoryEntry { entry, segments })
+        })
+        .collect()
+}
+
+fn read_usage_stats(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<UsageStat>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, date, total_dictations, total_words, total_duration
+         FROM usage_stats
+         ORDER BY date ASC",
+    )?;
+    let stats = stmt
+        .query_map([], |row| {
+            Ok(UsageStat {
+                id: row.get(0)?,
+                date: row.get(1)?,
+                total_dictations: row.get(2)?,
+                total_words: row.get(3)?,
+                total_duration: row.get(4)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(stats)
+}
+
+fn read_corrections(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<BackupCorrection>> {
+    let mut stmt = conn.prepare(
+        "SELECT heard, corrected, language, count, status, last_seen_at
+         FROM correction_candidates
+         ORDER BY id ASC",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(BackupCorrection {
+            heard: row.get(0)?,
+            corrected: row.get(1)?,
+            language: row.get(2)?,
+            count: row.get(3)?,
+            status: row.get(4)?,
+            last_seen_at: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn installed_model_refs(app: &tauri::AppHandle, active_model: &str) -> Vec<BackupModelRef> {
+    let Ok(model_dir) = ModelDownloader::get_model_dir(app) else {
+        return Vec::new();
+    };
+    AVAILABLE_MODELS
+        .iter()
+        .filter(|info| model_dir.join(info.filename).exists())
+        .map(|info| BackupModelRef {
+            id: info.id.to_string(),
+            filename: info.filename.to_string(),
+            size_bytes: info.size_bytes,
+            active: info.id == active_model,
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn export_backup(
+    db: State<'_, Database>,
+    app: tauri::AppHandle,
+    include_models: Option<bool>,
+) -> Result<String, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+
+    let mut settings = Map::new();
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM settings ORDER BY key")
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<(String, String)> = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    for (key, value) in rows {
+        if !keychain::is_api_key_setting(&key) {
+            settings.insert(key, Value::String(value));
+        }
+    }
+
+    let active_model = settings
+        .get("active_model")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let archive = BackupArchive {
+        manifest: BackupManifest {
+            format: BACKUP_FORMAT.to_string(),
+            format_version: BACKUP_FORMAT_VERSION,
+            app_version: app.package_info().version.to_string(),
+            schema_version: migrations::schema_version(&conn).map_err(|e| e.to_string())?,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        },
+        settings,
+        history: read_history(&conn).map_err(|e| e.to_string())?,
+        usage_stats: read_usage_stats(&conn).map_err(|e| e.to_string())?,
+        app_profiles: Some(profiles::load(&conn).map_err(|e| e.to_string())?),
+        voice_commands: Some(voice_commands::load(&conn).map_err(|e| e.to_string())?),
+        vocabulary: Some(vocabulary::load(&conn).map_err(|e| e.to_string())?),
+        correction_candidates: Some(read_corrections(&conn).map_err(|e| e.to_string())?),
+        models: if include_models.unwrap_or(false) {
+            installed_model_refs(&app, &active_model)
+        } else {
+            Vec::new()
+        },
+    };
+
+    tracing::info!(
+        "[backup] Exported {} settings, {} history entries, {} stat days, {} profiles, {} voice commands, {} glossary terms, {} model refs",
+        archive.settings.len(),
+        archive.history.len(),
+        archive.usage_stats.len(),
+        archive.app_profiles.as_ref().map_or(0, |p| p.len()),
+        archive.voice_commands.as_ref().map_or(0, |c| c.len()),
+        archive.vocabulary.as_ref().map_or(0, |v| v.len()),
+        archive.models.len()
+    );
+
+    serde_json::to_string_pretty(&archive).map_err(|e| e.to_string())
+}
+
+fn restore_history(
+    tx: &rusqlite::Transaction,
+    history: &[BackupHistoryEntry],
+    mode: RestoreMode,
+    summary: &mut RestoreSummary,
+) -> rusqlite::Result<()> {
+    for item in history {
+        let e = &item.entry;
+        if mode == RestoreMode::Merge {
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM dictation_history WHERE created_at = ?1 AND text = ?2)",
+                rusqlite::params![e.created_at, e.text],
+                |row| row.get(0),
+            )?;
+            if exists {
+                summary.history_skipped += 1;
+                continue;
+            }
+            tx.execute(
+                "INSERT INTO dictation_history (text, raw_text, duration, language, ai_provider, processing_time_ms, source, created_at, original_text)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                rusqlite::params![e.text, e.raw_text, e.duration, e.language, e.ai_provider, e.processing_time_ms, e.source, e.created_at, e.original_text],
+            )?;
+        } else {
+            tx.execute(
+                "INSERT INTO dictation_history (id, text, raw_text, duration, language, ai_provider, processing_time_ms, source, created_at, original_text)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                rusqlite::params![e.id, e.text, e.raw_text, e.duration, e.language, e.ai_provider, e.processing_time_ms, e.source, e.created_at, e.original_text],
+            )?;
+        }
+
+        let history_id = tx.last_insert_rowid();
+        search::index_history(tx, history_id, &e.text, &e.raw_text)?;
+        for seg in &item.segments {
+            tx.execute(
+                "INSERT INTO dictation_segments (history_id, segment_index, start_ms, end_ms, text)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![history_id, seg.segment_index, seg.start_ms, seg.end_ms, seg.text],
+            )?;
+        }
+        summary.history_restored += 1;
+    }
+    Ok(())
+}
+
+// Merging keeps the larger daily counters so restoring the same backup twice is harmless.
+fn restore_usage_stats(
+    tx: &rusqlite::Transaction,
+    stats: &[UsageStat],
+    summary: &mut RestoreSummary,
+) -> rusqlite::Result<()> {
+    for stat in stats {
+        tx.execute(
+            "INSERT INTO usage_stats (date, total_dictations, total_words, total_duration)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(date) DO UPDATE SET
+                total_dictations = MAX(total_dictations, excluded.total_dictations),
+                total_words = MAX(total_words, excluded.total_words),
+                total_duration = MAX(total_duration, excluded.total_duration)",
+            rusqlite::params![stat.date, stat.total_dictations, stat.total_words, stat.total_duration],
+        )?;
+        summary.usage_stats_restored += 1;
+    }
+    Ok(())
+}
+
+// Profiles get new ids; merging skips one that matches the same window as an existing profile.
+fn restore_profiles(
+    tx: &rusqlite::Transaction,
+    backup: &[AppProfile],
+    mode: RestoreMode,
+    summary: &mut RestoreSummary,
+) -> rusqlite::Result<()> {
+    for profile in backup {
+        if let Err(e) = profiles::validate(profile) {
+            tracing::warn!("[backup] Skipping profile '{}': {}", profile.name, e);
+            summary.profiles_skipped += 1;
+            continue;
+        }
+        if mode == RestoreMode::Merge {
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM app_profiles WHERE name = ?1 AND match_class = ?2 AND match_title = ?3)",
+                rusqlite::params![profile.name.trim(), profile.match_class.trim(), profile.match_title.trim()],
+                |row| row.get(0),
+            )?;
+            if exists {
+                summary.profiles_skipped += 1;
+                continue;
+            }
+        }
+        profiles::save(tx, &AppProfile { id: 0, ..profile.clone() })?;
+        summary.profiles_restored += 1;
+    }
+    Ok(())
+}
+
+// Terms that fail validation, including merged ones whose spellings are already taken, are skipped.
+fn restore_vocabulary(
+    tx: &rusqlite::Transaction,
+    backup: &[VocabularyTerm],
+    summary: &mut RestoreSummary,
+) -> rusqlite::Result<()> {
+    let mut existing = vocabulary::load(tx)?;
+    for term in backup {
+        let term = VocabularyTerm { id: 0, ..term.clone() };
+        let duplicate = existing.iter().any(|e| {
+            search::normalize_arabic(e.term.trim()) == search::normalize_arabic(term.term.trim()) && e.language == term.language
+        });
+        if duplicate {
+            summary.vocabulary_skipped += 1;
+            continue;
+        }
+        if let Err(e) = vocabulary::validate(&term, &existing) {
+            tracing::warn!("[backup] Skipping glossary term '{}': {}", term.term, e);
+            summary.vocabulary_skipped += 1;
+            continue;
+        }
+        let id = vocabulary::save(tx, &term)?;
+        tx.execute("UPDATE vocabulary SET hits = ?1 WHERE id = ?2", rusqlite::params![term.hits, id])?;
+        existing.push(VocabularyTerm { id, ..term });
+        summary.vocabulary_restored += 1;
+    }
+    Ok(())
+}
+
+// Merging keeps the higher count and a decision already made on this machine.
+fn restore_corrections(
+    tx: &rusqlite::Transaction,
+    backup: &[BackupCorrection],
+    summary: &mut RestoreSummary,
+) -> rusqlite::Result<()> {
+    for c in backup {
+        tx.execute(
+            "INSERT INTO correction_candidates (heard, heard_key, corrected, language, count, status, last_seen_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(heard_key, corrected, language) DO UPDATE SET
+                count = MAX(count, excluded.count),
+                status = CASE WHEN status = 'pending' THEN excluded.status ELSE status END,
+                last_seen_at = MAX(last_seen_at, excluded.last_seen_at)",
+            rusqlite::params![
+                c.heard,
+                corrections::fold(&c.heard),
+                c.corrected,
+                c.language,
+                c.count,
+                c.status,
+                c.last_seen_at
+            ],
+        )?;
+        summary.corrections_restored += 1;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn import_backup(
+    db: State<'_, Database>,
+    settings: State<'_, SettingsState>,
+    app: tauri::AppHandle,
+    json: String,
+    mode: String,
+) -> Result<RestoreSummary, String> {
+    let mode = match mode.as_str() {
+        "merge" => RestoreMode::Merge,
+        "replace" => RestoreMode::Replace,
+        other => return Err(format!("وضع الاستعادة غير معروف: {}", other)),
+    };
+
+    let archive: BackupArchive =
+        serde_json::from_str(&json).map_err(|e| format!("ملف النسخة الاحتياطية غير صالح: {}", e))?;
+    if archive.manifest.format != BACKUP_FORMAT
+        || archive.manifest.format_version > BACKUP_FORMAT_VERSION
+    {
+        return Err("صيغة النسخة الاحتياطية غير مدعومة".to_string());
+    }
+
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let schema_version = migrations::schema_version(&conn).map_err(|e| e.to_string())?;
+    if archive.manifest.schema_version > schema_version {
+        return Err(format!(
+            "النسخة الاحتياطية من إصدار أحدث ({})، يرجى تحديث التطبيق أولاً",
+            archive.manifest.app_version
+        ));
+    }
+
+    let model_dir = ModelDownloader::get_model_dir(&app).ok();
+    let model_installed = |id: &str| {
+        model::find_model(id)
+            .zip(model_dir.as_ref())
+            .map(|(info, dir)| dir.join(info.filename).exists())
+            .unwrap_or(false)
+    };
+
+    let mut summary = RestoreSummary {
+        missing_models: archive
+            .models
+            .iter()
+            .filter(|m| !model_installed(&m.id))
+            .map(|m| m.id.clone())
+            .collect(),
+        ..Default::default()
+    };
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    if mode == RestoreMode::Replace {
+        tx.execute_batch("DELETE FROM dictation_history; DELETE FROM usage_stats;")
+            .map_err(|e| e.to_string())?;
+        if archive.app_profiles.is_some() {
+            tx.execute("DELETE FROM app_profiles", []).map_err(|e| e.to_string())?;
+        }
+        if archive.vocabulary.is_some() {
+            tx.execute("DELETE FROM vocabulary", []).map_err(|e| e.to_string())?;
+        }
+        if archive.correction_candidates.is_some() {
+            tx.execute("DELETE FROM correction_candidates", []).map_err(|e| e.to_string())?;
+        }
+    }
+
+    for (key, value) in &archive.settings {
+        if keychain::is_api_key_setting(key) {
+            continue;
+        }
+        // Pointing at a model that is not on this machine would skip first-run setup.
+        if key == "active_model" && value.as_str().is_some_and(|id| !id.is_empty() && !model_installed(id)) {
+            summary.settings_skipped.push(key.clone());
+            continue;
+        }
+        match schema::validate_json(key, value) {
+            Ok(v) => {
+                write_setting(&tx, key, &v).map_err(|e| e.to_string())?;
+                summary.settings_restored += 1;
+            }
+            Err(e) => {
+                tracing::warn!("[backup] Skipping setting: {}", e);
+                summary.settings_skipped.push(key.clone());
+            }
+        }
+    }
+
+    restore_history(&tx, &archive.history, mode, &mut summary).map_err(|e| e.to_string())?;
+    restore_usage_stats(&tx, &archive.usage_stats, &mut summary).map_err(|e| e.to_string())?;
+    if let Some(backup) = &archive.app_profiles {
+        restore_profiles(&tx, backup, mode, &mut summary).map_err(|e| e.to_string())?;
+    }
+    if let Some(backup) = &archive.vocabulary {
+        restore_vocabulary(&tx, backup, &mut summary).map_err(|e| e.to_string())?;
+    }
+    if let Some(backup) = &archive.correction_candidates {
+        restore_corrections(&tx, backup, &mut summary).map_err(|e| e.to_string())?;
+    }
+    // Merging skips commands whose triggers are already taken, so restoring twice adds nothing.
+    if let Some(backup) = archive.voice_commands {
+        let result = voice_commands::import_commands(&tx, backup, mode == RestoreMode::Replace)?;
+        summary.voice_commands_restored = result.imported;
+        summary.voice_commands_skipped = result.skipped;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    let state = app.state::<DictationState>();
+    voice_commands::reload(&conn, &state)?;
+    vocabulary::reload(&conn, &state, &settings)?;
+    drop(conn);
+
+    summary.apply_failures = on_blocking_pool(&app, |app| {
+        app.state::<SettingsState>().reload(&app.state::<Database>())
+    })
+    .await?
+    .failures;
+
+    tracing::info!(
+        "[backup] Restored ({:?}): {} settings, {} history ({} skipped), {} stat days, {} profiles ({} skipped), {} voice commands ({} skipped), {} glossary terms ({} skipped), {} missing models",
+        mode,
+        summary.settings_restored,
+        summary.history_restored,
+        summary.history_skipped,
+        summary.usage_stats_restored,
+        summary.profiles_restored,
+        summary.profiles_skipped,
+        summary.voice_commands_restored,
+        summary.voice_commands_skipped,
+        summary.vocabulary_restored,
+        summary.vocabulary_skipped,
+        summary.missing_models.len()
+    );
+
+    Ok(summary)
+}
@@ -3,14 +3,26 @@ use crate::audio::noise_suppressor::NoiseSuppressor;
 use crate::audio::preprocessor::AudioPreprocessor;
 use crate::audio::recorder::AudioRecorder;
 use crate::audio::vad::AdaptiveVAD;
+use crate::commands::insertion::{self, LastInsertion};
+use crate::commands::vocabulary;
 use crate::commands::voice_commands::VoiceCommandProcessor;
-use crate::constants::{audio::*, hallucination::*};
-use crate::db::Database;
-use crate::keyboard::simulator::KeyboardSimulator;
-use crate::whisper::transcriber::WhisperTranscriber;
+use crate::constants::audio::*;
+use crate::db::models::AppProfile;
+use crate::db::{search, Database};
+use crate::events::{EventSink, PipelineEvent, RefineStatus};
+use crate::keyboard::live::LiveInsertion;
+use crate::pipeline::file::segments_text;
+use crate::pipeline::hallucination::is_chunk_hallucination;
+use crate::pipeline::postprocess::post_process;
+use crate::pipeline::refine::refine_with_retries;
+use crate::pipeline::streaming::{context_tail, find_overlap, overlap_word_limit, replace_tail};
+use crate::pipeline::vocabulary::Glossary;
+use crate::settings::{profiles, Settings, SettingsState};
+use crate::whisper::transcriber::{TranscriptSegment, WhisperTranscriber, CONTEXT_TOKEN_BUDGET};
+use crate::window::ActiveWindowProvider;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
-use tauri::{Emitter, Manager, State};
+use tauri::{Manager, State};
 
 pub fn show_overlay_window(app: &tauri::AppHandle) {
     if let Some(main_window) = app.get_webview_window("main") {
@@ -53,106 +65,198 @@ pub struct DictationState {
     pub transcriber: Mutex<WhisperTranscriber>,
     pub is_recording: Mutex<bool>,
     pub is_processing: Mutex<bool>,
+    // Set while a file transcription holds the transcriber; claimed under the is_recording lock.
+    pub is_transcribing: AtomicBool,
     pub streaming_active: Arc<AtomicBool>,
     pub streaming_thread: Mutex<Option<std::thread::JoinHandle<()>>>,
-    pub accumulated_text: Arc<Mutex<Vec<String>>>,
+    pub accumulated_text: Arc<Mutex<Vec<TranscriptSegment>>>,
     pub vad: Arc<Mutex<AdaptiveVAD>>,
     pub last_processed_pos: Arc<Mutex<usize>>,
     pub noise_suppressor: Mutex<NoiseSuppressor>,
     pub voice_commands: Mutex<VoiceCommandProcessor>,
+    pub vocabulary: Mutex<Glossary>,
+    pub auto_stop: Mutex<AutoStopConfig>,
+    pub window: Box<dyn ActiveWindowProvider>,
+    // Settings with the matched app profile applied; None when no profile matched.
+    pub session: Mutex<Option<Arc<Settings>>>,
+    pub live: Mutex<Option<LiveInsertion>>,
+    pub last_insertion: Mutex<Option<LastInsertion>>,
 }
 
-fn clean_trailing_hallucinations(text: &str) -> String {
-    let mut result = text.trim().to_string();
-    let mut changed = true;
+#[derive(Debug, Clone, Copy)]
+pub struct AutoStopConfig {
+    pub enabled: bool,
+    pub seconds: f32,
+}
 
-    while changed {
-        changed = false;
-        let trimmed = result.trim_end();
-        let cleaned = trimmed
-            .trim_end_matches(|c: char| matches!(c, '.' | '،' | '؟' | '!' | '؛'))
-            .trim_end();
+fn emit_status(sink: &dyn EventSink, is_recording: bool, is_processing: bool) {
+    sink.send(PipelineEvent::DictationStatus {
+        is_recording,
+        is_processing,
+    });
+}
 
-        for pattern in CONTAINS_PATTERNS.iter().chain(EXACT_PATTERNS.iter()) {
-            if cleaned.ends_with(pattern) {
-                let prefix = &cleaned[..cleaned.len() - pattern.len()];
-                if prefix.is_empty() || prefix.ends_with(' ') || prefix.ends_with('\n') {
-                    result = prefix.trim_end().to_string();
-                    changed = true;
-                    break;
-                }
-            }
+fn reset_processing(state: &DictationState, sink: &dyn EventSink) {
+    let mut is_processing = state.is_processing.lock().unwrap_or_else(|e| e.into_inner());
+    *is_processing = false;
+    emit_status(sink, false, false);
+}
+
+impl AutoStopConfig {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            enabled: settings.auto_stop_silence,
+            seconds: settings.auto_stop_seconds,
         }
     }
+}
 
-    result.trim().to_string()
+// Recorder buffer positions count interleaved samples at the device rate.
+fn buffer_ms(state: &DictationState, pos: usize) -> i64 {
+    let recorder = state.recorder.lock().unwrap();
+    let frames = pos / recorder.get_actual_channels().max(1) as usize;
+    (frames as u64 * 1000 / recorder.get_actual_sample_rate().max(1) as u64) as i64
 }
 
-fn is_chunk_hallucination(text: &str, audio_duration_secs: f32) -> bool {
-    let trimmed = text.trim();
-    if trimmed.is_empty() {
-        return true;
-    }
-    let char_count = trimmed.chars().count();
-    if char_count < 2 {
-        return true;
-    }
-    if audio_duration_secs > 2.0 && char_count < 3 {
-        return true;
-    }
-    for pattern in CONTAINS_PATTERNS {
-        if trimmed.contains(pattern) {
-            return true;
+// What the streaming loop tracks between chunks. It works on chunk text and the accumulated
+// transcript only, so the events it sends don't depend on the recorder or the model.
+struct ChunkStream<'a> {
+    sink: &'a dyn EventSink,
+    chunk_index: u32,
+    // Chunk prompt context only covers text since the last language switch.
+    context_language: String,
+    context_from: usize,
+    // Text, audio length and accumulated entry of the last accepted chunk, while the next chunk's
+    // overlap still covers it.
+    previous_chunk: Option<(String, f32, Option<usize>)>,
+}
+
+impl<'a> ChunkStream<'a> {
+    fn new(sink: &'a dyn EventSink, language: String) -> Self {
+        Self {
+            sink,
+            chunk_index: 0,
+            context_language: language,
+            context_from: 0,
+            previous_chunk: None,
         }
     }
-    for pattern in EXACT_PATTERNS {
-        if trimmed == *pattern {
-            return true;
+
+    fn context(&mut self, accumulated: &[TranscriptSegment], language: String) -> String {
+        if language != self.context_language {
+            tracing::debug!("[streaming] Language switched to {}, resetting chunk context", language);
+            self.context_language = language;
+            self.context_from = accumulated.len();
         }
+        let parts: Vec<String> = accumulated[self.context_from.min(accumulated.len())..]
+            .iter()
+            .map(|s| s.text.clone())
+            .collect();
+        context_tail(&parts, CONTEXT_TOKEN_BUDGET)
     }
-    let chars: Vec<char> = trimmed.chars().collect();
-    if chars.len() > 2 {
-        let first = chars[0];
-        if chars.iter().all(|&c| c == first || c == ' ') {
-            return true;
+
+    // Returns true once the silence is long enough to stop the recording.
+    fn silence(&self, auto_stop: AutoStopConfig, elapsed_secs: f32, silence_secs: f32) -> bool {
+        if !auto_stop.enabled || elapsed_secs <= AUTO_STOP_GRACE_SECS {
+            return false;
+        }
+        self.sink.send(PipelineEvent::SilenceCountdown {
+            remaining: auto_stop.seconds - silence_secs,
+            total: auto_stop.seconds,
+        });
+        if silence_secs < auto_stop.seconds {
+            return false;
         }
+        tracing::info!("[streaming] Auto-stop: {:.1}s silence detected", silence_secs);
+        self.sink.send(PipelineEvent::AutoStop {});
+        true
     }
-    let words: Vec<&str> = trimmed.split_whitespace().collect();
-    if words.len() >= 4 {
-        let first_word = words[0];
-        let repeat_count = words.iter().filter(|&&w| w == first_word).count();
-        if repeat_count as f32 / words.len() as f32 > 0.5 {
-            return true;
+
+    fn speech(&self, auto_stop: AutoStopConfig) {
+        if auto_stop.enabled {
+            self.sink.send(PipelineEvent::SilenceCountdown {
+                remaining: auto_stop.seconds,
+                total: auto_stop.seconds,
+            });
         }
     }
-    false
-}
 
-fn emit_status(app: &tauri::AppHandle, is_recording: bool, is_processing: bool) {
-    let _ = app.emit(
-        "dictation-status",
-        serde_json::json!({
-            "is_recording": is_recording,
-            "is_processing": is_processing
-        }),
-    );
-}
+    // A chunk that isn't accepted leaves nothing for the next chunk's overlap to match.
+    fn skip(&mut self) {
+        self.previous_chunk = None;
+    }
 
-fn reset_processing(state: &DictationState, app: &tauri::AppHandle) {
-    let mut is_processing = state.is_processing.lock().unwrap_or_else(|e| e.into_inner());
-    *is_processing = false;
-    emit_status(app, false, false);
+    // Adds a transcribed chunk to the transcript and sends it as a partial. Returns the text that
+    // is new, i.e. without the words the chunk repeats from the previous one.
+    fn accept(
+        &mut self,
+        accumulated: &Mutex<Vec<TranscriptSegment>>,
+        text: &str,
+        duration_secs: f32,
+        (start_ms, end_ms): (i64, i64),
+        correct: impl FnOnce(&str) -> String,
+    ) -> Option<String> {
+        self.chunk_index += 1;
+        if text.trim().is_empty() || is_chunk_hallucination(text, duration_secs) {
+            self.previous_chunk = None;
+            if !text.trim().is_empty() {
+                tracing::debug!("[streaming] Filtered chunk hallucination: '{}'", text.trim());
+            }
+            return None;
+        }
+
+        tracing::debug!("[streaming] Chunk {} result: '{}'", self.chunk_index, text.trim());
+        let text = correct(text).trim().to_string();
+
+        // The chunk re-decodes the end of the previous one, so drop the words it repeats.
+        let overlap = self.previous_chunk.as_ref().and_then(|(prev, secs, entry)| {
+            find_overlap(prev, &text, overlap_word_limit(prev, *secs)).map(|o| (o, *entry))
+        });
+        let (new_text, entry) = {
+            let mut acc = accumulated.lock().unwrap();
+            let new_text = match overlap {
+                Some((overlap, entry)) => {
+                    tracing::debug!("[streaming] Chunk {} repeats {} word(s) of the previous chunk", self.chunk_index, overlap.words);
+                    if let (Some((count, words)), Some(i)) = (overlap.repair, entry) {
+                        if let Some(repaired) = acc.get(i).and_then(|e| replace_tail(&e.text, count, &words)) {
+                            acc[i].text = repaired;
+                        }
+                    }
+                    overlap.rest
+                }
+                None => text.clone(),
+            };
+            let entry = (!new_text.is_empty()).then(|| {
+                acc.push(TranscriptSegment { start_ms, end_ms, text: new_text.clone() });
+                acc.len() - 1
+            });
+            (new_text, entry)
+        };
+        self.previous_chunk = Some((text, duration_secs, entry));
+        if new_text.is_empty() {
+            return None;
+        }
+
+        self.sink.send(PipelineEvent::Partial {
+            text: new_text.trim().to_string(),
+            chunk_index: self.chunk_index as i64,
+            is_final: false,
+        });
+        Some(new_text)
+    }
 }
 
-fn streaming_transcription_loop(streaming_active: Arc<AtomicBool>, app: tauri::AppHandle) {
+pub fn streaming_transcription_loop(
+    streaming_active: Arc<AtomicBool>,
+    state: &DictationState,
+    sink: &dyn EventSink,
+) {
     let mut local_processed_pos: usize = 0;
     let mut last_vad_pos: usize = 0;
-    let mut chunk_index: u32 = 0;
+    let mut stream = ChunkStream::new(sink, state.transcriber.lock().unwrap().get_language());
 
     tracing::debug!("[streaming] Loop started");
 
-    let state: tauri::State<'_, DictationState> = app.state();
-
     {
         let mut pos = state.last_processed_pos.lock().unwrap();
         *pos = 0;
@@ -165,28 +269,6 @@ fn streaming_transcription_loop(streaming_active: Arc<AtomicBool>, app: tauri::A
 
     let recording_start = std::time::Instant::now();
 
-    let auto_stop_enabled = {
-        let db: tauri::State<'_, Database> = app.state();
-        let conn = db.0.lock().unwrap();
-        let enabled = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'auto_stop_silence'",
-                [],
-                |row| row.get::<_, String>(0),
-            )
-            .unwrap_or_else(|_| "false".to_string());
-        let seconds: f32 = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'auto_stop_seconds'",
-                [],
-                |row| row.get::<_, String>(0),
-            )
-            .unwrap_or_else(|_| "10".to_string())
-            .parse()
-            .unwrap_or(AUTO_STOP_SILENCE_SECS);
-        (enabled == "true", seconds)
-    };
-
     while streaming_active.load(Ordering::SeqCst) {
         std::thread::sleep(std::time::Duration::from_millis(POLL_INTERVAL_MS));
 
@@ -194,12 +276,13 @@ fn streaming_transcription_loop(streaming_active: Arc<AtomicBool>, app: tauri::A
             break;
         }
 
+        let auto_stop = *state.auto_stop.lock().unwrap();
         let (current_len, audio_level) = {
             let recorder = state.recorder.lock().unwrap();
             (recorder.get_buffer_len(), recorder.get_audio_level())
         };
 
-        let _ = app.emit("audio-level", serde_json::json!({ "level": audio_level }));
+        sink.send(PipelineEvent::AudioLevel { level: audio_level });
 
         let new_samples = current_len.saturating_sub(local_processed_pos);
         if new_samples < CHUNK_SAMPLES {
@@ -213,18 +296,8 @@ fn streaming_transcription_loop(streaming_active: Arc<AtomicBool>, app: tauri::A
                 let mut vad = state.vad.lock().unwrap();
                 vad.feed(&recent_audio);
 
-                if auto_stop_enabled.0 && recording_start.elapsed().as_secs_f32() > 5.0 {
-                    let silence_dur = vad.silence_duration_secs();
-                    let remaining = auto_stop_enabled.1 - silence_dur;
-                    let _ = app.emit("silence-countdown", serde_json::json!({
-                        "remaining": remaining,
-                        "total": auto_stop_enabled.1
-                    }));
-                    if silence_dur >= auto_stop_enabled.1 {
-                        tracing::info!("[streaming] Auto-stop: {:.1}s silence detected", silence_dur);
-                        let _ = app.emit("dictation-auto-stop", serde_json::json!({}));
-                        break;
-                    }
+                if stream.silence(auto_stop, recording_start.elapsed().as_secs_f32(), vad.silence_duration_secs()) {
+                    break;
                 }
             }
             continue;
@@ -252,37 +325,22 @@ fn streaming_transcription_loop(streaming_active: Arc<AtomicBool>, app: tauri::A
 
             if !is_speech {
                 tracing::debug!("[streaming] VAD: no speech detected, skipping chunk");
+                stream.skip();
                 local_processed_pos = current_len;
                 *state.last_processed_pos.lock().unwrap() = current_len;
 
-                if auto_stop_enabled.0 && recording_start.elapsed().as_secs_f32() > 5.0 {
-                    let silence_dur = vad.silence_duration_secs();
-                    let remaining = auto_stop_enabled.1 - silence_dur;
-                    let _ = app.emit("silence-countdown", serde_json::json!({
-                        "remaining": remaining,
-                        "total": auto_stop_enabled.1
-                    }));
-                    if silence_dur >= auto_stop_enabled.1 {
-                        tracing::info!("[streaming] Auto-stop: {:.1}s silence detected", silence_dur);
-                        let _ = app.emit("dictation-auto-stop", serde_json::json!({}));
-                        break;
-                    }
+                if stream.silence(auto_stop, recording_start.elapsed().as_secs_f32(), vad.silence_duration_secs()) {
+                    break;
                 }
-
                 continue;
             }
 
-            if auto_stop_enabled.0 {
-                let _ = app.emit("silence-countdown", serde_json::json!({
-                    "remaining": auto_stop_enabled.1,
-                    "total": auto_stop_enabled.1
-                }));
-            }
+            stream.speech(auto_stop);
         }
 
         let ns_enabled = state.noise_suppressor.lock().map(|ns| ns.is_enabled()).unwrap_or(false);
         let denoised_audio = if ns_enabled {
-            tracing::debug!("[streaming] Applying noise suppression to chunk {}...", chunk_index);
+            tracing::debug!("[streaming] Applying noise suppression to chunk {}...", stream.chunk_index);
             match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
                 let ns = state.noise_suppressor.lock().unwrap();
                 ns.suppress(&chunk_audio)
@@ -293,7 +351,7 @@ fn streaming_transcription_loop(streaming_active: Arc<AtomicBool>, app: tauri::A
                         tracing::warn!("[streaming] Noise suppression produced NaN/Inf, using raw audio");
                         chunk_audio.clone()
                     } else {
-                        tracing::debug!("[streaming] Noise suppression completed OK for chunk {}", chunk_index);
+                        tracing::debug!("[streaming] Noise suppression completed OK for chunk {}", stream.chunk_index);
                         audio
                     }
                 }
@@ -309,7 +367,8 @@ fn streaming_transcription_loop(streaming_active: Arc<AtomicBool>, app: tauri::A
 
         let has_bad = processed_audio.iter().any(|v| !v.is_finite());
         if has_bad {
-            tracing::warn!("[streaming] Processed audio has NaN/Inf values, skipping chunk {}", chunk_index);
+            tracing::warn!("[streaming] Processed audio has NaN/Inf values, skipping chunk {}", stream.chunk_index);
+            stream.skip();
             local_processed_pos = current_len;
             *state.last_processed_pos.lock().unwrap() = current_len;
             continue;
@@ -317,17 +376,21 @@ fn streaming_transcription_loop(streaming_active: Arc<AtomicBool>, app: tauri::A
 
         tracing::debug!(
             "[streaming] Processing chunk {} ({} samples, {:.1}s)",
-            chunk_index,
+            stream.chunk_index,
             processed_audio.len(),
             processed_audio.len() as f64 / SAMPLE_RATE as f64,
         );
 
+        let language = state.transcriber.lock().unwrap().get_language();
+        let context = stream.context(&state.accumulated_text.lock().unwrap(), language);
+
         let text = {
             let transcriber = state.transcriber.lock().unwrap();
-            match transcriber.transcribe_chunk(&processed_audio) {
+            match transcriber.transcribe_chunk(&processed_audio, &context) {
                 Ok(t) => t,
                 Err(e) => {
                     tracing::error!("[streaming] Chunk transcription error: {}", e);
+                    stream.skip();
                     local_processed_pos = current_len;
                     *state.last_processed_pos.lock().unwrap() = current_len;
                     continue;
@@ -339,35 +402,25 @@ fn streaming_transcription_loop(streaming_active: Arc<AtomicBool>, app: tauri::A
             break;
         }
 
+        // Words repeated from the overlap are dropped, so the chunk's span starts after it.
+        let span = (buffer_ms(state, local_processed_pos), buffer_ms(state, current_len));
         local_processed_pos = current_len;
         *state.last_processed_pos.lock().unwrap() = current_len;
-        chunk_index += 1;
 
         let chunk_duration = chunk_audio.len() as f32 / SAMPLE_RATE as f32;
-        if !text.trim().is_empty() && !is_chunk_hallucination(&text, chunk_duration) {
-            tracing::debug!("[streaming] Chunk {} result: '{}'", chunk_index, text.trim());
-
-            {
-                let mut acc = state.accumulated_text.lock().unwrap();
-                acc.push(text.trim().to_string());
+        let accepted = stream.accept(&state.accumulated_text, &text, chunk_duration, span, |t| {
+            vocabulary::correct(state, t)
+        });
+        if let Some(text) = accepted {
+            if let Some(live) = state.live.lock().unwrap().as_mut() {
+                live.append(&text);
             }
-
-            let _ = app.emit(
-                "dictation-partial",
-                serde_json::json!({
-                    "text": text.trim(),
-                    "chunk_index": chunk_index,
-                    "is_final": false
-                }),
-            );
-        } else if !text.trim().is_empty() {
-            tracing::debug!("[streaming] Filtered chunk hallucination: '{}'", text.trim());
         }
     }
     tracing::debug!("[streaming] Loop ended");
 }
 
-async fn stop_streaming_thread(state: &State<'_, DictationState>) -> Result<(), String> {
+async fn stop_streaming_thread(state: &DictationState) -> Result<(), String> {
     state.streaming_active.store(false, Ordering::SeqCst);
     tracing::debug!("[dictation] Waiting for streaming thread to finish...");
 
@@ -417,17 +470,16 @@ fn capture_audio(state: &State<'_, DictationState>) -> Result<(Vec<f32>, u64), S
 fn transcribe_audio(
     state: &State<'_, DictationState>,
     audio_data: &[f32],
-) -> Result<String, String> {
+) -> Result<Vec<TranscriptSegment>, String> {
     tracing::debug!("[dictation] Starting final Whisper transcription on full audio...");
     let transcriber = state.transcriber.lock().map_err(|e| e.to_string())?;
-    match transcriber.transcribe(audio_data) {
-        Ok(t) => {
+    match transcriber.transcribe_segments(audio_data) {
+        Ok(segments) => {
             tracing::debug!(
-                "[dictation] Transcription complete: '{}' ({} chars)",
-                t,
-                t.len()
+                "[dictation] Transcription complete: {} segment(s)",
+                segments.len()
             );
-            Ok(t)
+            Ok(segments)
         }
         Err(e) => {
             tracing::error!("[dictation] ERROR in transcription: {}", e);
@@ -444,42 +496,16 @@ struct RefinementResult {
 
 async fn refine_with_ai(
     text: &str,
-    db: &State<'_, Database>,
-    app: &tauri::AppHandle,
+    settings: &Settings,
+    sink: &dyn EventSink,
 ) -> RefinementResult {
-    let ai_enabled = {
-        let conn = match db.0.lock() {
-            Ok(c) => c,
-            Err(_) => return RefinementResult { text: text.to_string(), ai_provider: String::new(), processing_time_ms: 0 },
-        };
-        conn.query_row(
-            "SELECT value FROM settings WHERE key = 'ai_refinement'",
-            [],
-            |row| row.get::<_, String>(0),
-        )
-        .unwrap_or_else(|_| "false".to_string())
-    };
-
-    if ai_enabled != "true" || text.trim().is_empty() {
+    if !settings.ai_refinement || text.trim().is_empty() {
         return RefinementResult { text: text.to_string(), ai_provider: String::new(), processing_time_ms: 0 };
     }
 
-    let language = {
-        let conn = match db.0.lock() {
-            Ok(c) => c,
-            Err(_) => return RefinementResult { text: text.to_string(), ai_provider: String::new(), processing_time_ms: 0 },
-        };
-        conn.query_row(
-            "SELECT value FROM settings WHERE key = 'language'",
-            [],
-            |row| row.get::<_, String>(0),
-        )
-        .unwrap_or_else(|_| "\"ar\"".to_string())
-        .trim_matches('"')
-        .to_string()
-    };
+    let language = settings.language.clone();
 
-    let refiner = match AIFactory::create_from_settings(db) {
+    let refiner = match AIFactory::create_from_settings(settings) {
         Ok(r) => r,
         Err(e) => {
             tracing::error!("[ai] Failed to create refiner: {}", e);
@@ -491,37 +517,15 @@ async fn refine_with_ai(
     tracing::info!("[ai] AI refinement enabled, using {} (language: {})...", provider_name, language);
 
     let ai_start = std::time::Instant::now();
-    let max_retries = 2u32;
-    let mut result = text.to_string();
-    for attempt in 0..=max_retries {
-        match refiner.refine_streaming(text, &language, app).await {
-            Ok(refined) if !refined.trim().is_empty() => {
-                tracing::debug!("[ai] Refinement successful (attempt {})", attempt + 1);
-                result = refined;
-                break;
-            }
-            Ok(_) => {
-                tracing::warn!("[ai] Refinement returned empty, using original");
-                break;
-            }
-            Err(e) => {
-                if attempt < max_retries {
-                    let delay = std::time::Duration::from_millis(500 * 2u64.pow(attempt));
-                    tracing::warn!("[ai] Attempt {} failed: {}, retrying in {:?}...", attempt + 1, e, delay);
-                    tokio::time::sleep(delay).await;
-                } else {
-                    tracing::error!("[ai] All {} attempts failed, using original: {}", max_retries + 1, e);
-                }
-            }
-        }
-    }
+    let result = refine_with_retries(refiner.as_ref(), text, &language, &settings.ai_instructions, sink).await;
     let processing_time_ms = ai_start.elapsed().as_millis() as u64;
     tracing::debug!("[ai] Processing took {}ms", processing_time_ms);
 
     RefinementResult { text: result, ai_provider: provider_name, processing_time_ms }
 }
 
-fn save_to_history(
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn save_to_history(
     state: &State<'_, DictationState>,
     db: &State<'_, Database>,
     text: &str,
@@ -529,21 +533,26 @@ fn save_to_history(
     duration: u64,
     ai_provider: &str,
     processing_time_ms: u64,
-) -> Result<(), String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    source: &str,
+) -> Result<i64, String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
     let language = {
         let transcriber = state.transcriber.lock().map_err(|e| e.to_string())?;
         transcriber.get_language()
     };
 
-    conn.execute(
-        "INSERT INTO dictation_history (text, raw_text, duration, language, ai_provider, processing_time_ms) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        rusqlite::params![text, raw_text, duration as i64, &language, ai_provider, processing_time_ms as i64],
+    // The search index is no longer kept by triggers, so the row and its index entry commit together.
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO dictation_history (text, raw_text, duration, language, ai_provider, processing_time_ms, source) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![text, raw_text, duration as i64, &language, ai_provider, processing_time_ms as i64, source],
     )
     .map_err(|e| e.to_string())?;
+    let history_id = tx.last_insert_rowid();
+    search::index_history(&tx, history_id, text, raw_text).map_err(|e| e.to_string())?;
 
     let word_count = text.split_whitespace().count() as i64;
-    conn.execute(
+    tx.execute(
         "INSERT INTO usage_stats (date, total_dictations, total_words, total_duration)
          VALUES (date('now'), 1, ?1, ?2)
          ON CONFLICT(date) DO UPDATE SET
@@ -553,65 +562,203 @@ fn save_to_history(
         rusqlite::params![word_count, duration as i64],
     )
     .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
 
-    Ok(())
+    Ok(history_id)
 }
 
-fn auto_type_text(db: &State<'_, Database>, text: &str) -> Result<(), String> {
-    let auto_type = {
-        let conn = db.0.lock().map_err(|e| e.to_string())?;
-        conn.query_row(
-            "SELECT value FROM settings WHERE key = 'auto_type'",
-            [],
-            |row| row.get::<_, String>(0),
+pub(crate) fn save_segments(
+    db: &State<'_, Database>,
+    history_id: i64,
+    segments: &[TranscriptSegment],
+) -> Result<(), String> {
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for (index, segment) in segments.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO dictation_segments (history_id, segment_index, start_ms, end_ms, text) VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![history_id, index as i64, segment.start_ms, segment.end_ms, &segment.text],
         )
-        .unwrap_or_else(|_| "true".to_string())
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// post_process with the session's glossary and, for dictation, its voice commands.
+pub(crate) fn post_process_transcript(
+    state: &DictationState,
+    segments: &mut Vec<TranscriptSegment>,
+    voice_commands: bool,
+) -> String {
+    let language = match state.transcriber.lock() {
+        Ok(t) => t.get_language(),
+        Err(_) => "ar".to_string(),
     };
+    let glossary = state.vocabulary.lock().unwrap_or_else(|e| e.into_inner());
+    let processor = voice_commands.then(|| state.voice_commands.lock().unwrap_or_else(|e| e.into_inner()));
+    post_process(segments, &glossary, processor.as_deref(), &language)
+}
 
-    if auto_type == "true" {
-        let simulator = KeyboardSimulator::new();
-        if let Err(e) = simulator.type_text(text) {
-            tracing::error!("Auto-type failed: {}", e);
-        }
+fn take_live(state: &DictationState) -> Option<LiveInsertion> {
+    state
+        .live
+        .lock()
+        .map(|mut l| l.take())
+        .unwrap_or_else(|e| e.into_inner().take())
+        .filter(|live| !live.inserted().is_empty())
+}
+
+// With live typing the partial text is already in the app, so only the difference is typed.
+fn insert_final(state: &DictationState, settings: &Settings, text: &str, raw_text: &str) -> Result<(), String> {
+    if let Some(live) = take_live(state) {
+        let method = live.finish(text)?;
+        insertion::record(state, text, raw_text, method, None);
+        return Ok(());
+    }
+    if settings.auto_type {
+        let method = insertion::insert_and_record(state, settings, text, raw_text)?;
+        tracing::debug!("[dictation] Inserted {} chars via '{}'", text.chars().count(), method);
     }
     Ok(())
 }
 
+fn discard_live(state: &DictationState) {
+    if let Some(live) = take_live(state) {
+        if let Err(e) = live.finish("") {
+            tracing::warn!("[live] Failed to erase live-typed text: {}", e);
+        }
+    }
+}
+
+// Where the streamed chunks stopped, as a sample position in the 16 kHz mono recording.
+fn tail_start(last_raw_pos: usize, rate: u32, channels: u16, audio_len: usize) -> usize {
+    let mono_pos = last_raw_pos / channels.max(1) as usize;
+    let converted_pos = if rate != SAMPLE_RATE {
+        (mono_pos as f64 * SAMPLE_RATE as f64 / rate as f64) as usize
+    } else {
+        mono_pos
+    };
+    converted_pos.min(audio_len)
+}
+
+// Moves the tail's segments to where it starts in the recording; a hallucinated tail is dropped.
+fn offset_tail(tail: Vec<TranscriptSegment>, tail_samples: usize, start: usize) -> Option<Vec<TranscriptSegment>> {
+    if tail.is_empty() {
+        return None;
+    }
+    let text = segments_text(&tail);
+    if is_chunk_hallucination(&text, tail_samples as f32 / SAMPLE_RATE as f32) {
+        tracing::debug!("[dictation] Tail was hallucination, skipping");
+        return None;
+    }
+    tracing::debug!("[dictation] Tail transcription: '{}'", text.trim());
+    let offset_ms = start as i64 * 1000 / SAMPLE_RATE as i64;
+    Some(
+        tail.into_iter()
+            .map(|s| TranscriptSegment {
+                start_ms: s.start_ms + offset_ms,
+                end_ms: s.end_ms + offset_ms,
+                text: s.text,
+            })
+            .collect(),
+    )
+}
+
 fn emit_final_result(
-    app: &tauri::AppHandle,
+    sink: &dyn EventSink,
     text: &str,
     duration: u64,
     language: &str,
 ) {
-    let _ = app.emit(
-        "dictation-partial",
-        serde_json::json!({
-            "text": text,
-            "chunk_index": -1,
-            "is_final": true
-        }),
-    );
+    sink.send(PipelineEvent::Partial {
+        text: text.to_string(),
+        chunk_index: -1,
+        is_final: true,
+    });
 
-    let _ = app.emit(
-        "dictation-result",
-        serde_json::json!({
-            "text": text,
-            "duration": duration,
-            "language": language
-        }),
-    );
+    sink.send(PipelineEvent::Result {
+        text: text.to_string(),
+        duration,
+        language: language.to_string(),
+    });
+}
+
+fn apply_decoding(state: &DictationState, settings: &Settings) -> Result<(), String> {
+    {
+        let mut transcriber = state.transcriber.lock().map_err(|e| e.to_string())?;
+        transcriber.set_language(&settings.language);
+        transcriber.set_decoding(settings.decoding());
+    }
+    vocabulary::apply(state, settings)?;
+    let mut vc = state.voice_commands.lock().map_err(|e| e.to_string())?;
+    vc.set_enabled(settings.voice_commands);
+    vc.set_language(&settings.language);
+    Ok(())
+}
+
+pub(crate) fn active_profile(state: &DictationState, db: &Database) -> Result<Option<AppProfile>, String> {
+    let Some(window) = state.window.active_window() else {
+        return Ok(None);
+    };
+    let profiles = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        profiles::load(&conn).map_err(|e| e.to_string())?
+    };
+    let profile = profiles::find(&profiles, &window).cloned();
+    match &profile {
+        Some(p) => tracing::info!("[profile] Matched '{}' for window '{}'", p.name, window.class),
+        None => tracing::debug!("[profile] No profile for window '{}' ({})", window.class, window.title),
+    }
+    Ok(profile)
+}
+
+fn begin_session(state: &DictationState, db: &Database, settings: &Settings) -> Result<(), String> {
+    let Some(profile) = active_profile(state, db)? else {
+        return Ok(());
+    };
+    let session = Arc::new(settings.with_profile(&profile));
+    apply_decoding(state, &session)?;
+    *state.session.lock().map_err(|e| e.to_string())? = Some(session);
+    Ok(())
+}
+
+// Puts the global language, vocabulary and voice commands back after a profile session.
+fn end_session(state: &DictationState, settings: &SettingsState) {
+    let session = state.session.lock().map(|mut s| s.take()).unwrap_or_else(|e| e.into_inner().take());
+    if session.is_some() {
+        if let Err(e) = apply_decoding(state, &settings.get()) {
+            tracing::error!("[profile] Failed to restore global settings: {}", e);
+        }
+    }
 }
 
 #[tauri::command]
 pub async fn start_dictation(
     state: State<'_, DictationState>,
     db: State<'_, Database>,
+    settings: State<'_, SettingsState>,
     app: tauri::AppHandle,
 ) -> Result<(), String> {
     let mut is_recording = state.is_recording.lock().map_err(|e| e.to_string())?;
     if *is_recording {
         return Err("التسجيل قيد التشغيل بالفعل".to_string());
     }
+    if state.is_transcribing.load(Ordering::SeqCst) {
+        return Err("لا يمكن بدء الإملاء أثناء تحويل ملف".to_string());
+    }
+
+    end_session(&state, &settings);
+    if let Err(e) = begin_session(&state, &db, &settings.get()) {
+        tracing::warn!("[profile] Failed to apply app profile, using global settings: {}", e);
+        end_session(&state, &settings);
+    }
+
+    {
+        let session = state.session.lock().map_err(|e| e.to_string())?.clone();
+        let live = LiveInsertion::from_settings(&session.unwrap_or_else(|| settings.get()));
+        *state.live.lock().map_err(|e| e.to_string())? = live;
+    }
 
     {
         let mut acc = state.accumulated_text.lock().map_err(|e| e.to_string())?;
@@ -623,53 +770,6 @@ pub async fn start_dictation(
         *pos = 0;
     }
 
-    {
-        let conn = db.0.lock().map_err(|e| e.to_string())?;
-        let lang = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'language'",
-                [],
-                |row| row.get::<_, String>(0),
-            )
-            .unwrap_or_else(|_| "\"ar\"".to_string())
-            .trim_matches('"')
-            .to_string();
-        let custom_vocab = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'custom_vocabulary'",
-                [],
-                |row| row.get::<_, String>(0),
-            )
-            .unwrap_or_default();
-        let mut transcriber = state.transcriber.lock().map_err(|e| e.to_string())?;
-        transcriber.set_language(&lang);
-        transcriber.set_custom_vocabulary(&custom_vocab);
-        tracing::debug!("[dictation] Language set to: {}, custom vocab: {} chars", lang, custom_vocab.len());
-
-        let noise_sup = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'noise_suppression'",
-                [],
-                |row| row.get::<_, String>(0),
-            )
-            .unwrap_or_else(|_| "false".to_string());
-        let mut ns = state.noise_suppressor.lock().map_err(|e| e.to_string())?;
-        ns.set_enabled(noise_sup == "true");
-        tracing::debug!("[dictation] Noise suppression: {}", noise_sup == "true");
-
-        let voice_cmd = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'voice_commands'",
-                [],
-                |row| row.get::<_, String>(0),
-            )
-            .unwrap_or_else(|_| "true".to_string());
-        let mut vc = state.voice_commands.lock().map_err(|e| e.to_string())?;
-        vc.set_enabled(voice_cmd == "true");
-        vc.set_language(&lang);
-        tracing::debug!("[dictation] Voice commands: {}", voice_cmd == "true");
-    }
-
     let recorder = state.recorder.lock().map_err(|e| e.to_string())?;
     recorder.start().map_err(|e| e.to_string())?;
     *is_recording = true;
@@ -684,7 +784,8 @@ pub async fn start_dictation(
     let app_handle = app.clone();
 
     let handle = std::thread::spawn(move || {
-        streaming_transcription_loop(streaming_active, app_handle);
+        let state: tauri::State<'_, DictationState> = app_handle.state();
+        streaming_transcription_loop(streaming_active, &state, &app_handle);
     });
 
     {
@@ -695,10 +796,66 @@ pub async fn start_dictation(
     Ok(())
 }
 
+pub(crate) async fn cancel_recording(state: &DictationState, app: &tauri::AppHandle) -> Result<(), String> {
+    {
+        let mut is_recording = state.is_recording.lock().map_err(|e| e.to_string())?;
+        if !*is_recording {
+            return Err("لا يوجد تسجيل نشط".to_string());
+        }
+        *is_recording = false;
+    }
+
+    if let Err(e) = stop_streaming_thread(state).await {
+        tracing::error!("[dictation] Failed to stop streaming thread: {}", e);
+    }
+
+    {
+        let recorder = state.recorder.lock().map_err(|e| e.to_string())?;
+        if let Err(e) = recorder.stop() {
+            tracing::warn!("[dictation] Failed to stop recorder on cancel: {}", e);
+        }
+    }
+
+    state.accumulated_text.lock().map_err(|e| e.to_string())?.clear();
+    *state.last_processed_pos.lock().map_err(|e| e.to_string())? = 0;
+    state.vad.lock().map_err(|e| e.to_string())?.reset();
+    discard_live(state);
+
+    emit_status(app, false, false);
+    app.send(PipelineEvent::Cancelled {});
+    hide_overlay_window(app);
+
+    end_session(state, &app.state::<SettingsState>());
+
+    tracing::info!("[dictation] Cancelled, recording discarded");
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_dictation(
+    state: State<'_, DictationState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    cancel_recording(&state, &app).await
+}
+
 #[tauri::command]
 pub async fn stop_dictation(
     state: State<'_, DictationState>,
     db: State<'_, Database>,
+    settings: State<'_, SettingsState>,
+    app: tauri::AppHandle,
+) -> Result<String, String> {
+    let session = state.session.lock().map_err(|e| e.to_string())?.clone();
+    let result = finish_dictation(state.clone(), db, session.unwrap_or_else(|| settings.get()), app).await;
+    end_session(&state, &settings);
+    result
+}
+
+async fn finish_dictation(
+    state: State<'_, DictationState>,
+    db: State<'_, Database>,
+    settings: Arc<Settings>,
     app: tauri::AppHandle,
 ) -> Result<String, String> {
     {
@@ -743,12 +900,10 @@ pub async fn stop_dictation(
         return Ok(String::new());
     }
 
-    let accumulated = {
-        let acc = state.accumulated_text.lock().unwrap_or_else(|e| e.into_inner());
-        acc.join(" ")
-    };
+    let accumulated = state.accumulated_text.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let accumulated_text = segments_text(&accumulated);
 
-    let text = if accumulated.trim().is_empty() {
+    let mut segments = if accumulated_text.trim().is_empty() {
         tracing::debug!("[dictation] No accumulated streaming text, falling back to full transcription");
         let speech_ratio = {
             let vad = state.vad.lock().unwrap_or_else(|e| e.into_inner());
@@ -757,7 +912,7 @@ pub async fn stop_dictation(
         tracing::debug!("[dictation] Running full transcription on complete audio ({} samples, {:.1}s, speech_ratio={:.1}%)",
             audio_data.len(), audio_data.len() as f64 / SAMPLE_RATE as f64, speech_ratio * 100.0);
         match transcribe_audio(&state, &audio_data) {
-            Ok(t) => t,
+            Ok(segments) => segments,
             Err(e) => {
                 reset_processing(&state, &app);
                 hide_overlay_window(&app);
@@ -770,23 +925,17 @@ pub async fn stop_dictation(
             (recorder.get_actual_sample_rate(), recorder.get_actual_channels())
         };
 
-        let mono_pos = last_raw_pos / actual_channels.max(1) as usize;
-        let converted_pos = if actual_rate != SAMPLE_RATE {
-            (mono_pos as f64 * SAMPLE_RATE as f64 / actual_rate as f64) as usize
-        } else {
-            mono_pos
-        };
-        let converted_pos = converted_pos.min(audio_data.len());
+        let converted_pos = tail_start(last_raw_pos, actual_rate, actual_channels, audio_data.len());
         let remaining_samples = audio_data.len().saturating_sub(converted_pos);
 
         tracing::debug!(
             "[dictation] Using accumulated streaming text ({} chars), tail: {} samples ({:.1}s)",
-            accumulated.len(),
+            accumulated_text.len(),
             remaining_samples,
             remaining_samples as f64 / SAMPLE_RATE as f64
         );
 
-        let tail_text = if remaining_samples > CHUNK_SAMPLES / 2 {
+        let tail_segments = if remaining_samples > CHUNK_SAMPLES / 2 {
             let tail_audio = &audio_data[converted_pos..];
             let ns_enabled = state.noise_suppressor.lock().map(|ns| ns.is_enabled()).unwrap_or(false);
             let denoised_tail = if ns_enabled {
@@ -816,17 +965,7 @@ pub async fn stop_dictation(
                 None
             } else {
             match transcribe_audio(&state, &processed_tail) {
-                Ok(t) if !t.trim().is_empty() => {
-                    let tail_duration = tail_audio.len() as f32 / SAMPLE_RATE as f32;
-                    if !is_chunk_hallucination(&t, tail_duration) {
-                        tracing::debug!("[dictation] Tail transcription: '{}'", t.trim());
-                        Some(t.trim().to_string())
-                    } else {
-                        tracing::debug!("[dictation] Tail was hallucination, skipping");
-                        None
-                    }
-                }
-                Ok(_) => None,
+                Ok(tail) => offset_tail(tail, tail_audio.len(), converted_pos),
                 Err(e) => {
                     tracing::warn!("[dictation] Tail transcription failed: {}", e);
                     None
@@ -838,57 +977,28 @@ pub async fn stop_dictation(
             None
         };
 
-        let mut parts = vec![accumulated.trim().to_string()];
-        if let Some(tail) = tail_text {
-            parts.push(tail);
-        }
-        parts.join(" ")
+        // Chunk overlaps were merged as they arrived; the tail starts where the last chunk ended.
+        let mut segments = accumulated;
+        segments.extend(tail_segments.unwrap_or_default());
+        segments
     };
 
-    let cleaned_text = clean_trailing_hallucinations(&text);
-    if cleaned_text.len() != text.trim().len() {
-        tracing::debug!("[dictation] Cleaned trailing hallucinations: '{}' -> '{}'", text.trim(), cleaned_text);
-    }
-    let text = cleaned_text;
-    tracing::debug!("[dictation] Text after hallucination cleaning: {} chars", text.len());
-
-    let text = match state.voice_commands.lock() {
-        Ok(vc) => {
-            let lang = match state.transcriber.lock() {
-                Ok(t) => t.get_language(),
-                Err(e) => {
-                    tracing::error!("[dictation] Failed to lock transcriber for language: {}", e);
-                    "ar".to_string()
-                }
-            };
-            let result = vc.process_text(&text, &lang);
-            if result.had_commands {
-                tracing::debug!("[voice_commands] Processed: '{}' -> '{}'", text, result.text);
-            }
-            result.text
-        }
-        Err(e) => {
-            tracing::error!("[dictation] Failed to lock voice_commands: {}", e);
-            text
-        }
-    };
-    tracing::debug!("[dictation] Text after voice commands: {} chars", text.len());
+    let text = post_process_transcript(&state, &mut segments, true);
+    vocabulary::record_usage(&state, &db, &text);
+    tracing::debug!("[dictation] Text after post-processing: {} chars", text.len());
 
     let raw_text = text.clone();
     tracing::debug!("[dictation] Starting AI refinement...");
     let refinement = match tokio::time::timeout(
         std::time::Duration::from_secs(30),
-        refine_with_ai(&text, &db, &app),
+        refine_with_ai(&text, &settings, &app),
     )
     .await
     {
         Ok(result) => result,
         Err(_) => {
             tracing::warn!("[ai] AI refinement timed out after 30s, using raw text");
-            let _ = app.emit(
-                "ai-refine-status",
-                serde_json::json!({ "status": "error" }),
-            );
+            app.send(PipelineEvent::RefineStatus { status: RefineStatus::Error });
             RefinementResult {
                 text: text.clone(),
                 ai_provider: String::new(),
@@ -906,10 +1016,15 @@ pub async fn stop_dictation(
     let total_duration = audio_data.len() as f32 / SAMPLE_RATE as f32;
     if !text.trim().is_empty() && !is_chunk_hallucination(&text, total_duration) {
         let save_raw = if ai_provider.is_empty() { "" } else { &raw_text };
-        if let Err(e) = save_to_history(&state, &db, &text, save_raw, duration, &ai_provider, processing_time_ms) {
-            tracing::error!("[dictation] Failed to save history: {}", e);
+        match save_to_history(&state, &db, &text, save_raw, duration, &ai_provider, processing_time_ms, "dictation") {
+            Ok(history_id) => {
+                if let Err(e) = save_segments(&db, history_id, &segments) {
+                    tracing::error!("[dictation] Failed to save segments: {}", e);
+                }
+            }
+            Err(e) => tracing::error!("[dictation] Failed to save history: {}", e),
         }
-        if let Err(e) = auto_type_text(&db, &text) {
+        if let Err(e) = insert_final(&state, &settings, &text, &raw_text) {
             tracing::error!("[dictation] Failed to auto-type: {}", e);
         }
 
@@ -920,6 +1035,7 @@ pub async fn stop_dictation(
         emit_final_result(&app, &text, duration, &language);
         hide_overlay_delayed(&app, 3);
     } else {
+        discard_live(&state);
         hide_overlay_window(&app);
     }
 
@@ -933,6 +1049,181 @@ pub fn get_dictation_status(state: State<'_, DictationState>) -> Result<serde_js
 
     Ok(serde_json::json!({
         "is_recording": *is_recording,
-        "is_processing": *is_processing
+        "is_processing": *is_processing,
+        "is_transcribing": state.is_transcribing.load(Ordering::SeqCst)
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::RecordingSink;
+
+    const AUTO_STOP: AutoStopConfig = AutoStopConfig {
+        enabled: true,
+        seconds: 3.0,
+    };
+
+    fn partial(text: &str, chunk_index: i64) -> PipelineEvent {
+        PipelineEvent::Partial {
+            text: text.to_string(),
+            chunk_index,
+            is_final: false,
+        }
+    }
+
+    fn countdown(remaining: f32) -> PipelineEvent {
+        PipelineEvent::SilenceCountdown {
+            remaining,
+            total: AUTO_STOP.seconds,
+        }
+    }
+
+    fn segment(text: &str) -> TranscriptSegment {
+        TranscriptSegment {
+            start_ms: 0,
+            end_ms: 1000,
+            text: text.to_string(),
+        }
+    }
+
+    fn texts(accumulated: &Mutex<Vec<TranscriptSegment>>) -> Vec<String> {
+        accumulated.lock().unwrap().iter().map(|s| s.text.clone()).collect()
+    }
+
+    #[test]
+    fn streams_partials_then_the_final_result() {
+        let sink = RecordingSink::default();
+        let accumulated = Mutex::new(Vec::new());
+        let mut stream = ChunkStream::new(&sink, "en".to_string());
+
+        let first = stream.accept(&accumulated, " the kwick brown fox ", 2.75, (0, 2000), |t| t.replace("kwick", "quick"));
+        let second = stream.accept(&accumulated, "brown fox jumps over", 2.75, (2000, 4000), str::to_string);
+        assert_eq!(first.as_deref(), Some("the quick brown fox"));
+        assert_eq!(second.as_deref(), Some("jumps over"));
+        assert_eq!(texts(&accumulated), vec!["the quick brown fox", "jumps over"]);
+
+        emit_final_result(&sink, "the quick brown fox jumps over", 4, "en");
+        assert_eq!(
+            sink.take(),
+            vec![
+                partial("the quick brown fox", 1),
+                partial("jumps over", 2),
+                PipelineEvent::Partial {
+                    text: "the quick brown fox jumps over".to_string(),
+                    chunk_index: -1,
+                    is_final: true,
+                },
+                PipelineEvent::Result {
+                    text: "the quick brown fox jumps over".to_string(),
+                    duration: 4,
+                    language: "en".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn fully_repeated_chunk_sends_nothing() {
+        let sink = RecordingSink::default();
+        let accumulated = Mutex::new(Vec::new());
+        let mut stream = ChunkStream::new(&sink, "en".to_string());
+
+        stream.accept(&accumulated, "hello there world", 2.75, (0, 2000), str::to_string);
+        assert_eq!(stream.accept(&accumulated, "there world", 2.75, (2000, 4000), str::to_string), None);
+        assert_eq!(sink.take(), vec![partial("hello there world", 1)]);
+        assert_eq!(texts(&accumulated), vec!["hello there world"]);
+    }
+
+    #[test]
+    fn hallucinated_chunks_still_count() {
+        let sink = RecordingSink::default();
+        let accumulated = Mutex::new(Vec::new());
+        let mut stream = ChunkStream::new(&sink, "ar".to_string());
+
+        stream.accept(&accumulated, "مرحبا بكم جميعا", 2.75, (0, 2000), str::to_string);
+        assert_eq!(stream.accept(&accumulated, "ترجمة نانسي قنقر", 2.75, (2000, 4000), str::to_string), None);
+        assert_eq!(stream.accept(&accumulated, "   ", 2.75, (4000, 6000), str::to_string), None);
+        // The filtered chunks broke the overlap, so repeated words are kept.
+        stream.accept(&accumulated, "جميعا في البرنامج", 2.75, (6000, 8000), str::to_string);
+        assert_eq!(
+            sink.take(),
+            vec![partial("مرحبا بكم جميعا", 1), partial("جميعا في البرنامج", 4)]
+        );
+    }
+
+    #[test]
+    fn skipped_chunk_keeps_repeated_words() {
+        let sink = RecordingSink::default();
+        let accumulated = Mutex::new(Vec::new());
+        let mut stream = ChunkStream::new(&sink, "en".to_string());
+
+        stream.accept(&accumulated, "the quick brown fox", 2.75, (0, 2000), str::to_string);
+        stream.skip();
+        stream.accept(&accumulated, "brown fox jumps", 2.75, (2000, 4000), str::to_string);
+        assert_eq!(texts(&accumulated), vec!["the quick brown fox", "brown fox jumps"]);
+    }
+
+    #[test]
+    fn counts_down_silence_until_auto_stop() {
+        let sink = RecordingSink::default();
+        let stream = ChunkStream::new(&sink, "ar".to_string());
+
+        assert!(!stream.silence(AUTO_STOP, AUTO_STOP_GRACE_SECS, 2.0));
+        assert!(!stream.silence(AUTO_STOP, 6.0, 1.0));
+        stream.speech(AUTO_STOP);
+        assert!(!stream.silence(AUTO_STOP, 7.0, 2.5));
+        assert!(stream.silence(AUTO_STOP, 8.0, 3.0));
+        assert_eq!(
+            sink.take(),
+            vec![
+                countdown(2.0),
+                countdown(3.0),
+                countdown(0.5),
+                countdown(0.0),
+                PipelineEvent::AutoStop {},
+            ]
+        );
+    }
+
+    #[test]
+    fn disabled_auto_stop_sends_nothing() {
+        let sink = RecordingSink::default();
+        let stream = ChunkStream::new(&sink, "ar".to_string());
+        let disabled = AutoStopConfig {
+            enabled: false,
+            ..AUTO_STOP
+        };
+
+        assert!(!stream.silence(disabled, 60.0, 30.0));
+        stream.speech(disabled);
+        assert!(sink.take().is_empty());
+    }
+
+    #[test]
+    fn resets_context_on_language_switch() {
+        let sink = RecordingSink::default();
+        let mut stream = ChunkStream::new(&sink, "en".to_string());
+        let mut accumulated = vec![segment("one"), segment("two")];
+
+        assert_eq!(stream.context(&accumulated, "en".to_string()), "one two");
+        assert_eq!(stream.context(&accumulated, "ar".to_string()), "");
+        accumulated.push(segment("ثلاثة"));
+        assert_eq!(stream.context(&accumulated, "ar".to_string()), "ثلاثة");
+    }
+
+    #[test]
+    fn converts_the_tail_start() {
+        assert_eq!(tail_start(96_000, 48_000, 2, 100_000), 16_000);
+        assert_eq!(tail_start(16_000, SAMPLE_RATE, 1, 100_000), 16_000);
+        assert_eq!(tail_start(96_000, 48_000, 2, 10_000), 10_000);
+    }
+
+    #[test]
+    fn offsets_the_tail() {
+        let tail = offset_tail(vec![segment("last few words")], 16_000, 32_000).unwrap();
+        assert_eq!((tail[0].start_ms, tail[0].end_ms), (2000, 3000));
+        assert!(offset_tail(Vec::new(), 16_000, 0).is_none());
+        assert!(offset_tail(vec![segment("ترجمة نانسي قنقر")], 16_000, 0).is_none());
+    }
+}
@@ -0,0 +1,559 @@
+use crate::commands::history::{load_entry, load_segments, query_entries};
+use crate::db::models::{DictationEntry, DictationSegment, HistoryExportOptions, HistorySort};
+use crate::db::Database;
+use tauri::State;
+
+const FALLBACK_WORDS_PER_CUE: usize = 10;
+const FALLBACK_MS_PER_WORD: i64 = 400;
+
+struct Cue {
+    start_ms: i64,
+    end_ms: i64,
+    text: String,
+}
+
+fn build_cues(entry: &DictationEntry, segments: &[DictationSegment]) -> Vec<Cue> {
+    if !segments.is_empty() {
+        return segments
+            .iter()
+            .map(|s| Cue {
+                start_ms: s.start_ms,
+                end_ms: s.end_ms.max(s.start_ms),
+                text: s.text.trim().to_string(),
+            })
+            .collect();
+    }
+
+    let words: Vec<&str> = entry.text.split_whitespace().collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let total_ms = if entry.duration > 0 {
+        entry.duration * 1000
+    } else {
+        words.len() as i64 * FALLBACK_MS_PER_WORD
+    };
+    let word_count = words.len() as i64;
+
+    words
+        .chunks(FALLBACK_WORDS_PER_CUE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let first_word = (i * FALLBACK_WORDS_PER_CUE) as i64;
+            let last_word = first_word + chunk.len() as i64;
+            Cue {
+                start_ms: total_ms * first_word / word_count,
+                end_ms: total_ms * last_word / word_count,
+                text: chunk.join(" "),
+            }
+        })
+        .collect()
+}
+
+fn format_timestamp(ms: i64, separator: char) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        (ms / 60_000) % 60,
+        (ms / 1000) % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+// A blank line ends a cue early in both formats, so the text keeps only its non-empty lines.
+fn cue_lines(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// WebVTT cue text is parsed for tags and entities, and "-->" is not allowed in it.
+fn vtt_text(text: &str) -> String {
+    cue_lines(text)
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace("-->", "--&gt;")
+}
+
+fn render_srt(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_timestamp(cue.start_ms, ','),
+            format_timestamp(cue.end_ms, ','),
+            cue_lines(&cue.text)
+        ));
+    }
+    out
+}
+
+fn render_vtt(cues: &[Cue]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for cue in cues {
+        out.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            format_timestamp(cue.start_ms, '.'),
+            format_timestamp(cue.end_ms, '.'),
+            vtt_text(&cue.text)
+        ));
+    }
+    out
+}
+
+fn render_json(entry: &DictationEntry, cues: &[Cue]) -> Result<String, String> {
+    let segments: Vec<serde_json::Value> = cues
+        .iter()
+        .enumerate()
+        .map(|(i, cue)| {
+            serde_json::json!({
+                "index": i,
+                "start_ms": cue.start_ms,
+                "end_ms": cue.end_ms,
+                "text": cue.text
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "id": entry.id,
+        "language": entry.language,
+        "duration": entry.duration,
+        "source": entry.source,
+        "created_at": entry.created_at,
+        "text": entry.text,
+        "segments": segments
+    }))
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn export_subtitles(db: State<'_, Database>, id: i64, format: String) -> Result<String, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let entry = load_entry(&conn, id).map_err(|_| "السجل غير موجود".to_string())?;
+    let segments = load_segments(&conn, id).map_err(|e| e.to_string())?;
+    drop(conn);
+
+    let cues = build_cues(&entry, &segments);
+    tracing::debug!(
+        "[export] Entry {} as {}: {} cue(s) ({})",
+        id,
+        format,
+        cues.len(),
+        if segments.is_empty() { "estimated" } else { "timed" }
+    );
+
+    match format.to_lowercase().as_str() {
+        "srt" => Ok(render_srt(&cues)),
+        "vtt" | "webvtt" => Ok(render_vtt(&cues)),
+        "json" => render_json(&entry, &cues),
+        other => Err(format!("صيغة التصدير غير مدعومة: {}", other)),
+    }
+}
+
+const RLM: char = '\u{200F}';
+const UTF8_BOM: &str = "\u{FEFF}";
+
+fn is_rtl_char(c: char) -> bool {
+    matches!(c, '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFC}')
+}
+
+// Direction of the first strong character, falling back to the entry language.
+fn is_rtl(entry: &DictationEntry) -> bool {
+    entry
+        .text
+        .chars()
+        .find(|c| c.is_alphabetic())
+        .map(is_rtl_char)
+        .unwrap_or(entry.language == "ar")
+}
+
+fn direction(entry: &DictationEntry) -> &'static str {
+    if is_rtl(entry) {
+        "rtl"
+    } else {
+        "ltr"
+    }
+}
+
+// Spreadsheets run cells starting with these as formulas, so they get a leading quote.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+// Backslash-escapes one line of entry text so it renders literally instead of as headings,
+// lists, tables, code blocks, emphasis or HTML.
+fn markdown_escape(line: &str) -> String {
+    let line = line.trim_start();
+    let mut out = String::with_capacity(line.len());
+    if line.starts_with(['-', '+', '=']) {
+        out.push('\\');
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    for (i, c) in line.chars().enumerate() {
+        let list_marker = i == digits && digits > 0 && matches!(c, '.' | ')');
+        if list_marker || matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn metadata_line(entry: &DictationEntry) -> String {
+    let mut parts = vec![
+        format!("language: {}", entry.language),
+        format!("duration: {}s", entry.duration),
+        format!("source: {}", entry.source),
+    ];
+    if !entry.ai_provider.is_empty() {
+        parts.push(format!("provider: {}", entry.ai_provider));
+        parts.push(format!("processing: {}ms", entry.processing_time_ms));
+    }
+    parts.join(" | ")
+}
+
+fn render_history_csv(entries: &[DictationEntry], opts: &HistoryExportOptions) -> String {
+    let mut header = vec!["id"];
+    if opts.include_timestamps {
+        header.push("created_at");
+    }
+    header.push("text");
+    if opts.include_raw_text {
+        header.push("raw_text");
+    }
+    if opts.include_metadata {
+        header.extend(["language", "duration", "source", "ai_provider", "processing_time_ms"]);
+    }
+
+    // The BOM makes spreadsheet apps read Arabic text as UTF-8.
+    let mut out = String::from(UTF8_BOM);
+    out.push_str(&header.join(","));
+    out.push_str("\r\n");
+
+    for entry in entries {
+        let mut row = vec![entry.id.to_string()];
+        if opts.include_timestamps {
+            row.push(csv_field(&entry.created_at));
+        }
+        row.push(csv_field(&entry.text));
+        if opts.include_raw_text {
+            row.push(csv_field(&entry.raw_text));
+        }
+        if opts.include_metadata {
+            row.push(csv_field(&entry.language));
+            row.push(entry.duration.to_string());
+            row.push(csv_field(&entry.source));
+            row.push(csv_field(&entry.ai_provider));
+            row.push(entry.processing_time_ms.to_string());
+        }
+        out.push_str(&row.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+fn render_history_jsonl(entries: &[DictationEntry], opts: &HistoryExportOptions) -> Result<String, String> {
+    let mut out = String::new();
+    for entry in entries {
+        let mut obj = serde_json::Map::new();
+        obj.insert("id".into(), entry.id.into());
+        if opts.include_timestamps {
+            obj.insert("created_at".into(), entry.created_at.clone().into());
+        }
+        obj.insert("text".into(), entry.text.clone().into());
+        obj.insert("direction".into(), direction(entry).into());
+        if opts.include_raw_text {
+            obj.insert("raw_text".into(), entry.raw_text.clone().into());
+        }
+        if opts.include_metadata {
+            obj.insert("language".into(), entry.language.clone().into());
+            obj.insert("duration".into(), entry.duration.into());
+            obj.insert("source".into(), entry.source.clone().into());
+            obj.insert("ai_provider".into(), entry.ai_provider.clone().into());
+            obj.insert("processing_time_ms".into(), entry.processing_time_ms.into());
+        }
+        out.push_str(&serde_json::to_string(&obj).map_err(|e| e.to_string())?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+// Markdown has no direction syntax, so RTL entries are wrapped in an HTML block.
+fn render_history_markdown(entries: &[DictationEntry], opts: &HistoryExportOptions) -> String {
+    let mut out = String::new();
+    for entry in entries {
+        if opts.include_timestamps {
+            out.push_str(&format!("## {}\n\n", entry.created_at));
+        }
+        if opts.include_metadata {
+            out.push_str(&format!("_{}_\n\n", metadata_line(entry)));
+        }
+
+        let rtl = is_rtl(entry);
+        if rtl {
+            out.push_str("<div dir=\"rtl\">\n\n");
+        }
+        for line in entry.text.trim().lines() {
+            out.push_str(&markdown_escape(line));
+            out.push('\n');
+        }
+        out.push('\n');
+        if opts.include_raw_text && !entry.raw_text.is_empty() {
+            for line in entry.raw_text.trim().lines() {
+                out.push_str(&format!("> {}\n", markdown_escape(line)));
+            }
+            out.push('\n');
+        }
+        if rtl {
+            out.push_str("</div>\n\n");
+        }
+        out.push_str("---\n\n");
+    }
+    out
+}
+
+// A leading RLM keeps RTL paragraphs right-aligned even when they start with digits or Latin.
+fn render_history_text(entries: &[DictationEntry], opts: &HistoryExportOptions) -> String {
+    let mut blocks = Vec::new();
+    for entry in entries {
+        let mark = if is_rtl(entry) { RLM.to_string() } else { String::new() };
+        let mut block = String::new();
+        if opts.include_timestamps {
+            block.push_str(&format!("[{}]\n", entry.created_at));
+        }
+        if opts.include_metadata {
+            block.push_str(&format!("{}\n", metadata_line(entry)));
+        }
+        for line in entry.text.trim().lines() {
+            block.push_str(&format!("{}{}\n", mark, line));
+        }
+        if opts.include_raw_text && !entry.raw_text.is_empty() {
+            block.push('\n');
+            for line in entry.raw_text.trim().lines() {
+                block.push_str(&format!("{}{}\n", mark, line));
+            }
+        }
+        blocks.push(block);
+    }
+    blocks.join("\n")
+}
+
+// Plain HTML that Word and LibreOffice open as a document, keeping per-entry direction.
+fn render_history_html(entries: &[DictationEntry], opts: &HistoryExportOptions) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Kateb</title>\n\
+         <style>body{font-family:Arial,sans-serif;line-height:1.6}.meta{color:#666;font-size:0.85em}\
+         .raw{color:#555;border-inline-start:3px solid #ccc;padding-inline-start:8px}</style>\n</head>\n<body>\n",
+    );
+    for entry in entries {
+        let dir = direction(entry);
+        out.push_str(&format!(
+            "<div dir=\"{}\" lang=\"{}\">\n",
+            dir,
+            html_escape(&entry.language)
+        ));
+        if opts.include_timestamps {
+            out.push_str(&format!("<h3 dir=\"ltr\">{}</h3>\n", html_escape(&entry.created_at)));
+        }
+        if opts.include_metadata {
+            out.push_str(&format!(
+                "<p class=\"meta\" dir=\"ltr\">{}</p>\n",
+                html_escape(&metadata_line(entry))
+            ));
+        }
+        for paragraph in entry.text.trim().split("\n\n") {
+            out.push_str(&format!(
+                "<p>{}</p>\n",
+                html_escape(paragraph).replace('\n', "<br>")
+            ));
+        }
+        if opts.include_raw_text && !entry.raw_text.is_empty() {
+            out.push_str(&format!(
+                "<p class=\"raw\">{}</p>\n",
+                html_escape(entry.raw_text.trim()).replace('\n', "<br>")
+            ));
+        }
+        out.push_str("</div>\n<hr>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn load_export_entries(
+    conn: &rusqlite::Connection,
+    opts: &mut HistoryExportOptions,
+) -> rusqlite::Result<Vec<DictationEntry>> {
+    if let Some(ref ids) = opts.ids {
+        let mut entries: Vec<DictationEntry> = ids
+            .iter()
+            .filter_map(|id| load_entry(conn, *id).ok())
+            .collect();
+        entries.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+        return Ok(entries);
+    }
+
+    let mut query = opts.filter.take().unwrap_or_default();
+    if query.limit.is_none() {
+        query.limit = Some(-1);
+    }
+    if query.sort.is_none() && query.search.is_none() {
+        query.sort = Some(HistorySort::Oldest);
+    }
+    Ok(query_entries(conn, &query)?
+        .items
+        .into_iter()
+        .map(|r| r.entry)
+        .collect())
+}
+
+#[tauri::command]
+pub fn export_history(
+    db: State<'_, Database>,
+    mut options: HistoryExportOptions,
+) -> Result<String, String> {
+    let entries = {
+        let conn = db.0.lock().map_err(|e| e.to_string())?;
+        load_export_entries(&conn, &mut options).map_err(|e| e.to_string())?
+    };
+
+    tracing::info!(
+        "[export] Exporting {} history entries as {}",
+        entries.len(),
+        options.format
+    );
+
+    match options.format.to_lowercase().as_str() {
+        "csv" => Ok(render_history_csv(&entries, &options)),
+        "jsonl" | "ndjson" => render_history_jsonl(&entries, &options),
+        "md" | "markdown" => Ok(render_history_markdown(&entries, &options)),
+        "txt" | "text" => Ok(render_history_text(&entries, &options)),
+        "html" | "doc" => Ok(render_history_html(&entries, &options)),
+        other => Err(format!("صيغة التصدير غير مدعومة: {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(start_ms: i64, end_ms: i64, text: &str) -> Cue {
+        Cue {
+            start_ms,
+            end_ms,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(format_timestamp(0, ','), "00:00:00,000");
+        assert_eq!(format_timestamp(61_001, '.'), "00:01:01.001");
+        assert_eq!(format_timestamp(3_599_999, ','), "00:59:59,999");
+        assert_eq!(format_timestamp(3_600_000, '.'), "01:00:00.000");
+        assert_eq!(format_timestamp(36_000_000 + 5, ','), "10:00:00,005");
+        assert_eq!(format_timestamp(-20, ','), "00:00:00,000");
+    }
+
+    #[test]
+    fn renders_srt() {
+        let cues = [cue(0, 1500, "مرحبا"), cue(1500, 3_600_250, "world")];
+        assert_eq!(
+            render_srt(&cues),
+            "1\n00:00:00,000 --> 00:00:01,500\nمرحبا\n\n2\n00:00:01,500 --> 01:00:00,250\nworld\n\n"
+        );
+    }
+
+    #[test]
+    fn renders_vtt() {
+        let cues = [cue(250, 1000, "hello")];
+        assert_eq!(render_vtt(&cues), "WEBVTT\n\n00:00:00.250 --> 00:00:01.000\nhello\n\n");
+    }
+
+    #[test]
+    fn keeps_cue_text_inside_its_cue() {
+        let cues = [cue(0, 1000, "first\n\n  \nsecond")];
+        assert_eq!(render_srt(&cues), "1\n00:00:00,000 --> 00:00:01,000\nfirst\nsecond\n\n");
+
+        let cues = [cue(0, 1000, "a --> b & <c>\n\nd")];
+        assert_eq!(
+            render_vtt(&cues),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.000\na --&gt; b &amp; &lt;c>\nd\n\n"
+        );
+    }
+
+    fn entry(id: i64, text: &str) -> DictationEntry {
+        DictationEntry {
+            id,
+            text: text.to_string(),
+            raw_text: String::new(),
+            duration: 3,
+            language: "ar".to_string(),
+            ai_provider: String::new(),
+            processing_time_ms: 0,
+            source: "dictation".to_string(),
+            created_at: "2024-01-01 10:00:00".to_string(),
+            original_text: None,
+        }
+    }
+
+    #[test]
+    fn quotes_formula_cells() {
+        assert_eq!(csv_field("=SUM(A1:A2)"), "'=SUM(A1:A2)");
+        assert_eq!(csv_field("+1"), "'+1");
+        assert_eq!(csv_field("-1"), "'-1");
+        assert_eq!(csv_field("@cmd"), "'@cmd");
+        assert_eq!(csv_field("a=b"), "a=b");
+        assert_eq!(csv_field("=1,2"), "\"'=1,2\"");
+    }
+
+    #[test]
+    fn quotes_special_characters() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(csv_field("مرحبا، بك"), "مرحبا، بك");
+    }
+
+    #[test]
+    fn writes_bom_once() {
+        let entries = [entry(1, "=1+1"), entry(2, "مرحبا")];
+        let csv = render_history_csv(&entries, &HistoryExportOptions::default());
+        assert!(csv.starts_with(UTF8_BOM));
+        assert_eq!(csv.matches(UTF8_BOM).count(), 1);
+        assert_eq!(csv, format!("{}id,text\r\n1,'=1+1\r\n2,مرحبا\r\n", UTF8_BOM));
+    }
+
+    #[test]
+    fn escapes_markdown() {
+        assert_eq!(markdown_escape("# title"), "\\# title");
+        assert_eq!(markdown_escape("- item"), "\\- item");
+        assert_eq!(markdown_escape("12. item"), "12\\. item");
+        assert_eq!(markdown_escape("a *b* <i>"), "a \\*b\\* \\<i\\>");
+        assert_eq!(markdown_escape("v1.2"), "v1.2");
+    }
+}
@@ -1,5 +1,11 @@
-use crate::db::models::{DictationEntry, SummaryStats, UsageStat};
+use crate::commands::vocabulary;
+use crate::db::models::{
+    CorrectionSuggestion, DictationEntry, DictationSegment, HistoryPage, HistoryQuery, HistorySearchResult,
+    HistorySort, SummaryStats, UsageStat,
+};
+use crate::db::search;
 use crate::db::Database;
+use rusqlite::types::Value;
 use tauri::State;
 
 fn map_dictation_row(row: &rusqlite::Row) -> rusqlite::Result<DictationEntry> {
@@ -11,10 +17,214 @@ fn map_dictation_row(row: &rusqlite::Row) -> rusqlite::Result<DictationEntry> {
         language: row.get(4)?,
         ai_provider: row.get(5)?,
         processing_time_ms: row.get(6)?,
-        created_at: row.get(7)?,
+        source: row.get(7)?,
+        created_at: row.get(8)?,
+        original_text: row.get(9)?,
     })
 }
 
+pub(crate) fn load_entry(conn: &rusqlite::Connection, id: i64) -> rusqlite::Result<DictationEntry> {
+    conn.query_row(
+        "SELECT id, text, raw_text, duration, language, ai_provider, processing_time_ms, source, created_at, original_text
+         FROM dictation_history
+         WHERE id = ?1",
+        [id],
+        map_dictation_row,
+    )
+}
+
+pub(crate) fn load_segments(conn: &rusqlite::Connection, history_id: i64) -> rusqlite::Result<Vec<DictationSegment>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, history_id, segment_index, start_ms, end_ms, text
+         FROM dictation_segments
+         WHERE history_id = ?1
+         ORDER BY segment_index ASC",
+    )?;
+    let segments = stmt
+        .query_map([history_id], |row| {
+            Ok(DictationSegment {
+                id: row.get(0)?,
+                history_id: row.get(1)?,
+                segment_index: row.get(2)?,
+                start_ms: row.get(3)?,
+                end_ms: row.get(4)?,
+                text: row.get(5)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(segments)
+}
+
+// A bare date for date_to means "through the end of that day".
+fn date_to_clause(date_to: &str) -> &'static str {
+    if date_to.len() == 10 {
+        "h.created_at < datetime(?, '+1 day')"
+    } else {
+        "h.created_at <= datetime(?)"
+    }
+}
+
+pub(crate) fn query_entries(
+    conn: &rusqlite::Connection,
+    query: &HistoryQuery,
+) -> rusqlite::Result<HistoryPage> {
+    let terms = query
+        .search
+        .as_deref()
+        .map(search::query_terms)
+        .unwrap_or_default();
+    let match_query = search::build_match_query(&terms);
+    if query.search.as_deref().is_some_and(|q| !q.trim().is_empty()) && match_query.is_none() {
+        return Ok(HistoryPage {
+            items: Vec::new(),
+            total: 0,
+        });
+    }
+
+    let mut clauses: Vec<&str> = Vec::new();
+    let mut params: Vec<Value> = Vec::new();
+
+    let (from, score) = match match_query {
+        Some(m) => {
+            clauses.push("dictation_history_fts MATCH ?");
+            params.push(Value::Text(m));
+            (
+                "FROM dictation_history_fts JOIN dictation_history h ON h.id = dictation_history_fts.rowid",
+                "bm25(dictation_history_fts, 1.0, 0.5)",
+            )
+        }
+        None => ("FROM dictation_history h", "0.0"),
+    };
+
+    if let Some(ref date_from) = query.date_from {
+        clauses.push("h.created_at >= datetime(?)");
+        params.push(Value::Text(date_from.clone()));
+    }
+    if let Some(ref date_to) = query.date_to {
+        clauses.push(date_to_clause(date_to));
+        params.push(Value::Text(date_to.clone()));
+    }
+    if let Some(ref language) = query.language {
+        clauses.push("h.language = ?");
+        params.push(Value::Text(language.clone()));
+    }
+    if let Some(ref provider) = query.ai_provider {
+        clauses.push("h.ai_provider = ?");
+        params.push(Value::Text(provider.clone()));
+    }
+    if let Some(min) = query.min_duration {
+        clauses.push("h.duration >= ?");
+        params.push(Value::Integer(min));
+    }
+    if let Some(max) = query.max_duration {
+        clauses.push("h.duration <= ?");
+        params.push(Value::Integer(max));
+    }
+    match query.refined {
+        Some(true) => clauses.push("(h.raw_text != '' AND h.text != h.raw_text)"),
+        Some(false) => clauses.push("(h.raw_text = '' OR h.text = h.raw_text)"),
+        None => {}
+    }
+
+    let where_sql = if clauses.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", clauses.join(" AND "))
+    };
+
+    let total: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) {} {}", from, where_sql),
+        rusqlite::params_from_iter(params.iter()),
+        |row| row.get(0),
+    )?;
+
+    let sort = query.sort.unwrap_or(if terms.is_empty() {
+        HistorySort::Newest
+    } else {
+        HistorySort::Relevance
+    });
+    let order_sql = match sort {
+        HistorySort::Relevance if !terms.is_empty() => "score ASC, h.created_at DESC, h.id DESC",
+        HistorySort::Newest | HistorySort::Relevance => "h.created_at DESC, h.id DESC",
+        HistorySort::Oldest => "h.created_at ASC, h.id ASC",
+        HistorySort::Longest => "h.duration DESC, h.created_at DESC",
+        HistorySort::Shortest => "h.duration ASC, h.created_at DESC",
+    };
+
+    params.push(Value::Integer(query.limit.unwrap_or(50)));
+    params.push(Value::Integer(query.offset.unwrap_or(0)));
+
+    let sql = format!(
+        "SELECT h.id, h.text, h.raw_text, h.duration, h.language, h.ai_provider, h.processing_time_ms, h.source, h.created_at,
+                h.original_text, {} AS score
+         {} {}
+         ORDER BY {}
+         LIMIT ? OFFSET ?",
+        score, from, where_sql, order_sql
+    );
+
+    let mut stmt = conn.prepare(&sql)?;
+    let items = stmt
+        .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+            Ok((map_dictation_row(row)?, row.get::<_, f64>(10)?))
+        })?
+        .filter_map(|r| r.ok())
+        .map(|(entry, bm25)| {
+            if terms.is_empty() {
+                return HistorySearchResult {
+                    entry,
+                    score: 0.0,
+                    snippet: Vec::new(),
+                    snippet_field: String::new(),
+                };
+            }
+            let (snippet, field) = match search::highlight_snippet(&entry.text, &terms) {
+                Some(snippet) => (snippet, "text"),
+                None => (
+                    search::highlight_snippet(&entry.raw_text, &terms).unwrap_or_default(),
+                    "raw_text",
+                ),
+            };
+            HistorySearchResult {
+                entry,
+                score: -bm25,
+                snippet,
+                snippet_field: field.to_string(),
+            }
+        })
+        .collect();
+
+    Ok(HistoryPage { items, total })
+}
+
+#[tauri::command]
+pub fn query_history(db: State<'_, Database>, query: HistoryQuery) -> Result<HistoryPage, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    query_entries(&conn, &query).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn search_history(
+    db: State<'_, Database>,
+    query: String,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) -> Result<Vec<HistorySearchResult>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let page = query_entries(
+        &conn,
+        &HistoryQuery {
+            search: Some(query),
+            limit,
+            offset,
+            ..Default::default()
+        },
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(page.items)
+}
+
 #[tauri::command]
 pub fn get_history(
     db: State<'_, Database>,
@@ -23,46 +233,53 @@ pub fn get_history(
     offset: Option<i64>,
 ) -> Result<Vec<DictationEntry>, String> {
     let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let limit = limit.unwrap_or(50);
-    let offset = offset.unwrap_or(0);
-
-    if let Some(ref query) = search {
-        let pattern = format!("%{}%", query);
-        let mut stmt = conn
-            .prepare(
-                "SELECT id, text, raw_text, duration, language, ai_provider, processing_time_ms, created_at
-                 FROM dictation_history
-                 WHERE text LIKE ?1
-                 ORDER BY created_at DESC
-                 LIMIT ?2 OFFSET ?3",
-            )
-            .map_err(|e| e.to_string())?;
+    let page = query_entries(
+        &conn,
+        &HistoryQuery {
+            search,
+            limit,
+            offset,
+            ..Default::default()
+        },
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(page.items.into_iter().map(|r| r.entry).collect())
+}
 
-        let result: Vec<DictationEntry> = stmt
-            .query_map(rusqlite::params![pattern, limit, offset], map_dictation_row)
-            .map_err(|e| e.to_string())?
-            .filter_map(|r| r.ok())
-            .collect();
+#[tauri::command]
+pub fn get_history_segments(db: State<'_, Database>, id: i64) -> Result<Vec<DictationSegment>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load_segments(&conn, id).map_err(|e| e.to_string())
+}
 
-        Ok(result)
-    } else {
-        let mut stmt = conn
-            .prepare(
-                "SELECT id, text, raw_text, duration, language, ai_provider, processing_time_ms, created_at
-                 FROM dictation_history
-                 ORDER BY created_at DESC
-                 LIMIT ?1 OFFSET ?2",
-            )
-            .map_err(|e| e.to_string())?;
+// Keeps the text as first saved and learns from the words the user replaced.
+#[tauri::command]
+pub fn update_history_item(db: State<'_, Database>, id: i64, text: String) -> Result<Vec<CorrectionSuggestion>, String> {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err("النص لا يمكن أن يكون فارغاً".to_string());
+    }
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let entry = load_entry(&conn, id).map_err(|e| e.to_string())?;
+    if entry.text == text {
+        return Ok(Vec::new());
+    }
 
-        let result: Vec<DictationEntry> = stmt
-            .query_map(rusqlite::params![limit, offset], map_dictation_row)
-            .map_err(|e| e.to_string())?
-            .filter_map(|r| r.ok())
-            .collect();
+    let original = entry.original_text.clone().unwrap_or_else(|| entry.text.clone());
+    // Editing back to the first version drops the edit marker.
+    let original = (original != text).then_some(original);
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE dictation_history SET text = ?1, original_text = ?2 WHERE id = ?3",
+        rusqlite::params![text, original, id],
+    )
+    .map_err(|e| e.to_string())?;
+    search::index_history(&tx, id, &text, &entry.raw_text).map_err(|e| e.to_string())?;
+    let suggestions =
+        vocabulary::record_corrections(&tx, &entry.text, &text, &entry.language).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
 
-        Ok(result)
-    }
+    Ok(suggestions)
 }
 
 #[tauri::command]
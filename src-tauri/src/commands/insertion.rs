@@ -0,0 +1,103 @@
+use crate::commands::dictation::DictationState;
+use crate::keyboard::clipboard::ClipboardSnapshot;
+use crate::keyboard::inserter;
+use crate::keyboard::simulator::KeyboardSimulator;
+use crate::settings::Settings;
+use arboard::Clipboard;
+
+pub struct LastInsertion {
+    pub text: String,
+    pub raw_text: String,
+    pub method: &'static str,
+    // Clipboard contents from before the insertion, kept only when undo may restore them.
+    previous_clipboard: Option<ClipboardSnapshot>,
+}
+
+pub(crate) fn capture_clipboard(settings: &Settings) -> Option<ClipboardSnapshot> {
+    if !settings.undo_restore_clipboard {
+        return None;
+    }
+    Clipboard::new()
+        .map(|mut clipboard| ClipboardSnapshot::capture(&mut clipboard))
+        .ok()
+}
+
+pub(crate) fn record(
+    state: &DictationState,
+    text: &str,
+    raw_text: &str,
+    method: &'static str,
+    previous_clipboard: Option<ClipboardSnapshot>,
+) {
+    let raw_text = if raw_text.is_empty() { text } else { raw_text };
+    if let Ok(mut last) = state.last_insertion.lock() {
+        *last = Some(LastInsertion {
+            text: text.to_string(),
+            raw_text: raw_text.to_string(),
+            method,
+            previous_clipboard,
+        });
+    }
+}
+
+// Inserts with the fallback chain and remembers the result for undo.
+pub(crate) fn insert_and_record(
+    state: &DictationState,
+    settings: &Settings,
+    text: &str,
+    raw_text: &str,
+) -> Result<&'static str, String> {
+    let previous_clipboard = capture_clipboard(settings);
+    let method = inserter::insert_text(settings, text)?;
+    record(state, text, raw_text, method, previous_clipboard);
+    Ok(method)
+}
+
+fn remove_text(settings: &Settings, text: &str) -> Result<(), String> {
+    let count = text.chars().count();
+    let simulator = KeyboardSimulator::new();
+    let result = if settings.undo_method == "select" {
+        simulator.select_back(count).and_then(|_| simulator.backspace(1))
+    } else {
+        simulator.backspace(count)
+    };
+    result.map_err(|e| e.to_string())
+}
+
+// Only puts the old clipboard back while it still holds our text, so newer copies survive.
+fn restore_clipboard(snapshot: ClipboardSnapshot, inserted: &str) {
+    let Ok(mut clipboard) = Clipboard::new() else {
+        return;
+    };
+    if clipboard.get_text().ok().as_deref() == Some(inserted) {
+        snapshot.restore(&mut clipboard);
+    }
+}
+
+pub(crate) fn undo_insertion(
+    state: &DictationState,
+    settings: &Settings,
+    reinsert_raw: bool,
+) -> Result<String, String> {
+    let last = state
+        .last_insertion
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take()
+        .ok_or_else(|| "لا يوجد نص مُدرج للتراجع عنه".to_string())?;
+
+    // Clipboard-only insertion never typed anything into the app.
+    if last.method != "clipboard" {
+        remove_text(settings, &last.text)?;
+    }
+    if let Some(snapshot) = last.previous_clipboard {
+        restore_clipboard(snapshot, &last.text);
+    }
+    tracing::info!("[insert] Undid {} chars inserted via '{}'", last.text.chars().count(), last.method);
+
+    if !reinsert_raw {
+        return Ok(last.text);
+    }
+    insert_and_record(state, settings, &last.raw_text, &last.raw_text)?;
+    Ok(last.raw_text)
+}
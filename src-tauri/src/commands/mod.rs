@@ -2,7 +2,12 @@ pub mod ai;
 pub mod audio;
 pub mod backup;
 pub mod dictation;
+pub mod export;
 pub mod history;
+pub mod insertion;
 pub mod models;
+pub mod profiles;
 pub mod settings;
+pub mod transcription;
+pub mod vocabulary;
 pub mod voice_commands;
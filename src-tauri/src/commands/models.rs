@@ -2,7 +2,33 @@ use crate::commands::dictation::DictationState;
 use crate::constants::model::{self, AVAILABLE_MODELS};
 use crate::db::Database;
 use crate::models::ModelDownloader;
-use tauri::State;
+use crate::commands::settings::on_blocking_pool;
+use crate::settings::{Settings, SettingsState};
+use crate::whisper::transcriber::WhisperTranscriber;
+use tauri::{Manager, State};
+
+pub(crate) fn load_active_model(
+    app: &tauri::AppHandle,
+    state: &DictationState,
+    settings: &Settings,
+) -> Result<(), String> {
+    let path = ModelDownloader::get_model_path_by_id(app, &settings.active_model)
+        .map_err(|e| e.to_string())?;
+
+    if !path.exists() {
+        return Err("ملف النموذج غير موجود".to_string());
+    }
+
+    // Dictation keeps the old model until the new one is ready.
+    let ctx = WhisperTranscriber::open_model(&path, settings.use_gpu).map_err(|e| e.to_string())?;
+    state.transcriber.lock().map_err(|e| e.to_string())?.set_model(ctx);
+
+    tracing::info!(
+        "[model] Loaded model '{}' (GPU: {}): {:?}",
+        settings.active_model, settings.use_gpu, path
+    );
+    Ok(())
+}
 
 #[tauri::command]
 pub fn get_available_models(app: tauri::AppHandle) -> Result<serde_json::Value, String> {
@@ -46,9 +72,10 @@ pub async fn download_specific_model(
     app: tauri::AppHandle,
     model_id: String,
 ) -> Result<String, String> {
+    let model_dir = ModelDownloader::get_model_dir(&app).map_err(|e| e.to_string())?;
     let downloader = ModelDownloader::new();
     let path = downloader
-        .download_model_by_id(&app, &model_id)
+        .download_model_by_id(&model_dir, &model_id, &app)
         .await
         .map_err(|e| e.to_string())?;
 
@@ -56,15 +83,8 @@ pub async fn download_specific_model(
 }
 
 #[tauri::command]
-pub fn get_active_model(db: State<'_, Database>) -> Result<serde_json::Value, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let active_id = conn
-        .query_row(
-            "SELECT value FROM settings WHERE key = 'active_model'",
-            [],
-            |row| row.get::<_, String>(0),
-        )
-        .unwrap_or_else(|_| String::new());
+pub fn get_active_model(settings: State<'_, SettingsState>) -> Result<serde_json::Value, String> {
+    let active_id = settings.get().active_model.clone();
 
     if active_id.is_empty() {
         return Ok(serde_json::json!({ "id": null, "name": null }));
@@ -82,12 +102,7 @@ pub fn get_active_model(db: State<'_, Database>) -> Result<serde_json::Value, St
 }
 
 #[tauri::command]
-pub async fn set_active_model(
-    state: State<'_, DictationState>,
-    db: State<'_, Database>,
-    app: tauri::AppHandle,
-    model_id: String,
-) -> Result<(), String> {
+pub async fn set_active_model(app: tauri::AppHandle, model_id: String) -> Result<(), String> {
     let path = ModelDownloader::get_model_path_by_id(&app, &model_id)
         .map_err(|e| e.to_string())?;
 
@@ -95,28 +110,21 @@ pub async fn set_active_model(
         return Err("النموذج غير مثبّت، يرجى تحميله أولاً".to_string());
     }
 
-    let use_gpu = {
-        let conn = db.0.lock().map_err(|e| e.to_string())?;
-        conn.execute(
-            "INSERT INTO settings (key, value) VALUES ('active_model', ?1)
-             ON CONFLICT(key) DO UPDATE SET value = ?1",
-            rusqlite::params![&model_id],
-        )
-        .map_err(|e| e.to_string())?;
-
-        conn.query_row(
-            "SELECT value FROM settings WHERE key = 'use_gpu'",
-            [],
-            |row| row.get::<_, String>(0),
-        )
-        .unwrap_or_else(|_| "false".to_string()) == "true"
-    };
-
-    let mut transcriber = state.transcriber.lock().map_err(|e| e.to_string())?;
-    transcriber.load_model(&path, use_gpu).map_err(|e| e.to_string())?;
-
-    tracing::info!("[model] Active model set to '{}': {:?}", model_id, path);
-    Ok(())
+    on_blocking_pool(&app, move |app| {
+        let settings = app.state::<SettingsState>();
+        // A changed id is loaded by the transcriber observer; re-selecting the same model reloads it here.
+        let update = settings.set(&app.state::<Database>(), "active_model", &model_id)?;
+        if let Some(message) = update.failure_message() {
+            return Err(message);
+        }
+        if update.changes.is_empty() {
+            load_active_model(app, &app.state::<DictationState>(), &settings.get())?;
+        }
+
+        tracing::info!("[model] Active model set to '{}'", model_id);
+        Ok(())
+    })
+    .await
 }
 
 #[tauri::command]
@@ -144,64 +152,19 @@ pub async fn check_any_model_installed(app: tauri::AppHandle) -> Result<bool, St
 }
 
 #[tauri::command]
-pub async fn reload_model(
-    state: State<'_, DictationState>,
-    db: State<'_, Database>,
-    app: tauri::AppHandle,
-) -> Result<(), String> {
-    let (active_model_id, use_gpu) = {
-        let conn = db.0.lock().map_err(|e| e.to_string())?;
-        let model_id = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'active_model'",
-                [],
-                |row| row.get::<_, String>(0),
-            )
-            .unwrap_or_default();
-        let gpu = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = 'use_gpu'",
-                [],
-                |row| row.get::<_, String>(0),
-            )
-            .unwrap_or_else(|_| "false".to_string())
-            == "true";
-        (model_id, gpu)
-    };
-
-    if active_model_id.is_empty() {
-        return Err("لا يوجد نموذج نشط لإعادة تحميله".to_string());
-    }
-
-    let path = ModelDownloader::get_model_path_by_id(&app, &active_model_id)
-        .map_err(|e| e.to_string())?;
-
-    if !path.exists() {
-        return Err("ملف النموذج غير موجود".to_string());
-    }
-
-    let mut transcriber = state.transcriber.lock().map_err(|e| e.to_string())?;
-    transcriber
-        .load_model(&path, use_gpu)
-        .map_err(|e| e.to_string())?;
-
-    tracing::info!(
-        "[model] Reloaded model '{}' (GPU: {})",
-        active_model_id, use_gpu
-    );
-    Ok(())
+pub async fn reload_model(app: tauri::AppHandle) -> Result<(), String> {
+    on_blocking_pool(&app, |app| {
+        let settings = app.state::<SettingsState>().get();
+        if settings.active_model.is_empty() {
+            return Err("لا يوجد نموذج نشط لإعادة تحميله".to_string());
+        }
+
+        load_active_model(app, &app.state::<DictationState>(), &settings)
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn has_active_model(db: State<'_, Database>) -> Result<bool, String> {
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    let active_id = conn
-        .query_row(
-            "SELECT value FROM settings WHERE key = 'active_model'",
-            [],
-            |row| row.get::<_, String>(0),
-        )
-        .unwrap_or_else(|_| String::new());
-
-    Ok(!active_id.is_empty())
+pub fn has_active_model(settings: State<'_, SettingsState>) -> Result<bool, String> {
+    Ok(!settings.get().active_model.is_empty())
 }
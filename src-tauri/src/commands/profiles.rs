@@ -0,0 +1,35 @@
+use crate::commands::dictation::DictationState;
+use crate::db::models::AppProfile;
+use crate::db::Database;
+use crate::settings::profiles;
+use crate::window::ActiveWindow;
+use tauri::State;
+
+#[tauri::command]
+pub fn list_profiles(db: State<'_, Database>) -> Result<Vec<AppProfile>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    profiles::load(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn save_profile(db: State<'_, Database>, profile: AppProfile) -> Result<i64, String> {
+    profiles::validate(&profile)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let id = profiles::save(&conn, &profile).map_err(|e| e.to_string())?;
+    tracing::info!("[profile] Saved '{}' (id {})", profile.name.trim(), id);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn delete_profile(db: State<'_, Database>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM app_profiles WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// Queries the compositor, so it runs off the main thread.
+#[tauri::command]
+pub async fn get_active_window(state: State<'_, DictationState>) -> Result<Option<ActiveWindow>, String> {
+    Ok(state.window.active_window())
+}
@@ -1,7 +1,21 @@
 use crate::db::models::Setting;
 use crate::db::Database;
 use crate::security::keychain;
-use tauri::State;
+use crate::settings::{SettingFailure, Settings, SettingsState};
+use std::collections::HashMap;
+use tauri::{Manager, State};
+
+// Settings observers may reload the Whisper model, which takes seconds, so async commands that
+// write settings run the write on the blocking pool instead of a runtime worker.
+pub(crate) async fn on_blocking_pool<T: Send + 'static>(
+    app: &tauri::AppHandle,
+    task: impl FnOnce(&tauri::AppHandle) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || task(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
 
 #[tauri::command]
 pub fn get_all_settings(db: State<'_, Database>) -> Result<Vec<Setting>, String> {
@@ -56,27 +70,51 @@ pub fn get_setting(db: State<'_, Database>, key: String) -> Result<Option<String
     Ok(result)
 }
 
+// Err means nothing was saved; failures list observers that could not apply a saved value.
 #[tauri::command]
-pub fn update_setting(db: State<'_, Database>, key: String, value: String) -> Result<(), String> {
-    if keychain::is_api_key_setting(&key) && keychain::is_available() {
-        keychain::store_api_key(&key, &value)?;
-        let conn = db.0.lock().map_err(|e| e.to_string())?;
-        conn.execute(
-            "INSERT INTO settings (key, value, updated_at) VALUES (?1, '', datetime('now'))
-             ON CONFLICT(key) DO UPDATE SET value = '', updated_at = datetime('now')",
-            [&key],
-        )
-        .map_err(|e| e.to_string())?;
-        return Ok(());
+pub async fn update_setting(
+    app: tauri::AppHandle,
+    key: String,
+    value: String,
+) -> Result<Vec<SettingFailure>, String> {
+    on_blocking_pool(&app, move |app| {
+        let db = app.state::<Database>();
+        let settings = app.state::<SettingsState>();
+        let update = if keychain::is_api_key_setting(&key) && keychain::is_available() {
+            keychain::store_api_key(&key, &value)?;
+            settings.set(&db, &key, "")?
+        } else {
+            settings.set(&db, &key, &value)?
+        };
+        Ok(update.failures)
+    })
+    .await
+}
+
+// Saves the whole settings form in one batch so observers see the final state once.
+#[tauri::command]
+pub async fn update_settings(
+    app: tauri::AppHandle,
+    values: HashMap<String, String>,
+) -> Result<Vec<SettingFailure>, String> {
+    let mut entries = Vec::with_capacity(values.len());
+    for (key, value) in values {
+        if keychain::is_api_key_setting(&key) && keychain::is_available() {
+            keychain::store_api_key(&key, &value)?;
+            entries.push((key, String::new()));
+        } else {
+            entries.push((key, value));
+        }
     }
 
-    let conn = db.0.lock().map_err(|e| e.to_string())?;
-    conn.execute(
-        "INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, datetime('now'))
-         ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = datetime('now')",
-        [&key, &value],
-    )
-    .map_err(|e| e.to_string())?;
+    on_blocking_pool(&app, move |app| {
+        let update = app.state::<SettingsState>().set_many(&app.state::<Database>(), &entries)?;
+        Ok(update.failures)
+    })
+    .await
+}
 
-    Ok(())
+#[tauri::command]
+pub fn get_typed_settings(settings: State<'_, SettingsState>) -> Settings {
+    (*settings.get()).clone()
 }
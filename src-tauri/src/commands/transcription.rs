@@ -0,0 +1,114 @@
+use crate::audio::decoder;
+use crate::commands::dictation::{post_process_transcript, save_segments, save_to_history, DictationState};
+use crate::commands::vocabulary;
+use crate::constants::audio::SAMPLE_RATE;
+use crate::db::Database;
+use crate::events::{EventSink, PipelineEvent, TaskStatus};
+use crate::pipeline::file::transcribe_samples;
+use crate::settings::SettingsState;
+use crate::whisper::transcriber::TranscriptSegment;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{Manager, State};
+
+fn transcribe_path(
+    app: &tauri::AppHandle,
+    path: &Path,
+    label: &str,
+    noise_suppression: bool,
+) -> Result<(String, u64, Vec<TranscriptSegment>), String> {
+    let samples = decoder::decode_file(path).map_err(|e| e.to_string())?;
+    let duration = (samples.len() as f64 / SAMPLE_RATE as f64).round() as u64;
+    let state: State<'_, DictationState> = app.state();
+    let mut segments = transcribe_samples(&state.transcriber, &samples, noise_suppression, label, app)?;
+    let text = post_process_transcript(&state, &mut segments, false);
+    Ok((text, duration, segments))
+}
+
+// Clears is_transcribing however the transcription ends.
+struct TranscribingGuard<'a>(&'a AtomicBool);
+
+impl Drop for TranscribingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+#[tauri::command]
+pub async fn transcribe_file(
+    state: State<'_, DictationState>,
+    db: State<'_, Database>,
+    settings: State<'_, SettingsState>,
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<String, String> {
+    let file_path = PathBuf::from(&path);
+    if !file_path.is_file() {
+        return Err("الملف الصوتي غير موجود".to_string());
+    }
+
+    let _guard = {
+        let is_recording = state.is_recording.lock().map_err(|e| e.to_string())?;
+        if *is_recording {
+            return Err("لا يمكن تحويل ملف أثناء الإملاء".to_string());
+        }
+        if state.is_transcribing.swap(true, Ordering::SeqCst) {
+            return Err("يجري تحويل ملف آخر بالفعل".to_string());
+        }
+        TranscribingGuard(&state.is_transcribing)
+    };
+
+    let noise_suppression = settings.get().noise_suppression;
+
+    app.send(PipelineEvent::FileStatus {
+        status: TaskStatus::Started,
+        path: path.clone(),
+        text: None,
+        message: None,
+    });
+
+    let handle = app.clone();
+    let path_for_task = path.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        transcribe_path(&handle, Path::new(&path_for_task), &path_for_task, noise_suppression)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+
+    let (text, duration, segments) = match result {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::error!("[file] Transcription failed for '{}': {}", path, e);
+            app.send(PipelineEvent::FileStatus {
+                status: TaskStatus::Error,
+                path: path.clone(),
+                text: None,
+                message: Some(e.clone()),
+            });
+            return Err(e);
+        }
+    };
+
+    if !text.is_empty() {
+        vocabulary::record_usage(&state, &db, &text);
+        match save_to_history(&state, &db, &text, "", duration, "", 0, "file") {
+            Ok(history_id) => {
+                if let Err(e) = save_segments(&db, history_id, &segments) {
+                    tracing::error!("[file] Failed to save segments: {}", e);
+                }
+            }
+            Err(e) => tracing::error!("[file] Failed to save history: {}", e),
+        }
+    }
+
+    app.send(PipelineEvent::FileStatus {
+        status: TaskStatus::Completed,
+        path: path.clone(),
+        text: Some(text.clone()),
+        message: None,
+    });
+
+    tracing::info!("[file] Transcription completed: {} chars", text.len());
+    Ok(text)
+}
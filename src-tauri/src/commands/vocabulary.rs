@@ -0,0 +1,403 @@
+use crate::commands::dictation::DictationState;
+use crate::db::models::{CorrectionSuggestion, VocabularyPreview, VocabularyTerm};
+use crate::db::search::normalize_arabic;
+use crate::db::Database;
+use crate::pipeline::corrections;
+use crate::pipeline::vocabulary::estimate_tokens;
+use crate::settings::schema::LANGUAGES;
+use crate::settings::{Settings, SettingsState};
+use crate::whisper::transcriber::vocabulary_budget;
+use rusqlite::types::Type;
+use rusqlite::{Connection, OptionalExtension, Row};
+use std::sync::Arc;
+use tauri::State;
+
+const MAX_PRIORITY: i64 = 100;
+// Edits seen this many times are offered as glossary entries.
+const SUGGEST_AFTER: i64 = 2;
+
+fn from_row(row: &Row) -> rusqlite::Result<VocabularyTerm> {
+    let heard_as: String = row.get(2)?;
+    Ok(VocabularyTerm {
+        id: row.get(0)?,
+        term: row.get(1)?,
+        heard_as: serde_json::from_str(&heard_as)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, Type::Text, Box::new(e)))?,
+        language: row.get(3)?,
+        priority: row.get(4)?,
+        enabled: row.get(5)?,
+        hits: row.get(6)?,
+    })
+}
+
+pub fn load(conn: &Connection) -> rusqlite::Result<Vec<VocabularyTerm>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, term, heard_as, language, priority, enabled, hits FROM vocabulary
+         ORDER BY priority DESC, term COLLATE NOCASE",
+    )?;
+    let rows = stmt.query_map([], from_row)?;
+    rows.collect()
+}
+
+fn clean(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn clean_variants(term: &VocabularyTerm) -> Vec<String> {
+    let canonical = normalize_arabic(&clean(&term.term));
+    let mut variants: Vec<String> = Vec::new();
+    for variant in term.heard_as.iter().map(|v| clean(v)) {
+        let folded = normalize_arabic(&variant);
+        if variant.is_empty() || folded == canonical || variants.iter().any(|v| normalize_arabic(v) == folded) {
+            continue;
+        }
+        variants.push(variant);
+    }
+    variants
+}
+
+pub fn save(conn: &Connection, term: &VocabularyTerm) -> rusqlite::Result<i64> {
+    let heard_as = serde_json::to_string(&clean_variants(term))
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+    if term.id > 0 {
+        conn.execute(
+            "UPDATE vocabulary SET term = ?1, heard_as = ?2, language = ?3, priority = ?4, enabled = ?5,
+                updated_at = datetime('now')
+             WHERE id = ?6",
+            rusqlite::params![clean(&term.term), heard_as, term.language, term.priority, term.enabled, term.id],
+        )?;
+        return Ok(term.id);
+    }
+
+    conn.execute(
+        "INSERT INTO vocabulary (term, heard_as, language, priority, enabled) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![clean(&term.term), heard_as, term.language, term.priority, term.enabled],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+fn same_scope(a: &VocabularyTerm, b: &VocabularyTerm) -> bool {
+    a.language.is_none() || b.language.is_none() || a.language == b.language
+}
+
+// A spelling may only map to one term per language, otherwise the replacement pass is ambiguous.
+pub fn validate(term: &VocabularyTerm, existing: &[VocabularyTerm]) -> Result<(), String> {
+    let canonical = clean(&term.term);
+    if canonical.is_empty() {
+        return Err("المصطلح مطلوب".to_string());
+    }
+    if let Some(language) = &term.language {
+        if !LANGUAGES.contains(&language.as_str()) {
+            return Err(format!("لغة غير مدعومة: {}", language));
+        }
+    }
+    if term.priority.abs() > MAX_PRIORITY {
+        return Err(format!("الأولوية يجب أن تكون بين -{0} و {0}", MAX_PRIORITY));
+    }
+    if !term.enabled {
+        return Ok(());
+    }
+
+    let spellings: Vec<String> = std::iter::once(canonical).chain(clean_variants(term)).collect();
+    for other in existing.iter().filter(|o| o.id != term.id && o.enabled && same_scope(term, o)) {
+        let other_spellings: Vec<String> = std::iter::once(&other.term)
+            .chain(&other.heard_as)
+            .map(|s| normalize_arabic(&clean(s)))
+            .collect();
+        if let Some(spelling) = spellings.iter().find(|s| other_spellings.contains(&normalize_arabic(s))) {
+            return Err(format!("\"{}\" مستخدم بالفعل للمصطلح \"{}\"", spelling, other.term));
+        }
+    }
+    Ok(())
+}
+
+fn effective_settings(state: &DictationState, settings: &SettingsState) -> Arc<Settings> {
+    let session = state.session.lock().ok().and_then(|s| s.clone());
+    session.unwrap_or_else(|| settings.get())
+}
+
+// Rebuilds the Whisper prompt for the given language and free-text terms.
+pub(crate) fn apply(state: &DictationState, settings: &Settings) -> Result<(), String> {
+    let prompt = {
+        let mut glossary = state.vocabulary.lock().map_err(|e| e.to_string())?;
+        glossary.set_language(&settings.language);
+        glossary.set_extra_terms(&settings.custom_vocabulary);
+        glossary.prompt(vocabulary_budget(&settings.language))
+    };
+    tracing::debug!("[vocabulary] Prompt ({} tokens est.): '{}'", estimate_tokens(&prompt), prompt);
+    let mut transcriber = state.transcriber.lock().map_err(|e| e.to_string())?;
+    transcriber.set_vocabulary_prompt(&prompt);
+    Ok(())
+}
+
+pub(crate) fn reload(conn: &Connection, state: &DictationState, settings: &SettingsState) -> Result<(), String> {
+    let terms = load(conn).map_err(|e| e.to_string())?;
+    state.vocabulary.lock().map_err(|e| e.to_string())?.set_terms(terms);
+    apply(state, &effective_settings(state, settings))
+}
+
+pub(crate) fn correct(state: &DictationState, text: &str) -> String {
+    let Ok(glossary) = state.vocabulary.lock() else {
+        return text.to_string();
+    };
+    let (corrected, count) = glossary.correct(text);
+    if count > 0 {
+        tracing::debug!("[vocabulary] Corrected {} term(s): '{}' -> '{}'", count, text, corrected);
+    }
+    corrected
+}
+
+// Counts which glossary terms came up so frequently used ones rank higher in the prompt.
+pub(crate) fn record_usage(state: &DictationState, db: &Database, text: &str) {
+    let ids = match state.vocabulary.lock() {
+        Ok(mut glossary) => {
+            let ids = glossary.matched_ids(text);
+            glossary.record_hits(&ids);
+            ids
+        }
+        Err(_) => return,
+    };
+    if ids.is_empty() {
+        return;
+    }
+    let Ok(conn) = db.0.lock() else {
+        return;
+    };
+    for id in ids {
+        if let Err(e) = conn.execute("UPDATE vocabulary SET hits = hits + 1 WHERE id = ?1", [id]) {
+            tracing::warn!("[vocabulary] Failed to record usage for term {}: {}", id, e);
+        }
+    }
+}
+
+fn matching_term<'a>(terms: &'a [VocabularyTerm], corrected: &str, language: &str) -> Option<&'a VocabularyTerm> {
+    let folded = normalize_arabic(corrected);
+    terms
+        .iter()
+        .find(|t| normalize_arabic(&t.term) == folded && t.language.as_deref().is_none_or(|l| l == language))
+}
+
+fn suggestions(conn: &Connection, ids: Option<&[i64]>) -> rusqlite::Result<Vec<CorrectionSuggestion>> {
+    let terms = load(conn)?;
+    let mut stmt = conn.prepare(
+        "SELECT id, heard, corrected, language, count FROM correction_candidates
+         WHERE status = 'pending' AND count >= ?1
+         ORDER BY count DESC, last_seen_at DESC",
+    )?;
+    let rows = stmt.query_map([SUGGEST_AFTER], |row| {
+        Ok(CorrectionSuggestion {
+            id: row.get(0)?,
+            heard: row.get(1)?,
+            corrected: row.get(2)?,
+            language: row.get(3)?,
+            count: row.get(4)?,
+            term_id: None,
+        })
+    })?;
+    let mut result = Vec::new();
+    for row in rows {
+        let mut suggestion = row?;
+        if ids.is_some_and(|ids| !ids.contains(&suggestion.id)) {
+            continue;
+        }
+        suggestion.term_id = matching_term(&terms, &suggestion.corrected, &suggestion.language).map(|t| t.id);
+        result.push(suggestion);
+    }
+    Ok(result)
+}
+
+// Counts the word substitutions in a history edit and returns those that have now recurred.
+pub(crate) fn record_corrections(
+    conn: &Connection,
+    before: &str,
+    after: &str,
+    language: &str,
+) -> rusqlite::Result<Vec<CorrectionSuggestion>> {
+    let mut ids = Vec::new();
+    for (heard, corrected) in corrections::substitutions(before, after) {
+        let id: i64 = conn.query_row(
+            "INSERT INTO correction_candidates (heard, heard_key, corrected, language) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(heard_key, corrected, language)
+             DO UPDATE SET count = count + 1, last_seen_at = datetime('now')
+             RETURNING id",
+            rusqlite::params![heard, corrections::fold(&heard), corrected, language],
+            |row| row.get(0),
+        )?;
+        tracing::debug!("[vocabulary] Learned correction '{}' -> '{}'", heard, corrected);
+        ids.push(id);
+    }
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    suggestions(conn, Some(&ids))
+}
+
+#[tauri::command]
+pub fn list_vocabulary(db: State<'_, Database>) -> Result<Vec<VocabularyTerm>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn save_vocabulary_term(
+    db: State<'_, Database>,
+    state: State<'_, DictationState>,
+    settings: State<'_, SettingsState>,
+    term: VocabularyTerm,
+) -> Result<i64, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    validate(&term, &load(&conn).map_err(|e| e.to_string())?)?;
+    let id = save(&conn, &term).map_err(|e| e.to_string())?;
+    reload(&conn, &state, &settings)?;
+    tracing::info!("[vocabulary] Saved '{}' (id {})", clean(&term.term), id);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn delete_vocabulary_term(
+    db: State<'_, Database>,
+    state: State<'_, DictationState>,
+    settings: State<'_, SettingsState>,
+    id: i64,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM vocabulary WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    reload(&conn, &state, &settings)
+}
+
+// Shows what actually reaches Whisper for the current language, after the token budget.
+#[tauri::command]
+pub fn preview_vocabulary_prompt(
+    state: State<'_, DictationState>,
+    settings: State<'_, SettingsState>,
+) -> Result<VocabularyPreview, String> {
+    let settings = settings.get();
+    let budget = vocabulary_budget(&settings.language);
+    let glossary = state.vocabulary.lock().map_err(|e| e.to_string())?;
+    let (prompt, included, total) = glossary.prompt_with_stats(budget);
+    Ok(VocabularyPreview {
+        tokens: estimate_tokens(&prompt),
+        prompt,
+        budget,
+        included,
+        total,
+    })
+}
+
+#[tauri::command]
+pub fn list_correction_suggestions(db: State<'_, Database>) -> Result<Vec<CorrectionSuggestion>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    suggestions(&conn, None).map_err(|e| e.to_string())
+}
+
+// Adds the heard spelling to the matching glossary term, or creates the term.
+#[tauri::command]
+pub fn accept_correction_suggestion(
+    db: State<'_, Database>,
+    state: State<'_, DictationState>,
+    settings: State<'_, SettingsState>,
+    id: i64,
+) -> Result<i64, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let (heard, corrected, language): (String, String, String) = conn
+        .query_row(
+            "SELECT heard, corrected, language FROM correction_candidates WHERE id = ?1",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "الاقتراح غير موجود".to_string())?;
+
+    let terms = load(&conn).map_err(|e| e.to_string())?;
+    let term = match matching_term(&terms, &corrected, &language) {
+        Some(existing) => {
+            let mut term = existing.clone();
+            term.heard_as.push(heard);
+            term
+        }
+        None => VocabularyTerm {
+            id: 0,
+            term: corrected,
+            heard_as: vec![heard],
+            language: Some(language),
+            priority: 0,
+            enabled: true,
+            hits: 0,
+        },
+    };
+    validate(&term, &terms)?;
+    let term_id = save(&conn, &term).map_err(|e| e.to_string())?;
+    conn.execute("UPDATE correction_candidates SET status = 'accepted' WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    reload(&conn, &state, &settings)?;
+    tracing::info!("[vocabulary] Accepted suggestion {} into term {}", id, term_id);
+    Ok(term_id)
+}
+
+#[tauri::command]
+pub fn dismiss_correction_suggestion(db: State<'_, Database>, id: i64) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("UPDATE correction_candidates SET status = 'dismissed' WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{migrations, search};
+
+    fn open() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        search::register_functions(&conn).unwrap();
+        migrations::run_migrations(&mut conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn suggests_after_repeated_edits() {
+        let conn = open();
+        for seen in 1..SUGGEST_AFTER {
+            let suggestions = record_corrections(&conn, "open kay tab", "open Kateb", "en").unwrap();
+            assert!(suggestions.is_empty(), "suggested after {} edit(s)", seen);
+        }
+
+        let suggestions = record_corrections(&conn, "open KAY tab", "open Kateb", "en").unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].heard, "kay tab");
+        assert_eq!(suggestions[0].corrected, "Kateb");
+        assert_eq!(suggestions[0].count, SUGGEST_AFTER);
+        assert_eq!(suggestions[0].term_id, None);
+    }
+
+    #[test]
+    fn counts_each_language_separately() {
+        let conn = open();
+        for language in LANGUAGES.iter().cycle().take(LANGUAGES.len() * (SUGGEST_AFTER as usize - 1)) {
+            record_corrections(&conn, "open kay tab", "open Kateb", language).unwrap();
+        }
+        assert!(suggestions(&conn, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn points_suggestions_at_existing_terms() {
+        let conn = open();
+        let term = VocabularyTerm {
+            id: 0,
+            term: "Kateb".to_string(),
+            heard_as: Vec::new(),
+            language: None,
+            priority: 0,
+            enabled: true,
+            hits: 0,
+        };
+        let id = save(&conn, &term).unwrap();
+        for _ in 0..SUGGEST_AFTER {
+            record_corrections(&conn, "open kay tab", "open Kateb", "ar").unwrap();
+        }
+        assert_eq!(suggestions(&conn, None).unwrap()[0].term_id, Some(id));
+    }
+}
@@ -1,6 +1,24 @@
+use crate::commands::dictation::DictationState;
+use crate::db::models::{TriggerConflict, VoiceCommand, VoiceCommandAction, VoiceCommandImport};
+use crate::db::search::fold_char;
+use crate::db::Database;
+use chrono::{DateTime, Datelike, Local};
+use rusqlite::types::Type;
+use rusqlite::{Connection, Row};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+const EXPORT_FORMAT: &str = "kateb-voice-commands";
+const EXPORT_FORMAT_VERSION: u32 = 1;
+const MAX_DELETE_WORDS: usize = 50;
+
+const WEEKDAYS_AR: [&str; 7] = ["الاثنين", "الثلاثاء", "الأربعاء", "الخميس", "الجمعة", "السبت", "الأحد"];
+
 pub struct VoiceCommandProcessor {
     enabled: bool,
     language: String,
+    // Folded trigger keys, longest first so "new paragraph" wins over a shorter trigger inside it.
+    triggers: Vec<(Vec<char>, VoiceCommandAction)>,
 }
 
 pub struct ProcessResult {
@@ -10,23 +28,115 @@ pub struct ProcessResult {
     pub delete_count: usize,
 }
 
-struct VoiceCommand {
-    triggers: Vec<&'static str>,
-    action: CommandAction,
+fn command(name: &str, ar: &[&str], en: &[&str], action: VoiceCommandAction) -> VoiceCommand {
+    VoiceCommand {
+        id: 0,
+        name: name.to_string(),
+        triggers_ar: ar.iter().map(|t| t.to_string()).collect(),
+        triggers_en: en.iter().map(|t| t.to_string()).collect(),
+        action,
+        enabled: true,
+    }
+}
+
+fn insert(text: &str) -> VoiceCommandAction {
+    VoiceCommandAction::Insert { text: text.to_string() }
+}
+
+fn localized(ar: &str, en: &str) -> VoiceCommandAction {
+    VoiceCommandAction::InsertLocalized {
+        ar: ar.to_string(),
+        en: en.to_string(),
+    }
+}
+
+// Used as-is by the CLI; the voice_commands migration seeds its own copy into the database.
+pub fn builtin_commands() -> Vec<VoiceCommand> {
+    vec![
+        command("New line", &["سطر جديد"], &["new line", "newline"], insert("\n")),
+        command("New paragraph", &["فقرة جديدة"], &["new paragraph"], insert("\n\n")),
+        command("Period", &["نقطة"], &["period", "dot", "full stop"], localized(".", ".")),
+        command("Comma", &["فاصلة"], &["comma"], localized("،", ",")),
+        command("Question mark", &["علامة استفهام"], &["question mark"], localized("؟", "?")),
+        command(
+            "Exclamation mark",
+            &["علامة تعجب"],
+            &["exclamation mark", "exclamation point"],
+            insert("!"),
+        ),
+        command("Space", &["مسافة"], &["space"], insert(" ")),
+        command(
+            "Delete last word",
+            &["احذف", "تراجع"],
+            &["delete", "undo"],
+            VoiceCommandAction::DeleteWords { count: 1 },
+        ),
+    ]
+}
+
+fn trigger_key(trigger: &str) -> Vec<char> {
+    let words: Vec<&str> = trigger.split_whitespace().collect();
+    words.join(" ").chars().map(fold_char).collect()
+}
+
+fn is_word_boundary(c: char) -> bool {
+    c.is_whitespace()
+        || matches!(
+            c,
+            '.' | ',' | '?' | '!' | ';' | ':' | '،' | '؟' | '؛' | '"' | '\'' | '(' | ')' | '-'
+        )
 }
 
-enum CommandAction {
-    Insert(&'static str),
-    InsertLocalized { ar: &'static str, en: &'static str },
-    DeleteLastWord,
+fn delete_words(text: &mut String, count: usize) {
+    for _ in 0..count {
+        let trimmed = text.trim_end();
+        let cut = trimmed.rfind(char::is_whitespace).unwrap_or(0);
+        text.truncate(cut);
+    }
+}
+
+fn expand_template(template: &str, language: &str, now: &DateTime<Local>) -> String {
+    let weekday = if language == "ar" {
+        WEEKDAYS_AR[now.weekday().num_days_from_monday() as usize].to_string()
+    } else {
+        now.format("%A").to_string()
+    };
+    template
+        .replace("{datetime}", &now.format("%Y-%m-%d %H:%M").to_string())
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &now.format("%H:%M").to_string())
+        .replace("{year}", &now.format("%Y").to_string())
+        .replace("{month}", &now.format("%m").to_string())
+        .replace("{day}", &now.format("%d").to_string())
+        .replace("{weekday}", &weekday)
+}
+
+fn clean_extra_spaces(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut prev_space = false;
+    for ch in text.chars() {
+        if ch == ' ' {
+            if !prev_space {
+                result.push(ch);
+            }
+            prev_space = true;
+        } else {
+            prev_space = false;
+            result.push(ch);
+        }
+    }
+    result
 }
 
 impl VoiceCommandProcessor {
     pub fn new(enabled: bool) -> Self {
-        Self {
+        let mut processor = Self {
             enabled,
             language: "ar".to_string(),
-        }
+            triggers: Vec::new(),
+        };
+        processor.set_commands(builtin_commands());
+        processor
     }
 
     pub fn set_enabled(&mut self, enabled: bool) {
@@ -37,8 +147,38 @@ impl VoiceCommandProcessor {
         self.language = language.to_string();
     }
 
+    pub fn set_commands(&mut self, commands: Vec<VoiceCommand>) {
+        let mut triggers: Vec<(Vec<char>, VoiceCommandAction)> = commands
+            .into_iter()
+            .filter(|c| c.enabled)
+            .flat_map(|c| {
+                let action = c.action;
+                c.triggers_ar
+                    .into_iter()
+                    .chain(c.triggers_en)
+                    .map(|t| trigger_key(&t))
+                    .filter(|key| !key.is_empty())
+                    .map(move |key| (key, action.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        triggers.sort_by_key(|t| std::cmp::Reverse(t.0.len()));
+        self.triggers = triggers;
+    }
+
+    fn match_at(&self, folded: &[char], pos: usize) -> Option<(usize, &VoiceCommandAction)> {
+        self.triggers.iter().find_map(|(key, action)| {
+            let end = pos + key.len();
+            let matched = end <= folded.len()
+                && folded[pos..end] == key[..]
+                && (end == folded.len() || is_word_boundary(folded[end]));
+            matched.then_some((end, action))
+        })
+    }
+
+    // Single left-to-right pass, so text inserted by one command is never matched by another.
     pub fn process_text(&self, text: &str, language: &str) -> ProcessResult {
-        if !self.enabled || text.trim().is_empty() {
+        if !self.enabled || text.trim().is_empty() || self.triggers.is_empty() {
             return ProcessResult {
                 text: text.to_string(),
                 had_commands: false,
@@ -46,168 +186,434 @@ impl VoiceCommandProcessor {
             };
         }
 
-        let commands = Self::get_commands();
-        let mut result = text.to_string();
+        let chars: Vec<char> = text.chars().collect();
+        let folded: Vec<char> = chars.iter().map(|c| fold_char(*c)).collect();
+        let now = Local::now();
+        let mut result = String::with_capacity(text.len());
         let mut had_commands = false;
         let mut delete_count: usize = 0;
+        let mut pos = 0;
 
-        for cmd in &commands {
-            for trigger in &cmd.triggers {
-                let replaced = Self::replace_standalone(&result, trigger, &cmd.action, language);
-                if replaced.0 != result {
-                    had_commands = true;
-                    delete_count += replaced.1;
-                    result = replaced.0;
+        while pos < chars.len() {
+            let at_boundary = pos == 0 || is_word_boundary(folded[pos - 1]);
+            let Some((end, action)) = at_boundary.then(|| self.match_at(&folded, pos)).flatten() else {
+                result.push(chars[pos]);
+                pos += 1;
+                continue;
+            };
+
+            match action {
+                VoiceCommandAction::Insert { text } => result.push_str(text),
+                VoiceCommandAction::InsertLocalized { ar, en } => {
+                    result.push_str(if language == "ar" { ar } else { en })
+                }
+                VoiceCommandAction::DeleteWords { count } => {
+                    delete_words(&mut result, *count);
+                    delete_count += count;
+                }
+                VoiceCommandAction::Template { template } => {
+                    result.push_str(&expand_template(template, language, &now))
                 }
             }
+            had_commands = true;
+            pos = end;
         }
 
-        result = Self::clean_extra_spaces(&result);
-
         ProcessResult {
-            text: result,
+            text: clean_extra_spaces(&result),
             had_commands,
             delete_count,
         }
     }
+}
 
-    fn get_commands() -> Vec<VoiceCommand> {
-        vec![
-            VoiceCommand {
-                triggers: vec!["سطر جديد", "new line", "newline"],
-                action: CommandAction::Insert("\n"),
-            },
-            VoiceCommand {
-                triggers: vec!["فقرة جديدة", "new paragraph"],
-                action: CommandAction::Insert("\n\n"),
-            },
-            VoiceCommand {
-                triggers: vec!["نقطة", "period", "dot", "full stop"],
-                action: CommandAction::InsertLocalized { ar: ".", en: "." },
-            },
-            VoiceCommand {
-                triggers: vec!["فاصلة", "comma"],
-                action: CommandAction::InsertLocalized {
-                    ar: "،",
-                    en: ",",
-                },
-            },
-            VoiceCommand {
-                triggers: vec!["علامة استفهام", "question mark"],
-                action: CommandAction::InsertLocalized {
-                    ar: "؟",
-                    en: "?",
-                },
-            },
-            VoiceCommand {
-                triggers: vec!["علامة تعجب", "exclamation mark", "exclamation point"],
-                action: CommandAction::Insert("!"),
-            },
-            VoiceCommand {
-                triggers: vec!["مسافة", "space"],
-                action: CommandAction::Insert(" "),
-            },
-            VoiceCommand {
-                triggers: vec!["احذف", "تراجع", "delete", "undo"],
-                action: CommandAction::DeleteLastWord,
-            },
-        ]
-    }
-
-    fn is_word_boundary(c: char) -> bool {
-        c.is_whitespace()
-            || matches!(
-                c,
-                '.' | ',' | '?' | '!' | ';' | ':' | '،' | '؟' | '؛' | '"' | '\'' | '(' | ')'
-                    | '-'
-            )
-    }
-
-    fn replace_standalone(
-        text: &str,
-        trigger: &str,
-        action: &CommandAction,
-        language: &str,
-    ) -> (String, usize) {
-        let lower_text = text.to_lowercase();
-        let lower_trigger = trigger.to_lowercase();
-        let mut result = String::new();
-        let mut delete_count: usize = 0;
-        let mut last_end = 0;
-
-        let trigger_len = lower_trigger.len();
-
-        let mut search_start = 0;
-        while let Some(pos) = lower_text[search_start..].find(&lower_trigger) {
-            let abs_pos = search_start + pos;
-            let end_pos = abs_pos + trigger_len;
-
-            let before_ok = abs_pos == 0
-                || lower_text[..abs_pos]
-                    .chars()
-                    .last()
-                    .map_or(true, |c| Self::is_word_boundary(c));
-
-            let after_ok = end_pos >= lower_text.len()
-                || lower_text[end_pos..]
-                    .chars()
-                    .next()
-                    .map_or(true, |c| Self::is_word_boundary(c));
-
-            if before_ok && after_ok {
-                result.push_str(&text[last_end..abs_pos]);
-
-                match action {
-                    CommandAction::Insert(s) => {
-                        result.push_str(s);
-                    }
-                    CommandAction::InsertLocalized { ar, en } => {
-                        if language == "ar" {
-                            result.push_str(ar);
-                        } else {
-                            result.push_str(en);
-                        }
-                    }
-                    CommandAction::DeleteLastWord => {
-                        let trimmed = result.trim_end();
-                        if let Some(last_space) = trimmed.rfind(|c: char| c.is_whitespace()) {
-                            result = trimmed[..last_space].to_string();
-                        } else {
-                            result.clear();
-                        }
-                        delete_count += 1;
-                    }
-                }
+fn json_column<T: serde::de::DeserializeOwned>(row: &Row, idx: usize) -> rusqlite::Result<T> {
+    let raw: String = row.get(idx)?;
+    serde_json::from_str(&raw)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, Type::Text, Box::new(e)))
+}
+
+fn to_json<T: Serialize>(value: &T) -> rusqlite::Result<String> {
+    serde_json::to_string(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))
+}
+
+fn from_row(row: &Row) -> rusqlite::Result<VoiceCommand> {
+    Ok(VoiceCommand {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        triggers_ar: json_column(row, 2)?,
+        triggers_en: json_column(row, 3)?,
+        action: json_column(row, 4)?,
+        enabled: row.get(5)?,
+    })
+}
+
+pub fn load(conn: &Connection) -> rusqlite::Result<Vec<VoiceCommand>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, triggers_ar, triggers_en, action, enabled FROM voice_commands ORDER BY id",
+    )?;
+    let rows = stmt.query_map([], from_row)?;
+    rows.collect()
+}
 
-                last_end = end_pos;
-                search_start = end_pos;
+fn clean_triggers(triggers: &[String]) -> Vec<String> {
+    triggers
+        .iter()
+        .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+pub fn save(conn: &Connection, command: &VoiceCommand) -> rusqlite::Result<i64> {
+    let triggers_ar = to_json(&clean_triggers(&command.triggers_ar))?;
+    let triggers_en = to_json(&clean_triggers(&command.triggers_en))?;
+    let action = to_json(&command.action)?;
+
+    if command.id > 0 {
+        conn.execute(
+            "UPDATE voice_commands SET name = ?1, triggers_ar = ?2, triggers_en = ?3, action = ?4, enabled = ?5,
+                updated_at = datetime('now')
+             WHERE id = ?6",
+            rusqlite::params![
+                command.name.trim(),
+                triggers_ar,
+                triggers_en,
+                action,
+                command.enabled,
+                command.id,
+            ],
+        )?;
+        return Ok(command.id);
+    }
+
+    conn.execute(
+        "INSERT INTO voice_commands (name, triggers_ar, triggers_en, action, enabled) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![command.name.trim(), triggers_ar, triggers_en, action, command.enabled],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn validate(command: &VoiceCommand) -> Result<(), String> {
+    let triggers: Vec<String> = clean_triggers(&command.triggers_ar)
+        .into_iter()
+        .chain(clean_triggers(&command.triggers_en))
+        .collect();
+    if triggers.is_empty() {
+        return Err("يجب تحديد عبارة تشغيل واحدة على الأقل".to_string());
+    }
+    if let Some(t) = triggers.iter().find(|t| t.chars().all(is_word_boundary)) {
+        return Err(format!("عبارة التشغيل يجب أن تحتوي على كلمة: \"{}\"", t));
+    }
+
+    match &command.action {
+        VoiceCommandAction::Insert { text } if text.is_empty() => Err("النص المُدرج مطلوب".to_string()),
+        VoiceCommandAction::InsertLocalized { ar, en } if ar.is_empty() || en.is_empty() => {
+            Err("النص العربي والإنجليزي مطلوبان".to_string())
+        }
+        VoiceCommandAction::DeleteWords { count } if *count == 0 || *count > MAX_DELETE_WORDS => {
+            Err(format!("عدد الكلمات المحذوفة يجب أن يكون بين 1 و {}", MAX_DELETE_WORDS))
+        }
+        VoiceCommandAction::Template { template } if template.trim().is_empty() => {
+            Err("نص القالب مطلوب".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn contains_words(longer: &[char], shorter: &[char]) -> bool {
+    let longer: String = longer.iter().collect();
+    let shorter: String = shorter.iter().collect();
+    let longer: Vec<&str> = longer.split(' ').collect();
+    let shorter: Vec<&str> = shorter.split(' ').collect();
+    longer.windows(shorter.len()).any(|w| w == shorter.as_slice())
+}
+
+// Duplicates make one command unreachable; overlaps only mean the longer trigger takes precedence.
+pub fn find_conflicts(commands: &[VoiceCommand]) -> Vec<TriggerConflict> {
+    let entries: Vec<(&VoiceCommand, &String, Vec<char>)> = commands
+        .iter()
+        .filter(|c| c.enabled)
+        .flat_map(|c| {
+            c.triggers_ar
+                .iter()
+                .chain(&c.triggers_en)
+                .map(move |t| (c, t, trigger_key(t)))
+        })
+        .filter(|(_, _, key)| !key.is_empty())
+        .collect();
+
+    let mut conflicts = Vec::new();
+    for (i, (command, trigger, key)) in entries.iter().enumerate() {
+        for (other, other_trigger, other_key) in &entries[i + 1..] {
+            let kind = if key == other_key {
+                "duplicate"
+            } else if (key.len() > other_key.len() && contains_words(key, other_key))
+                || (other_key.len() > key.len() && contains_words(other_key, key))
+            {
+                "overlap"
             } else {
-                let next_char_len = lower_text[abs_pos..]
-                    .chars()
-                    .next()
-                    .map_or(1, |c| c.len_utf8());
-                search_start = abs_pos + next_char_len;
-            }
+                continue;
+            };
+            conflicts.push(TriggerConflict {
+                kind: kind.to_string(),
+                trigger: trigger.to_string(),
+                other_trigger: other_trigger.to_string(),
+                command_id: command.id,
+                command_name: command.name.clone(),
+                other_id: other.id,
+                other_name: other.name.clone(),
+            });
         }
+    }
+    conflicts
+}
 
-        result.push_str(&text[last_end..]);
-        (result, delete_count)
+fn check_duplicates(command: &VoiceCommand, existing: &[VoiceCommand]) -> Result<(), String> {
+    let mut all: Vec<VoiceCommand> = existing.iter().filter(|c| c.id != command.id).cloned().collect();
+    all.push(command.clone());
+
+    let duplicate = find_conflicts(&all)
+        .into_iter()
+        .find(|c| c.kind == "duplicate" && (c.command_id == command.id || c.other_id == command.id));
+    match duplicate {
+        Some(c) if c.command_id == c.other_id => {
+            Err(format!("عبارة التشغيل \"{}\" مكررة في نفس الأمر", c.trigger))
+        }
+        Some(c) => {
+            let other = if c.command_id == command.id { c.other_name } else { c.command_name };
+            Err(format!("عبارة التشغيل \"{}\" مستخدمة في الأمر \"{}\"", c.trigger, other))
+        }
+        None => Ok(()),
     }
+}
 
-    fn clean_extra_spaces(text: &str) -> String {
-        let mut result = String::with_capacity(text.len());
-        let mut prev_space = false;
-        for ch in text.chars() {
-            if ch == ' ' {
-                if !prev_space {
-                    result.push(ch);
-                }
-                prev_space = true;
+pub(crate) fn reload(conn: &Connection, state: &DictationState) -> Result<(), String> {
+    let commands = load(conn).map_err(|e| e.to_string())?;
+    let mut vc = state.voice_commands.lock().map_err(|e| e.to_string())?;
+    vc.set_commands(commands);
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct VoiceCommandExport {
+    format: String,
+    format_version: u32,
+    commands: Vec<VoiceCommand>,
+}
+
+#[tauri::command]
+pub fn list_voice_commands(db: State<'_, Database>) -> Result<Vec<VoiceCommand>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    load(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn save_voice_command(
+    db: State<'_, Database>,
+    state: State<'_, DictationState>,
+    command: VoiceCommand,
+) -> Result<i64, String> {
+    validate(&command)?;
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    if command.enabled {
+        check_duplicates(&command, &load(&conn).map_err(|e| e.to_string())?)?;
+    }
+    let id = save(&conn, &command).map_err(|e| e.to_string())?;
+    reload(&conn, &state)?;
+    tracing::info!("[voice_commands] Saved '{}' (id {})", command.name.trim(), id);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn delete_voice_command(
+    db: State<'_, Database>,
+    state: State<'_, DictationState>,
+    id: i64,
+) -> Result<(), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM voice_commands WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    reload(&conn, &state)
+}
+
+#[tauri::command]
+pub fn get_voice_command_conflicts(db: State<'_, Database>) -> Result<Vec<TriggerConflict>, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let commands = load(&conn).map_err(|e| e.to_string())?;
+    Ok(find_conflicts(&commands))
+}
+
+#[tauri::command]
+pub fn export_voice_commands(db: State<'_, Database>) -> Result<String, String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    let commands = load(&conn)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|c| VoiceCommand { id: 0, ..c })
+        .collect();
+    let export = VoiceCommandExport {
+        format: EXPORT_FORMAT.to_string(),
+        format_version: EXPORT_FORMAT_VERSION,
+        commands,
+    };
+    serde_json::to_string_pretty(&export).map_err(|e| e.to_string())
+}
+
+// Invalid commands and ones whose triggers duplicate an existing command are skipped.
+#[tauri::command]
+pub fn import_voice_commands(
+    db: State<'_, Database>,
+    state: State<'_, DictationState>,
+    json: String,
+    replace: bool,
+) -> Result<VoiceCommandImport, String> {
+    let parsed: VoiceCommandExport =
+        serde_json::from_str(&json).map_err(|e| format!("Invalid JSON: {}", e))?;
+    if parsed.format != EXPORT_FORMAT {
+        return Err(format!("صيغة ملف غير معروفة: {}", parsed.format));
+    }
+    if parsed.format_version > EXPORT_FORMAT_VERSION {
+        return Err(format!(
+            "إصدار الملف {} أحدث من المدعوم ({})",
+            parsed.format_version, EXPORT_FORMAT_VERSION
+        ));
+    }
+
+    let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let summary = import_commands(&tx, parsed.commands, replace)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    reload(&conn, &state)?;
+    tracing::info!(
+        "[voice_commands] Imported {} command(s), skipped {}",
+        summary.imported, summary.skipped
+    );
+    Ok(summary)
+}
+
+// Shared by file import and backup restore; the caller owns the transaction and the reload.
+pub(crate) fn import_commands(
+    conn: &Connection,
+    commands: Vec<VoiceCommand>,
+    replace: bool,
+) -> Result<VoiceCommandImport, String> {
+    if replace {
+        conn.execute("DELETE FROM voice_commands", []).map_err(|e| e.to_string())?;
+    }
+
+    let mut existing = load(conn).map_err(|e| e.to_string())?;
+    let mut summary = VoiceCommandImport { imported: 0, skipped: 0 };
+    for command in commands {
+        let command = VoiceCommand { id: 0, ..command };
+        let checked = validate(&command).and_then(|_| {
+            if command.enabled {
+                check_duplicates(&command, &existing)
             } else {
-                prev_space = false;
-                result.push(ch);
+                Ok(())
             }
+        });
+        if let Err(e) = checked {
+            tracing::warn!("[voice_commands] Skipping '{}': {}", command.name, e);
+            summary.skipped += 1;
+            continue;
+        }
+        let id = save(conn, &command).map_err(|e| e.to_string())?;
+        existing.push(VoiceCommand { id, ..command });
+        summary.imported += 1;
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{migrations, search};
+
+    fn open() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        search::register_functions(&conn).unwrap();
+        migrations::run_migrations(&mut conn).unwrap();
+        conn
+    }
+
+    fn user(id: i64, name: &str, ar: &[&str], en: &[&str]) -> VoiceCommand {
+        VoiceCommand {
+            id,
+            ..command(name, ar, en, insert("x"))
         }
-        result
+    }
+
+    #[test]
+    fn seeds_the_builtin_commands() {
+        let seeded = load(&open()).unwrap();
+        let builtin = builtin_commands();
+        assert_eq!(seeded.len(), builtin.len());
+        for (seeded, builtin) in seeded.iter().zip(&builtin) {
+            assert_eq!(seeded.name, builtin.name);
+            assert_eq!(seeded.triggers_ar, builtin.triggers_ar);
+            assert_eq!(seeded.triggers_en, builtin.triggers_en);
+            assert_eq!(seeded.action, builtin.action);
+            assert!(seeded.enabled);
+        }
+        assert!(find_conflicts(&seeded).iter().all(|c| c.kind != "duplicate"));
+    }
+
+    #[test]
+    fn finds_duplicate_and_overlapping_triggers() {
+        let commands = vec![
+            user(1, "Line", &["سطر جديد"], &["new line"]),
+            user(2, "Other line", &["سطر  جديد"], &[]),
+            user(3, "Line break", &[], &["New Line please"]),
+            user(4, "Lines", &[], &["new lines"]),
+        ];
+        let conflicts = find_conflicts(&commands);
+        let kinds: Vec<(&str, i64, i64)> = conflicts
+            .iter()
+            .map(|c| (c.kind.as_str(), c.command_id, c.other_id))
+            .collect();
+        assert_eq!(kinds, vec![("duplicate", 1, 2), ("overlap", 1, 3)]);
+    }
+
+    #[test]
+    fn ignores_disabled_commands() {
+        let mut disabled = user(2, "Other line", &[], &["new line"]);
+        disabled.enabled = false;
+        let commands = vec![user(1, "Line", &[], &["new line"]), disabled];
+        assert!(find_conflicts(&commands).is_empty());
+    }
+
+    #[test]
+    fn longer_trigger_wins() {
+        let processor = VoiceCommandProcessor::new(true);
+        assert_eq!(processor.process_text("one new paragraph two", "en").text, "one \n\n two");
+        assert_eq!(processor.process_text("مرحبا فاصلة عالم", "ar").text, "مرحبا ، عالم");
+        assert_eq!(processor.process_text("one two delete", "en").text, "one");
+        assert!(!processor.process_text("newlines", "en").had_commands);
+    }
+
+    #[test]
+    fn restoring_twice_adds_nothing() {
+        let conn = open();
+        let backup = vec![user(7, "Sign off", &[], &["sign off"])];
+
+        let first = import_commands(&conn, backup.clone(), false).unwrap();
+        let second = import_commands(&conn, backup, false).unwrap();
+        assert_eq!((first.imported, first.skipped), (1, 0));
+        assert_eq!((second.imported, second.skipped), (0, 1));
+        assert_eq!(load(&conn).unwrap().len(), builtin_commands().len() + 1);
+    }
+
+    #[test]
+    fn replacing_restores_the_backup_only() {
+        let conn = open();
+        let backup = vec![
+            user(0, "Sign off", &[], &["sign off"]),
+            user(0, "Empty", &[], &[]),
+        ];
+        let summary = import_commands(&conn, backup, true).unwrap();
+        assert_eq!((summary.imported, summary.skipped), (1, 1));
+        let names: Vec<String> = load(&conn).unwrap().into_iter().map(|c| c.name).collect();
+        assert_eq!(names, vec!["Sign off".to_string()]);
     }
 }
@@ -5,6 +5,10 @@ pub mod audio {
     pub const OVERLAP_SAMPLES: usize = (16000.0 * 0.75) as usize;
     pub const POLL_INTERVAL_MS: u64 = 250;
     pub const AUTO_STOP_SILENCE_SECS: f32 = 10.0;
+    // No auto-stop countdown during the first seconds of a recording.
+    pub const AUTO_STOP_GRACE_SECS: f32 = 5.0;
+    pub const FILE_WINDOW_SECS: f32 = 30.0;
+    pub const FILE_WINDOW_SAMPLES: usize = (16000.0 * FILE_WINDOW_SECS) as usize;
 }
 
 pub mod hallucination {
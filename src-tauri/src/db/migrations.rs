@@ -1,7 +1,136 @@
-use rusqlite::Connection;
+use crate::error::AppError;
+use crate::settings::schema::SCHEMA;
+use rusqlite::{Connection, OptionalExtension, Transaction};
 
-pub fn run_migrations(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+struct Migration {
+    version: i64,
+    name: &'static str,
+    up: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        up: initial_schema,
+    },
+    Migration {
+        version: 2,
+        name: "history_ai_columns",
+        up: history_ai_columns,
+    },
+    Migration {
+        version: 3,
+        name: "history_source_and_segments",
+        up: history_source_and_segments,
+    },
+    Migration {
+        version: 4,
+        name: "reset_legacy_defaults",
+        up: reset_legacy_defaults,
+    },
+    Migration {
+        version: 5,
+        name: "history_fts",
+        up: history_fts,
+    },
+    Migration {
+        version: 6,
+        name: "history_created_at_index",
+        up: history_created_at_index,
+    },
+    Migration {
+        version: 7,
+        name: "app_profiles",
+        up: app_profiles,
+    },
+    Migration {
+        version: 8,
+        name: "app_profiles_paste_chord",
+        up: app_profiles_paste_chord,
+    },
+    Migration {
+        version: 9,
+        name: "voice_commands",
+        up: voice_commands_table,
+    },
+    Migration {
+        version: 10,
+        name: "vocabulary",
+        up: vocabulary_table,
+    },
+    Migration {
+        version: 11,
+        name: "history_edits",
+        up: history_edits,
+    },
+    Migration {
+        version: 12,
+        name: "app_profiles_decoding",
+        up: app_profiles_decoding,
+    },
+    Migration {
+        version: 13,
+        name: "history_fts_index_in_app",
+        up: history_fts_index_in_app,
+    },
+];
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub fn schema_version(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )
+}
+
+pub fn run_migrations(conn: &mut Connection) -> Result<(), Box<dyn std::error::Error>> {
     conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        ",
+    )?;
+
+    let current = schema_version(conn)?;
+    let latest = latest_version();
+    if current > latest {
+        return Err(Box::new(AppError::SchemaTooNew(current, latest)));
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        (migration.up)(&tx)?;
+        tx.execute(
+            "INSERT INTO schema_version (version, name) VALUES (?1, ?2)",
+            rusqlite::params![migration.version, migration.name],
+        )?;
+        tx.commit()?;
+        tracing::info!(
+            "[db] Applied migration {} ({})",
+            migration.version,
+            migration.name
+        );
+    }
+
+    Ok(())
+}
+
+fn history_columns(tx: &Transaction) -> rusqlite::Result<Vec<String>> {
+    tx.prepare("PRAGMA table_info(dictation_history)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect()
+}
+
+fn initial_schema(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
         "
         CREATE TABLE IF NOT EXISTS settings (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -13,11 +142,8 @@ pub fn run_migrations(conn: &Connection) -> Result<(), Box<dyn std::error::Error
         CREATE TABLE IF NOT EXISTS dictation_history (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             text TEXT NOT NULL,
-            raw_text TEXT NOT NULL DEFAULT '',
             duration INTEGER NOT NULL,
             language TEXT NOT NULL,
-            ai_provider TEXT NOT NULL DEFAULT '',
-            processing_time_ms INTEGER NOT NULL DEFAULT 0,
             created_at TEXT NOT NULL DEFAULT (datetime('now'))
         );
 
@@ -29,75 +155,322 @@ pub fn run_migrations(conn: &Connection) -> Result<(), Box<dyn std::error::Error
             total_duration INTEGER NOT NULL DEFAULT 0
         );
         ",
-    )?;
-    let columns: Vec<String> = conn
-        .prepare("PRAGMA table_info(dictation_history)")?
-        .query_map([], |row| row.get::<_, String>(1))?
-        .filter_map(|r| r.ok())
-        .collect();
+    )
+}
 
+// Databases created before versioning may already have these columns.
+fn history_ai_columns(tx: &Transaction) -> rusqlite::Result<()> {
+    let columns = history_columns(tx)?;
     if !columns.iter().any(|c| c == "raw_text") {
-        conn.execute_batch(
-            "
-            ALTER TABLE dictation_history ADD COLUMN raw_text TEXT NOT NULL DEFAULT '';
-            ALTER TABLE dictation_history ADD COLUMN ai_provider TEXT NOT NULL DEFAULT '';
-            ALTER TABLE dictation_history ADD COLUMN processing_time_ms INTEGER NOT NULL DEFAULT 0;
-            ",
+        tx.execute_batch(
+            "ALTER TABLE dictation_history ADD COLUMN raw_text TEXT NOT NULL DEFAULT '';",
         )?;
     }
+    if !columns.iter().any(|c| c == "ai_provider") {
+        tx.execute_batch(
+            "ALTER TABLE dictation_history ADD COLUMN ai_provider TEXT NOT NULL DEFAULT '';",
+        )?;
+    }
+    if !columns.iter().any(|c| c == "processing_time_ms") {
+        tx.execute_batch(
+            "ALTER TABLE dictation_history ADD COLUMN processing_time_ms INTEGER NOT NULL DEFAULT 0;",
+        )?;
+    }
+    Ok(())
+}
 
-    conn.execute(
-        "UPDATE settings SET value = 'false' WHERE key = 'auto_stop_silence' AND value = 'true'",
-        [],
-    )?;
-    conn.execute(
-        "UPDATE settings SET value = '10' WHERE key = 'auto_stop_seconds' AND value = '5'",
-        [],
-    )?;
-    conn.execute(
-        "UPDATE settings SET value = 'false' WHERE key = 'noise_suppression' AND value = 'true'",
-        [],
+fn history_source_and_segments(tx: &Transaction) -> rusqlite::Result<()> {
+    if !history_columns(tx)?.iter().any(|c| c == "source") {
+        tx.execute_batch(
+            "ALTER TABLE dictation_history ADD COLUMN source TEXT NOT NULL DEFAULT 'dictation';",
+        )?;
+    }
+
+    tx.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS dictation_segments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            history_id INTEGER NOT NULL REFERENCES dictation_history(id) ON DELETE CASCADE,
+            segment_index INTEGER NOT NULL,
+            start_ms INTEGER NOT NULL,
+            end_ms INTEGER NOT NULL,
+            text TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_dictation_segments_history
+            ON dictation_segments(history_id, segment_index);
+        ",
+    )
+}
+
+// Intentionally empty. The old startup code reset noise_suppression, auto_stop_silence and
+// auto_stop_seconds on every launch, so older databases already hold the reset values, and a
+// value set since can't be told apart from a user's own choice. Kept so versions don't shift.
+fn reset_legacy_defaults(_tx: &Transaction) -> rusqlite::Result<()> {
+    Ok(())
+}
+
+// Indexed columns hold arabic_normalize() output, registered in db::search.
+fn history_fts(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "
+        CREATE VIRTUAL TABLE IF NOT EXISTS dictation_history_fts USING fts5(
+            text,
+            raw_text,
+            tokenize = 'unicode61 remove_diacritics 2'
+        );
+
+        INSERT INTO dictation_history_fts (rowid, text, raw_text)
+            SELECT id, arabic_normalize(text), arabic_normalize(raw_text) FROM dictation_history;
+
+        CREATE TRIGGER IF NOT EXISTS dictation_history_fts_insert
+        AFTER INSERT ON dictation_history BEGIN
+            INSERT INTO dictation_history_fts (rowid, text, raw_text)
+            VALUES (new.id, arabic_normalize(new.text), arabic_normalize(new.raw_text));
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS dictation_history_fts_delete
+        AFTER DELETE ON dictation_history BEGIN
+            DELETE FROM dictation_history_fts WHERE rowid = old.id;
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS dictation_history_fts_update
+        AFTER UPDATE OF text, raw_text ON dictation_history BEGIN
+            UPDATE dictation_history_fts
+            SET text = arabic_normalize(new.text), raw_text = arabic_normalize(new.raw_text)
+            WHERE rowid = new.id;
+        END;
+        ",
+    )
+}
+
+fn history_created_at_index(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_dictation_history_created_at ON dictation_history(created_at);",
+    )
+}
+
+// NULL override columns inherit the global setting.
+fn app_profiles(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS app_profiles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            match_class TEXT NOT NULL DEFAULT '',
+            match_title TEXT NOT NULL DEFAULT '',
+            enabled INTEGER NOT NULL DEFAULT 1,
+            priority INTEGER NOT NULL DEFAULT 0,
+            language TEXT,
+            custom_vocabulary TEXT,
+            ai_refinement INTEGER,
+            ai_provider TEXT,
+            ai_instructions TEXT,
+            voice_commands INTEGER,
+            insertion_method TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        ",
+    )
+}
+
+fn app_profiles_paste_chord(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE app_profiles ADD COLUMN paste_chord TEXT;")
+}
+
+// Triggers and actions are stored as JSON, the same shape used for import/export. The seeded
+// rows are the built-in commands as they stood when this migration shipped.
+fn voice_commands_table(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS voice_commands (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL DEFAULT '',
+            triggers_ar TEXT NOT NULL DEFAULT '[]',
+            triggers_en TEXT NOT NULL DEFAULT '[]',
+            action TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        INSERT INTO voice_commands (name, triggers_ar, triggers_en, action) VALUES
+            ('New line', '["سطر جديد"]', '["new line","newline"]', '{"type":"insert","text":"\n"}'),
+            ('New paragraph', '["فقرة جديدة"]', '["new paragraph"]', '{"type":"insert","text":"\n\n"}'),
+            ('Period', '["نقطة"]', '["period","dot","full stop"]', '{"type":"insert_localized","ar":".","en":"."}'),
+            ('Comma', '["فاصلة"]', '["comma"]', '{"type":"insert_localized","ar":"،","en":","}'),
+            ('Question mark', '["علامة استفهام"]', '["question mark"]', '{"type":"insert_localized","ar":"؟","en":"?"}'),
+            ('Exclamation mark', '["علامة تعجب"]', '["exclamation mark","exclamation point"]', '{"type":"insert","text":"!"}'),
+            ('Space', '["مسافة"]', '["space"]', '{"type":"insert","text":" "}'),
+            ('Delete last word', '["احذف","تراجع"]', '["delete","undo"]', '{"type":"delete_words","count":1}');
+        "#,
+    )
+}
+
+// The free-text vocabulary splitter as it stood when the vocabulary migration shipped.
+fn legacy_vocabulary_terms(text: &str) -> Vec<String> {
+    text.split([',', '،', ';', '؛', '\n'])
+        .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+// Moves the old free-text global vocabulary into the table; app profiles keep their own field.
+fn vocabulary_table(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS vocabulary (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            term TEXT NOT NULL,
+            heard_as TEXT NOT NULL DEFAULT '[]',
+            language TEXT,
+            priority INTEGER NOT NULL DEFAULT 0,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            hits INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        ",
     )?;
 
+    let legacy: Option<String> = tx
+        .query_row("SELECT value FROM settings WHERE key = 'custom_vocabulary'", [], |row| row.get(0))
+        .optional()?;
+    let Some(legacy) = legacy else {
+        return Ok(());
+    };
+    for term in legacy_vocabulary_terms(&legacy) {
+        tx.execute("INSERT INTO vocabulary (term) VALUES (?1)", [&term])?;
+    }
+    tx.execute("UPDATE settings SET value = '' WHERE key = 'custom_vocabulary'", [])?;
     Ok(())
 }
 
+// heard_key is the folded spelling so case and Arabic letter variants count as one correction.
+fn history_edits(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "
+        ALTER TABLE dictation_history ADD COLUMN original_text TEXT;
+
+        CREATE TABLE IF NOT EXISTS correction_candidates (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            heard TEXT NOT NULL,
+            heard_key TEXT NOT NULL,
+            corrected TEXT NOT NULL,
+            language TEXT NOT NULL,
+            count INTEGER NOT NULL DEFAULT 1,
+            status TEXT NOT NULL DEFAULT 'pending',
+            last_seen_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(heard_key, corrected, language)
+        );
+        ",
+    )
+}
+
+fn app_profiles_decoding(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch("ALTER TABLE app_profiles ADD COLUMN decoding_profile TEXT;")
+}
+
+// Inserts and edits are indexed by db::search::index_history; the delete trigger stays since it
+// needs no app-defined function.
+fn history_fts_index_in_app(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "
+        DROP TRIGGER IF EXISTS dictation_history_fts_insert;
+        DROP TRIGGER IF EXISTS dictation_history_fts_update;
+        ",
+    )
+}
+
 pub fn seed_default_settings(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
-    let defaults = vec![
-        ("shortcut", r#""Ctrl+Shift+D""#),
-        ("language", r#""ar""#),
-        ("auto_punctuation", "true"),
-        ("sound_notifications", "true"),
-        ("auto_start", "false"),
-        ("whisper_model", r#""large-v3""#),
-        ("max_recording_duration", "300"),
-        ("auto_type", "true"),
-        ("use_gpu", "false"),
-        ("ai_refinement", "false"),
-        ("ai_provider", "local"),
-        ("claude_api_key", ""),
-        ("openai_api_key", ""),
-        ("gemini_api_key", ""),
-        ("grok_api_key", ""),
-        ("local_api_key", ""),
-        ("claude_api_url", ""),
-        ("openai_api_url", ""),
-        ("gemini_api_url", ""),
-        ("grok_api_url", ""),
-        ("local_api_url", ""),
-        ("auto_stop_silence", "false"),
-        ("auto_stop_seconds", "10"),
-        ("custom_vocabulary", ""),
-        ("noise_suppression", "false"),
-        ("voice_commands", "true"),
-    ];
-
-    for (key, value) in defaults {
+    for spec in SCHEMA {
         conn.execute(
             "INSERT OR IGNORE INTO settings (key, value) VALUES (?1, ?2)",
-            [key, value],
+            [spec.key, spec.stored_default().as_str()],
         )?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::search;
+
+    fn open() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        search::register_functions(&conn).unwrap();
+        conn
+    }
+
+    fn setting(conn: &Connection, key: &str) -> String {
+        conn.query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn runs_each_step_once() {
+        let mut conn = open();
+        run_migrations(&mut conn).unwrap();
+        run_migrations(&mut conn).unwrap();
+
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+        let applied: i64 = conn
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(applied, MIGRATIONS.len() as i64);
+        let seeded: i64 = conn
+            .query_row("SELECT COUNT(*) FROM voice_commands", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(seeded, 8);
+    }
+
+    #[test]
+    fn rejects_newer_schema() {
+        let mut conn = open();
+        run_migrations(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO schema_version (version, name) VALUES (?1, 'future')",
+            [latest_version() + 1],
+        )
+        .unwrap();
+
+        let err = run_migrations(&mut conn).unwrap_err();
+        match err.downcast_ref::<AppError>() {
+            Some(AppError::SchemaTooNew(current, latest)) => {
+                assert_eq!(*current, latest_version() + 1);
+                assert_eq!(*latest, latest_version());
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn keeps_legacy_settings() {
+        let mut conn = open();
+        conn.execute_batch(
+            "
+            CREATE TABLE settings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                key TEXT UNIQUE NOT NULL,
+                value TEXT NOT NULL,
+                updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+            );
+            INSERT INTO settings (key, value) VALUES
+                ('noise_suppression', 'true'),
+                ('auto_stop_silence', 'true'),
+                ('auto_stop_seconds', '5');
+            ",
+        )
+        .unwrap();
+
+        run_migrations(&mut conn).unwrap();
+        seed_default_settings(&conn).unwrap();
+
+        assert_eq!(setting(&conn, "noise_suppression"), "true");
+        assert_eq!(setting(&conn, "auto_stop_silence"), "true");
+        assert_eq!(setting(&conn, "auto_stop_seconds"), "5");
+    }
+}
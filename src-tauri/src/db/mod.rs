@@ -1,5 +1,6 @@
 pub mod migrations;
 pub mod models;
+pub mod search;
 
 use rusqlite::Connection;
 use std::sync::Mutex;
@@ -15,9 +16,11 @@ pub fn init_database(app: &AppHandle) -> Result<(), Box<dyn std::error::Error>>
     std::fs::create_dir_all(&app_dir)?;
 
     let db_path = app_dir.join("dictation.db");
-    let conn = Connection::open(db_path)?;
+    let mut conn = Connection::open(db_path)?;
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    search::register_functions(&conn)?;
 
-    migrations::run_migrations(&conn)?;
+    migrations::run_migrations(&mut conn)?;
     migrations::seed_default_settings(&conn)?;
 
     app.manage(Database(Mutex::new(conn)));
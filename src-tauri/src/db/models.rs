@@ -17,7 +17,79 @@ pub struct DictationEntry {
     pub language: String,
     pub ai_provider: String,
     pub processing_time_ms: i64,
+    pub source: String,
     pub created_at: String,
+    // Text as first saved, kept once the user edits the entry.
+    #[serde(default)]
+    pub original_text: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnippetPart {
+    pub text: String,
+    pub highlighted: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistorySearchResult {
+    #[serde(flatten)]
+    pub entry: DictationEntry,
+    pub score: f64,
+    pub snippet: Vec<SnippetPart>,
+    pub snippet_field: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistorySort {
+    Newest,
+    Oldest,
+    Longest,
+    Shortest,
+    Relevance,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    pub search: Option<String>,
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    pub language: Option<String>,
+    pub ai_provider: Option<String>,
+    pub min_duration: Option<i64>,
+    pub max_duration: Option<i64>,
+    pub refined: Option<bool>,
+    pub sort: Option<HistorySort>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryExportOptions {
+    pub format: String,
+    pub ids: Option<Vec<i64>>,
+    pub filter: Option<HistoryQuery>,
+    pub include_raw_text: bool,
+    pub include_timestamps: bool,
+    pub include_metadata: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HistoryPage {
+    pub items: Vec<HistorySearchResult>,
+    pub total: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DictationSegment {
+    pub id: i64,
+    pub history_id: i64,
+    pub segment_index: i64,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,3 +109,110 @@ pub struct SummaryStats {
     pub days_active: i64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppProfile {
+    #[serde(default)]
+    pub id: i64,
+    pub name: String,
+    #[serde(default)]
+    pub match_class: String,
+    #[serde(default)]
+    pub match_title: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub priority: i64,
+    pub language: Option<String>,
+    pub custom_vocabulary: Option<String>,
+    pub ai_refinement: Option<bool>,
+    pub ai_provider: Option<String>,
+    pub ai_instructions: Option<String>,
+    pub voice_commands: Option<bool>,
+    pub insertion_method: Option<String>,
+    #[serde(default)]
+    pub paste_chord: Option<String>,
+    #[serde(default)]
+    pub decoding_profile: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VoiceCommandAction {
+    Insert { text: String },
+    InsertLocalized { ar: String, en: String },
+    DeleteWords { count: usize },
+    Template { template: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceCommand {
+    #[serde(default)]
+    pub id: i64,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub triggers_ar: Vec<String>,
+    #[serde(default)]
+    pub triggers_en: Vec<String>,
+    pub action: VoiceCommandAction,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VocabularyTerm {
+    #[serde(default)]
+    pub id: i64,
+    pub term: String,
+    #[serde(default)]
+    pub heard_as: Vec<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub priority: i64,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub hits: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VocabularyPreview {
+    pub prompt: String,
+    pub tokens: usize,
+    pub budget: usize,
+    pub included: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CorrectionSuggestion {
+    pub id: i64,
+    pub heard: String,
+    pub corrected: String,
+    pub language: String,
+    pub count: i64,
+    // Existing glossary term the spelling would be added to; None creates a new term.
+    pub term_id: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TriggerConflict {
+    pub kind: String,
+    pub trigger: String,
+    pub other_trigger: String,
+    pub command_id: i64,
+    pub command_name: String,
+    pub other_id: i64,
+    pub other_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VoiceCommandImport {
+    pub imported: u32,
+    pub skipped: u32,
+}
+
+fn default_true() -> bool {
+    true
+}
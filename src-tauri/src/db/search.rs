@@ -0,0 +1,207 @@
+use crate::db::models::SnippetPart;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::Connection;
+
+const SNIPPET_WORDS: usize = 24;
+const SNIPPET_LEAD_WORDS: usize = 8;
+
+fn is_tashkeel(c: char) -> bool {
+    matches!(c, '\u{0610}'..='\u{061A}' | '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{0640}' | '\u{06D6}'..='\u{06ED}')
+}
+
+pub(crate) fn fold_char(c: char) -> char {
+    match c {
+        'أ' | 'إ' | 'آ' | 'ٱ' => 'ا',
+        'ى' | 'ئ' => 'ي',
+        'ؤ' => 'و',
+        'ة' => 'ه',
+        _ => c.to_lowercase().next().unwrap_or(c),
+    }
+}
+
+pub fn normalize_arabic(text: &str) -> String {
+    text.chars()
+        .filter(|c| !is_tashkeel(*c))
+        .map(fold_char)
+        .collect()
+}
+
+pub fn register_functions(conn: &Connection) -> rusqlite::Result<()> {
+    conn.create_scalar_function(
+        "arabic_normalize",
+        1,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let text: Option<String> = ctx.get(0)?;
+            Ok(text.map(|t| normalize_arabic(&t)))
+        },
+    )
+}
+
+// The index holds normalized text. It is written here rather than by triggers so that any
+// connection can change history, not only ones with arabic_normalize() registered.
+pub fn index_history(conn: &Connection, id: i64, text: &str, raw_text: &str) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM dictation_history_fts WHERE rowid = ?1", [id])?;
+    conn.execute(
+        "INSERT INTO dictation_history_fts (rowid, text, raw_text) VALUES (?1, ?2, ?3)",
+        rusqlite::params![id, normalize_arabic(text), normalize_arabic(raw_text)],
+    )?;
+    Ok(())
+}
+
+pub fn query_terms(query: &str) -> Vec<String> {
+    normalize_arabic(query)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+// Every term must match, each as a prefix, so partial words still find results.
+pub fn build_match_query(terms: &[String]) -> Option<String> {
+    if terms.is_empty() {
+        return None;
+    }
+    Some(
+        terms
+            .iter()
+            .map(|t| format!("\"{}\"*", t))
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || is_tashkeel(c)
+}
+
+fn word_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut start: Option<usize> = None;
+    for (i, c) in text.char_indices() {
+        match (is_word_char(c), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                ranges.push((s, i));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        ranges.push((s, text.len()));
+    }
+    ranges
+}
+
+fn push_part(parts: &mut Vec<SnippetPart>, text: &str, highlighted: bool) {
+    if text.is_empty() {
+        return;
+    }
+    match parts.last_mut() {
+        Some(last) if last.highlighted == highlighted => last.text.push_str(text),
+        _ => parts.push(SnippetPart {
+            text: text.to_string(),
+            highlighted,
+        }),
+    }
+}
+
+// Works on the original text so the snippet keeps its tashkeel and spelling.
+pub fn highlight_snippet(text: &str, terms: &[String]) -> Option<Vec<SnippetPart>> {
+    let words = word_ranges(text);
+    let matches: Vec<bool> = words
+        .iter()
+        .map(|&(s, e)| {
+            let word = normalize_arabic(&text[s..e]);
+            terms.iter().any(|t| word.starts_with(t.as_str()))
+        })
+        .collect();
+
+    let first = matches.iter().position(|m| *m)?;
+    let from = first.saturating_sub(SNIPPET_LEAD_WORDS);
+    let to = (from + SNIPPET_WORDS).min(words.len());
+
+    let mut parts = Vec::new();
+    let start_byte = if from == 0 { 0 } else { words[from].0 };
+    let end_byte = if to == words.len() { text.len() } else { words[to - 1].1 };
+
+    if from > 0 {
+        push_part(&mut parts, "… ", false);
+    }
+    let mut cursor = start_byte;
+    for (&(s, e), &hit) in words[from..to].iter().zip(&matches[from..to]) {
+        push_part(&mut parts, &text[cursor..s], false);
+        push_part(&mut parts, &text[s..e], hit);
+        cursor = e;
+    }
+    push_part(&mut parts, &text[cursor..end_byte], false);
+    if to < words.len() {
+        push_part(&mut parts, " …", false);
+    }
+
+    Some(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(text: &str, query: &str) -> Option<Vec<(String, bool)>> {
+        highlight_snippet(text, &query_terms(query))
+            .map(|parts| parts.into_iter().map(|p| (p.text, p.highlighted)).collect())
+    }
+
+    #[test]
+    fn strips_tashkeel_and_tatweel() {
+        assert_eq!(normalize_arabic("مُحَمَّدٌ"), "محمد");
+        assert_eq!(normalize_arabic("كـتـاب"), "كتاب");
+    }
+
+    #[test]
+    fn folds_letter_variants() {
+        assert_eq!(normalize_arabic("أحمد إبراهيم آمن ٱلله"), "احمد ابراهيم امن الله");
+        assert_eq!(normalize_arabic("مستشفى قائم"), "مستشفي قايم");
+        assert_eq!(normalize_arabic("سؤال"), "سوال");
+        assert_eq!(normalize_arabic("مدرسة"), "مدرسه");
+        assert_eq!(fold_char('Q'), 'q');
+    }
+
+    #[test]
+    fn builds_prefix_query() {
+        let terms = query_terms("إلى المدرسةِ!");
+        assert_eq!(terms, vec!["الي", "المدرسه"]);
+        assert_eq!(build_match_query(&terms).as_deref(), Some("\"الي\"* \"المدرسه\"*"));
+        assert_eq!(build_match_query(&query_terms("  ،! ")), None);
+    }
+
+    #[test]
+    fn highlights_original_spelling() {
+        assert_eq!(
+            parts("ذهبتُ إلى المدرسة صباحاً", "مدرسه الى"),
+            Some(vec![
+                ("ذهبتُ ".to_string(), false),
+                ("إلى".to_string(), true),
+                (" المدرسة صباحاً".to_string(), false),
+            ])
+        );
+        assert_eq!(
+            parts("ذهبتُ إلى المدرسة", "المدرسه"),
+            Some(vec![
+                ("ذهبتُ إلى ".to_string(), false),
+                ("المدرسة".to_string(), true),
+            ])
+        );
+        assert_eq!(parts("ذهبتُ إلى المدرسة", "بيت"), None);
+    }
+
+    #[test]
+    fn trims_long_snippets() {
+        let text = (0..40).map(|i| format!("w{}", i)).collect::<Vec<_>>().join(" ");
+        let parts = parts(&text, "w20").unwrap();
+        assert_eq!(parts.len(), 3);
+        assert!(parts[0].0.starts_with("… w12 "));
+        assert_eq!(parts[1], ("w20".to_string(), true));
+        assert!(parts[2].0.ends_with(" w35 …"));
+    }
+}
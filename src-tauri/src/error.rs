@@ -31,6 +31,9 @@ pub enum AppError {
 
     #[error("خطأ في الشبكة: {0}")]
     NetworkError(String),
+
+    #[error("إصدار قاعدة البيانات ({0}) أحدث من الإصدار المدعوم ({1})، يرجى تحديث التطبيق")]
+    SchemaTooNew(i64, i64),
 }
 
 impl From<AppError> for String {
@@ -0,0 +1,147 @@
+use serde::Serialize;
+use tauri::Emitter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RefineStatus {
+    Started,
+    Done,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Started,
+    Completed,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum PipelineEvent {
+    DictationStatus {
+        is_recording: bool,
+        is_processing: bool,
+    },
+    Partial {
+        text: String,
+        chunk_index: i64,
+        is_final: bool,
+    },
+    Result {
+        text: String,
+        duration: u64,
+        language: String,
+    },
+    AudioLevel {
+        level: f32,
+    },
+    SilenceCountdown {
+        remaining: f32,
+        total: f32,
+    },
+    AutoStop {},
+    Cancelled {},
+    RefineStatus {
+        status: RefineStatus,
+    },
+    RefineChunk {
+        chunk: String,
+        accumulated: String,
+    },
+    DownloadStatus {
+        status: TaskStatus,
+        model_id: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+    DownloadProgress {
+        model_id: String,
+        progress: f64,
+        downloaded: u64,
+        total: u64,
+    },
+    FileStatus {
+        status: TaskStatus,
+        path: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+    FileProgress {
+        path: String,
+        progress: f64,
+        processed: usize,
+        total: usize,
+        window: usize,
+        windows: usize,
+    },
+    SettingChanged {
+        key: String,
+        value: Option<String>,
+    },
+}
+
+impl PipelineEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PipelineEvent::DictationStatus { .. } => "dictation-status",
+            PipelineEvent::Partial { .. } => "dictation-partial",
+            PipelineEvent::Result { .. } => "dictation-result",
+            PipelineEvent::AudioLevel { .. } => "audio-level",
+            PipelineEvent::SilenceCountdown { .. } => "silence-countdown",
+            PipelineEvent::AutoStop {} => "dictation-auto-stop",
+            PipelineEvent::Cancelled {} => "dictation-cancelled",
+            PipelineEvent::RefineStatus { .. } => "ai-refine-status",
+            PipelineEvent::RefineChunk { .. } => "ai-refine-chunk",
+            PipelineEvent::DownloadStatus { .. } => "model-download-status",
+            PipelineEvent::DownloadProgress { .. } => "model-download-progress",
+            PipelineEvent::FileStatus { .. } => "file-transcription-status",
+            PipelineEvent::FileProgress { .. } => "file-transcription-progress",
+            PipelineEvent::SettingChanged { .. } => "settings-changed",
+        }
+    }
+}
+
+pub trait EventSink: Send + Sync {
+    fn send(&self, event: PipelineEvent);
+}
+
+impl EventSink for tauri::AppHandle {
+    fn send(&self, event: PipelineEvent) {
+        let _ = self.emit(event.name(), &event);
+    }
+}
+
+pub struct NullSink;
+
+impl EventSink for NullSink {
+    fn send(&self, _event: PipelineEvent) {}
+}
+
+#[cfg(test)]
+#[derive(Default)]
+pub struct RecordingSink {
+    events: std::sync::Mutex<Vec<PipelineEvent>>,
+}
+
+#[cfg(test)]
+impl RecordingSink {
+    pub fn take(&self) -> Vec<PipelineEvent> {
+        self.events
+            .lock()
+            .map(|mut e| std::mem::take(&mut *e))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+impl EventSink for RecordingSink {
+    fn send(&self, event: PipelineEvent) {
+        if let Ok(mut events) = self.events.lock() {
+            events.push(event);
+        }
+    }
+}
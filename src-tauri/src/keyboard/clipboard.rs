@@ -0,0 +1,77 @@
+use arboard::{Clipboard, Error, ImageData};
+
+// What was on the clipboard before dictation replaced it.
+pub struct ClipboardSnapshot {
+    text: Option<String>,
+    html: Option<String>,
+    image: Option<ImageData<'static>>,
+    unreadable: Vec<&'static str>,
+}
+
+fn read<T>(
+    format: &'static str,
+    result: Result<T, Error>,
+    unreadable: &mut Vec<&'static str>,
+) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(Error::ContentNotAvailable) => None,
+        Err(e) => {
+            tracing::debug!("[clipboard] Failed to read {}: {}", format, e);
+            unreadable.push(format);
+            None
+        }
+    }
+}
+
+impl ClipboardSnapshot {
+    pub fn capture(clipboard: &mut Clipboard) -> Self {
+        let mut unreadable = Vec::new();
+        let text = read("text", clipboard.get_text(), &mut unreadable);
+        let html = read("html", clipboard.get().html(), &mut unreadable);
+        let image = read("image", clipboard.get_image(), &mut unreadable);
+        Self {
+            text,
+            html,
+            image,
+            unreadable,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.text.is_none() && self.html.is_none() && self.image.is_none()
+    }
+
+    // Writing a format replaces the whole clipboard, so only combinations that can be
+    // written back in one call are restored: text, HTML with its plain-text alternative, or an image.
+    fn unrestorable_reason(&self) -> Option<String> {
+        if !self.unreadable.is_empty() {
+            return Some(format!("could not read {}", self.unreadable.join(", ")));
+        }
+        if self.image.is_some() && (self.text.is_some() || self.html.is_some()) {
+            return Some("image mixed with text cannot be written back together".to_string());
+        }
+        if self.is_empty() {
+            // Either truly empty or holding a format arboard can't read (e.g. a file list).
+            return Some("no readable format".to_string());
+        }
+        None
+    }
+
+    pub fn restore(self, clipboard: &mut Clipboard) {
+        if let Some(reason) = self.unrestorable_reason() {
+            tracing::warn!("[clipboard] Previous clipboard not restored: {}", reason);
+            return;
+        }
+
+        let result = match (self.image, self.html, self.text) {
+            (Some(image), _, _) => clipboard.set_image(image),
+            (None, Some(html), text) => clipboard.set_html(html, text),
+            (None, None, Some(text)) => clipboard.set_text(text),
+            (None, None, None) => Ok(()),
+        };
+        if let Err(e) = result {
+            tracing::warn!("[clipboard] Failed to restore previous clipboard: {}", e);
+        }
+    }
+}
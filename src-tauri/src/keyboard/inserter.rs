@@ -0,0 +1,107 @@
+use super::simulator::{KeyboardSimulator, PasteChord};
+use crate::settings::Settings;
+use std::time::Duration;
+
+pub trait TextInserter: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn insert(&self, text: &str) -> Result<(), anyhow::Error>;
+}
+
+pub struct ClipboardPaste {
+    pub chord: PasteChord,
+    pub delay: Duration,
+}
+
+impl TextInserter for ClipboardPaste {
+    fn name(&self) -> &'static str {
+        "paste"
+    }
+
+    fn insert(&self, text: &str) -> Result<(), anyhow::Error> {
+        KeyboardSimulator::new().paste(text, self.chord, self.delay)
+    }
+}
+
+pub struct DirectType;
+
+impl TextInserter for DirectType {
+    fn name(&self) -> &'static str {
+        "type"
+    }
+
+    fn insert(&self, text: &str) -> Result<(), anyhow::Error> {
+        KeyboardSimulator::new().type_unicode(text)
+    }
+}
+
+// Pastes through the PRIMARY selection, leaving the regular clipboard untouched (Linux only).
+pub struct PrimarySelection {
+    pub delay: Duration,
+}
+
+impl TextInserter for PrimarySelection {
+    fn name(&self) -> &'static str {
+        "primary"
+    }
+
+    fn insert(&self, text: &str) -> Result<(), anyhow::Error> {
+        KeyboardSimulator::new().paste_primary(text, self.delay)
+    }
+}
+
+// Leaves the text on the clipboard for the user to paste themselves.
+pub struct ClipboardOnly;
+
+impl TextInserter for ClipboardOnly {
+    fn name(&self) -> &'static str {
+        "clipboard"
+    }
+
+    fn insert(&self, text: &str) -> Result<(), anyhow::Error> {
+        KeyboardSimulator::new().set_clipboard(text)
+    }
+}
+
+pub fn create(method: &str, settings: &Settings) -> Option<Box<dyn TextInserter>> {
+    let chord = PasteChord::from_id(&settings.paste_chord).unwrap_or(PasteChord::CtrlV);
+    let delay = Duration::from_millis(settings.paste_delay_ms);
+    match method {
+        "paste" => Some(Box::new(ClipboardPaste { chord, delay })),
+        "type" => Some(Box::new(DirectType)),
+        "primary" => Some(Box::new(PrimarySelection { delay })),
+        "clipboard" => Some(Box::new(ClipboardOnly)),
+        _ => None,
+    }
+}
+
+pub fn chain(settings: &Settings) -> Vec<Box<dyn TextInserter>> {
+    let mut methods = vec![settings.insertion_method.as_str()];
+    if settings.insertion_fallback != settings.insertion_method {
+        methods.push(settings.insertion_fallback.as_str());
+    }
+    methods.into_iter().filter_map(|m| create(m, settings)).collect()
+}
+
+// Returns the backend that succeeded.
+pub fn insert_text(settings: &Settings, text: &str) -> Result<&'static str, String> {
+    let mut errors = Vec::new();
+    for inserter in chain(settings) {
+        match inserter.insert(text) {
+            Ok(()) => {
+                if !errors.is_empty() {
+                    tracing::warn!("[insert] Fell back to '{}' after: {}", inserter.name(), errors.join("; "));
+                }
+                return Ok(inserter.name());
+            }
+            Err(e) => {
+                tracing::warn!("[insert] '{}' failed: {}", inserter.name(), e);
+                errors.push(format!("{}: {}", inserter.name(), e));
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        return Err("لا توجد طريقة إدراج مفعّلة".to_string());
+    }
+    Err(format!("فشل إدراج النص: {}", errors.join("; ")))
+}
@@ -0,0 +1,116 @@
+use super::inserter::{self, TextInserter};
+use super::simulator::KeyboardSimulator;
+use crate::settings::Settings;
+
+// Backspaces needed to reach the longest common prefix, then the text to type after it.
+pub fn diff(inserted: &str, target: &str) -> (usize, String) {
+    let common = inserted
+        .chars()
+        .zip(target.chars())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let backspaces = inserted.chars().count() - common;
+    (backspaces, target.chars().skip(common).collect())
+}
+
+// Types accepted streaming chunks into the focused app while the user is still speaking.
+pub struct LiveInsertion {
+    settings: Settings,
+    inserter: Box<dyn TextInserter>,
+    inserted: String,
+    failed: bool,
+}
+
+impl LiveInsertion {
+    // Clipboard-only insertion can't be corrected afterwards, so it never streams.
+    pub fn from_settings(settings: &Settings) -> Option<Self> {
+        if !settings.live_typing || !settings.auto_type || settings.insertion_method == "clipboard" {
+            return None;
+        }
+        let inserter = inserter::create(&settings.insertion_method, settings)?;
+        Some(Self {
+            settings: settings.clone(),
+            inserter,
+            inserted: String::new(),
+            failed: false,
+        })
+    }
+
+    pub fn inserted(&self) -> &str {
+        &self.inserted
+    }
+
+    pub fn append(&mut self, chunk: &str) {
+        let chunk = chunk.trim();
+        if self.failed || chunk.is_empty() {
+            return;
+        }
+        let piece = if self.inserted.is_empty() {
+            chunk.to_string()
+        } else {
+            format!(" {}", chunk)
+        };
+
+        match self.inserter.insert(&piece) {
+            Ok(()) => self.inserted.push_str(&piece),
+            Err(e) => {
+                // Stop streaming; the final pass corrects whatever did get typed.
+                tracing::warn!("[live] '{}' failed, pausing live typing: {}", self.inserter.name(), e);
+                self.failed = true;
+            }
+        }
+    }
+
+    // Returns the backend that typed the correction, which may be the fallback, so undo knows
+    // what it is removing.
+    pub fn finish(self, target: &str) -> Result<&'static str, String> {
+        let (backspaces, rest) = diff(&self.inserted, target);
+        tracing::debug!(
+            "[live] Final correction: {} backspace(s), {} char(s) typed",
+            backspaces,
+            rest.chars().count()
+        );
+
+        if backspaces > 0 {
+            KeyboardSimulator::new()
+                .backspace(backspaces)
+                .map_err(|e| e.to_string())?;
+        }
+        if rest.is_empty() {
+            return Ok(self.inserter.name());
+        }
+        inserter::insert_text(&self.settings, &rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff;
+
+    #[test]
+    fn appends_after_inserted_text() {
+        assert_eq!(diff("", "hello"), (0, "hello".to_string()));
+        assert_eq!(diff("hello", "hello world"), (0, " world".to_string()));
+        assert_eq!(diff("hello", "hello"), (0, String::new()));
+    }
+
+    #[test]
+    fn erases_back_to_common_prefix() {
+        assert_eq!(diff("hello wrld", "hello world"), (3, "orld".to_string()));
+        assert_eq!(diff("hello world", "hello"), (6, String::new()));
+        assert_eq!(diff("abc", ""), (3, String::new()));
+    }
+
+    #[test]
+    fn counts_arabic_code_points() {
+        // The fixed word gains a damma, a separate code point after the base letter.
+        assert_eq!(diff("السلام عليكم", "السلامُ عليكم"), (6, "ُ عليكم".to_string()));
+        assert_eq!(diff("مرحبا", "مرحباً"), (0, "ً".to_string()));
+        assert_eq!(diff("شكراً جزيلاً", "شكراً"), (7, String::new()));
+    }
+
+    #[test]
+    fn counts_emoji_modifiers() {
+        assert_eq!(diff("ok 👍🏽", "ok 👍"), (1, String::new()));
+    }
+}
@@ -1 +1,4 @@
+pub mod clipboard;
+pub mod inserter;
+pub mod live;
 pub mod simulator;
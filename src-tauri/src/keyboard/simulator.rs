@@ -1,7 +1,52 @@
+use super::clipboard::ClipboardSnapshot;
 use arboard::Clipboard;
 use enigo::{Direction, Enigo, Key, Keyboard, Settings};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+const CLIPBOARD_POLL: Duration = Duration::from_millis(10);
+
+// Clipboard writes can land asynchronously (clipboard managers, Wayland bridges), so wait until
+// the text reads back before pasting, up to `timeout`.
+fn wait_for_text(mut read: impl FnMut() -> Option<String>, text: &str, timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    while read().as_deref() != Some(text) {
+        if Instant::now() >= deadline {
+            tracing::debug!("[clipboard] Text did not read back within {:?}, pasting anyway", timeout);
+            return;
+        }
+        thread::sleep(CLIPBOARD_POLL);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PasteChord {
+    CtrlV,
+    CtrlShiftV,
+    ShiftInsert,
+}
+
+impl PasteChord {
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "ctrl+v" => Some(PasteChord::CtrlV),
+            "ctrl+shift+v" => Some(PasteChord::CtrlShiftV),
+            "shift+insert" => Some(PasteChord::ShiftInsert),
+            _ => None,
+        }
+    }
+
+    fn keys(&self) -> Result<(Vec<Key>, Key), anyhow::Error> {
+        match self {
+            PasteChord::CtrlV => Ok((vec![Key::Control], Key::Unicode('v'))),
+            PasteChord::CtrlShiftV => Ok((vec![Key::Control, Key::Shift], Key::Unicode('v'))),
+            #[cfg(not(target_os = "macos"))]
+            PasteChord::ShiftInsert => Ok((vec![Key::Shift], Key::Insert)),
+            #[cfg(target_os = "macos")]
+            PasteChord::ShiftInsert => Err(anyhow::anyhow!("Shift+Insert غير مدعوم على هذا النظام")),
+        }
+    }
+}
 
 pub struct KeyboardSimulator;
 
@@ -10,32 +55,123 @@ impl KeyboardSimulator {
         Self
     }
 
-    pub fn type_text(&self, text: &str) -> Result<(), anyhow::Error> {
-        let mut clipboard = Clipboard::new()
-            .map_err(|e| anyhow::anyhow!("فشل الوصول للحافظة: {}", e))?;
+    fn enigo(&self) -> Result<Enigo, anyhow::Error> {
+        Enigo::new(&Settings::default())
+            .map_err(|e| anyhow::anyhow!("فشل تهيئة محاكي لوحة المفاتيح: {}", e))
+    }
+
+    fn clipboard(&self) -> Result<Clipboard, anyhow::Error> {
+        Clipboard::new().map_err(|e| anyhow::anyhow!("فشل الوصول للحافظة: {}", e))
+    }
 
-        let old_clipboard = clipboard.get_text().unwrap_or_default();
+    pub fn press_chord(&self, chord: PasteChord) -> Result<(), anyhow::Error> {
+        let (modifiers, key) = chord.keys()?;
+        let mut enigo = self.enigo()?;
+
+        for modifier in &modifiers {
+            enigo.key(*modifier, Direction::Press)
+                .map_err(|e| anyhow::anyhow!("فشل محاكاة {:?}: {}", modifier, e))?;
+        }
+        let result = enigo.key(key, Direction::Click)
+            .map_err(|e| anyhow::anyhow!("فشل محاكاة {:?}: {}", key, e));
+        // Release modifiers even if the key click failed so they don't stay stuck.
+        for modifier in modifiers.iter().rev() {
+            let _ = enigo.key(*modifier, Direction::Release);
+        }
+        result
+    }
+
+    pub fn set_clipboard(&self, text: &str) -> Result<(), anyhow::Error> {
+        self.clipboard()?
+            .set_text(text)
+            .map_err(|e| anyhow::anyhow!("فشل نسخ النص للحافظة: {}", e))
+    }
+
+    // `delay` is how long the target app gets to read the clipboard before it is restored.
+    pub fn paste(&self, text: &str, chord: PasteChord, delay: Duration) -> Result<(), anyhow::Error> {
+        let mut clipboard = self.clipboard()?;
+
+        let snapshot = ClipboardSnapshot::capture(&mut clipboard);
 
         clipboard
             .set_text(text)
             .map_err(|e| anyhow::anyhow!("فشل نسخ النص للحافظة: {}", e))?;
 
-        thread::sleep(Duration::from_millis(50));
+        wait_for_text(|| clipboard.get_text().ok(), text, delay);
+
+        let result = self.press_chord(chord);
+
+        thread::sleep(delay);
 
-        let mut enigo = Enigo::new(&Settings::default())
-            .map_err(|e| anyhow::anyhow!("فشل تهيئة محاكي لوحة المفاتيح: {}", e))?;
+        snapshot.restore(&mut clipboard);
 
-        enigo.key(Key::Control, Direction::Press)
-            .map_err(|e| anyhow::anyhow!("فشل محاكاة Ctrl: {}", e))?;
-        enigo.key(Key::Unicode('v'), Direction::Click)
-            .map_err(|e| anyhow::anyhow!("فشل محاكاة V: {}", e))?;
-        enigo.key(Key::Control, Direction::Release)
-            .map_err(|e| anyhow::anyhow!("فشل تحرير Ctrl: {}", e))?;
+        result
+    }
+
+    // Shift+Insert pastes CLIPBOARD in GTK and Qt apps, so the selection is pasted with a
+    // middle-click, which lands at the mouse pointer.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    pub fn paste_primary(&self, text: &str, delay: Duration) -> Result<(), anyhow::Error> {
+        use arboard::{GetExtLinux, LinuxClipboardKind, SetExtLinux};
+        use enigo::{Button, Mouse};
+
+        let mut clipboard = self.clipboard()?;
+        clipboard
+            .set()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text(text)
+            .map_err(|e| anyhow::anyhow!("فشل نسخ النص للتحديد الأساسي: {}", e))?;
 
-        thread::sleep(Duration::from_millis(100));
+        wait_for_text(
+            || clipboard.get().clipboard(LinuxClipboardKind::Primary).text().ok(),
+            text,
+            delay,
+        );
 
-        let _ = clipboard.set_text(&old_clipboard);
+        let result = self
+            .enigo()?
+            .button(Button::Middle, Direction::Click)
+            .map_err(|e| anyhow::anyhow!("فشل محاكاة النقر الأوسط: {}", e));
 
+        // The selection is served by this process, so keep the handle alive until the app has read it.
+        thread::sleep(delay);
+        drop(clipboard);
+        result
+    }
+
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    pub fn paste_primary(&self, _text: &str, _delay: Duration) -> Result<(), anyhow::Error> {
+        Err(anyhow::anyhow!("التحديد الأساسي غير مدعوم على هذا النظام"))
+    }
+
+    pub fn backspace(&self, count: usize) -> Result<(), anyhow::Error> {
+        let mut enigo = self.enigo()?;
+        for _ in 0..count {
+            enigo.key(Key::Backspace, Direction::Click)
+                .map_err(|e| anyhow::anyhow!("فشل محاكاة Backspace: {}", e))?;
+        }
         Ok(())
     }
+
+    // Shift+Left selects by logical character in most editors, though some RTL widgets move visually.
+    pub fn select_back(&self, count: usize) -> Result<(), anyhow::Error> {
+        let mut enigo = self.enigo()?;
+        enigo.key(Key::Shift, Direction::Press)
+            .map_err(|e| anyhow::anyhow!("فشل محاكاة Shift: {}", e))?;
+        let mut result = Ok(());
+        for _ in 0..count {
+            if let Err(e) = enigo.key(Key::LeftArrow, Direction::Click) {
+                result = Err(anyhow::anyhow!("فشل محاكاة السهم الأيسر: {}", e));
+                break;
+            }
+        }
+        let _ = enigo.key(Key::Shift, Direction::Release);
+        result
+    }
+
+    pub fn type_unicode(&self, text: &str) -> Result<(), anyhow::Error> {
+        self.enigo()?
+            .text(text)
+            .map_err(|e| anyhow::anyhow!("فشل كتابة النص مباشرة: {}", e))
+    }
 }
@@ -1,172 +1,28 @@
-mod ai;
-mod audio;
-mod commands;
-mod constants;
-mod db;
-mod error;
+pub mod ai;
+pub mod audio;
+pub mod commands;
+pub mod constants;
+pub mod db;
+pub mod error;
+pub mod events;
 mod keyboard;
 mod logging;
 mod models;
+pub mod pipeline;
 mod security;
-mod whisper;
+pub mod settings;
+mod shortcuts;
+pub mod whisper;
+pub mod window;
 
 use commands::dictation::DictationState;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex};
-use std::time::Instant;
 use tauri::menu::{MenuBuilder, MenuItemBuilder};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
-use tauri::{Emitter, Manager};
+use tauri::Manager;
 use tauri_plugin_autostart::MacosLauncher;
 
-pub struct ShortcutState(pub Arc<Mutex<String>>);
-
-fn parse_shortcut(shortcut: &str) -> ShortcutType {
-    let parts: Vec<&str> = shortcut.split('+').map(|s| s.trim()).collect();
-
-    if parts.len() == 2 && parts[0] == parts[1] && parts[0].len() == 1 {
-        return ShortcutType::DoubleTap(parts[0].to_uppercase());
-    }
-
-    let mut ctrl = false;
-    let mut shift = false;
-    let mut alt = false;
-    let mut meta = false;
-    let mut key = String::new();
-
-    for part in &parts {
-        match part.to_lowercase().as_str() {
-            "ctrl" | "control" => ctrl = true,
-            "shift" => shift = true,
-            "alt" => alt = true,
-            "meta" | "super" | "cmd" => meta = true,
-            _ => key = part.to_string(),
-        }
-    }
-
-    if key.is_empty() {
-        return ShortcutType::DoubleTap("Z".to_string());
-    }
-
-    ShortcutType::ModifierCombo {
-        ctrl,
-        shift,
-        alt,
-        meta,
-        key,
-    }
-}
-
-enum ShortcutType {
-    DoubleTap(String),
-    ModifierCombo {
-        ctrl: bool,
-        shift: bool,
-        alt: bool,
-        meta: bool,
-        key: String,
-    },
-}
-
-fn rdev_key_matches(rdev_key: &rdev::Key, target: &str) -> bool {
-    let target_upper = target.to_uppercase();
-    match rdev_key {
-        rdev::Key::KeyA => target_upper == "A",
-        rdev::Key::KeyB => target_upper == "B",
-        rdev::Key::KeyC => target_upper == "C",
-        rdev::Key::KeyD => target_upper == "D",
-        rdev::Key::KeyE => target_upper == "E",
-        rdev::Key::KeyF => target_upper == "F",
-        rdev::Key::KeyG => target_upper == "G",
-        rdev::Key::KeyH => target_upper == "H",
-        rdev::Key::KeyI => target_upper == "I",
-        rdev::Key::KeyJ => target_upper == "J",
-        rdev::Key::KeyK => target_upper == "K",
-        rdev::Key::KeyL => target_upper == "L",
-        rdev::Key::KeyM => target_upper == "M",
-        rdev::Key::KeyN => target_upper == "N",
-        rdev::Key::KeyO => target_upper == "O",
-        rdev::Key::KeyP => target_upper == "P",
-        rdev::Key::KeyQ => target_upper == "Q",
-        rdev::Key::KeyR => target_upper == "R",
-        rdev::Key::KeyS => target_upper == "S",
-        rdev::Key::KeyT => target_upper == "T",
-        rdev::Key::KeyU => target_upper == "U",
-        rdev::Key::KeyV => target_upper == "V",
-        rdev::Key::KeyW => target_upper == "W",
-        rdev::Key::KeyX => target_upper == "X",
-        rdev::Key::KeyY => target_upper == "Y",
-        rdev::Key::KeyZ => target_upper == "Z",
-        rdev::Key::Num0 => target_upper == "0",
-        rdev::Key::Num1 => target_upper == "1",
-        rdev::Key::Num2 => target_upper == "2",
-        rdev::Key::Num3 => target_upper == "3",
-        rdev::Key::Num4 => target_upper == "4",
-        rdev::Key::Num5 => target_upper == "5",
-        rdev::Key::Num6 => target_upper == "6",
-        rdev::Key::Num7 => target_upper == "7",
-        rdev::Key::Num8 => target_upper == "8",
-        rdev::Key::Num9 => target_upper == "9",
-        rdev::Key::F1 => target_upper == "F1",
-        rdev::Key::F2 => target_upper == "F2",
-        rdev::Key::F3 => target_upper == "F3",
-        rdev::Key::F4 => target_upper == "F4",
-        rdev::Key::F5 => target_upper == "F5",
-        rdev::Key::F6 => target_upper == "F6",
-        rdev::Key::F7 => target_upper == "F7",
-        rdev::Key::F8 => target_upper == "F8",
-        rdev::Key::F9 => target_upper == "F9",
-        rdev::Key::F10 => target_upper == "F10",
-        rdev::Key::F11 => target_upper == "F11",
-        rdev::Key::F12 => target_upper == "F12",
-        rdev::Key::Space => target_upper == "SPACE",
-        rdev::Key::Tab => target_upper == "TAB",
-        rdev::Key::Escape => target_upper == "ESCAPE" || target_upper == "ESC",
-        _ => false,
-    }
-}
-
-fn is_modifier_key(key: &rdev::Key) -> bool {
-    matches!(
-        key,
-        rdev::Key::ControlLeft
-            | rdev::Key::ControlRight
-            | rdev::Key::ShiftLeft
-            | rdev::Key::ShiftRight
-            | rdev::Key::Alt
-            | rdev::Key::AltGr
-            | rdev::Key::MetaLeft
-            | rdev::Key::MetaRight
-    )
-}
-
-fn is_ctrl_key(key: &rdev::Key) -> bool {
-    matches!(key, rdev::Key::ControlLeft | rdev::Key::ControlRight)
-}
-
-fn is_shift_key(key: &rdev::Key) -> bool {
-    matches!(key, rdev::Key::ShiftLeft | rdev::Key::ShiftRight)
-}
-
-fn is_alt_key(key: &rdev::Key) -> bool {
-    matches!(key, rdev::Key::Alt | rdev::Key::AltGr)
-}
-
-fn is_meta_key(key: &rdev::Key) -> bool {
-    matches!(key, rdev::Key::MetaLeft | rdev::Key::MetaRight)
-}
-
-#[tauri::command]
-fn update_shortcut(
-    shortcut: String,
-    shortcut_state: tauri::State<'_, ShortcutState>,
-) -> Result<(), String> {
-    let mut current = shortcut_state.0.lock().map_err(|e| e.to_string())?;
-    *current = shortcut;
-    tracing::info!("[shortcut] Updated to: {}", *current);
-    Ok(())
-}
-
 #[tauri::command]
 fn show_overlay(app: tauri::AppHandle) -> Result<(), String> {
     if let Some(window) = app.get_webview_window("overlay") {
@@ -210,34 +66,41 @@ pub fn run() {
                 security::keychain::migrate_from_db(&conn);
             }
 
+            let settings_state = {
+                let db_state: tauri::State<'_, db::Database> = app.state();
+                let conn = db_state.0.lock().unwrap();
+                settings::SettingsState::load(&conn)?
+            };
+            let initial_settings = settings_state.get();
+            app.manage(settings_state);
+
             let mut transcriber = whisper::transcriber::WhisperTranscriber::new();
+            transcriber.set_language(&initial_settings.language);
+            transcriber.set_decoding(initial_settings.decoding());
 
-            let (active_model_id, use_gpu, shortcut_setting) = {
+            let mut glossary = pipeline::vocabulary::Glossary::new();
+            {
                 let db_state: tauri::State<'_, db::Database> = app.state();
                 let conn = db_state.0.lock().unwrap();
-                let model_id = conn.query_row(
-                    "SELECT value FROM settings WHERE key = 'active_model'",
-                    [],
-                    |row| row.get::<_, String>(0),
-                )
-                .unwrap_or_default();
-                let gpu = conn.query_row(
-                    "SELECT value FROM settings WHERE key = 'use_gpu'",
-                    [],
-                    |row| row.get::<_, String>(0),
-                )
-                .unwrap_or_else(|_| "false".to_string()) == "true";
-                let shortcut = conn.query_row(
-                    "SELECT value FROM settings WHERE key = 'shortcut'",
-                    [],
-                    |row| row.get::<_, String>(0),
-                )
-                .unwrap_or_else(|_| "Z+Z".to_string());
-                let shortcut = shortcut.trim_matches('"').to_string();
-                (model_id, gpu, shortcut)
-            };
+                match commands::vocabulary::load(&conn) {
+                    Ok(terms) => glossary.set_terms(terms),
+                    Err(e) => tracing::warn!("[vocabulary] Failed to load glossary: {}", e),
+                }
+            }
+            glossary.set_language(&initial_settings.language);
+            glossary.set_extra_terms(&initial_settings.custom_vocabulary);
+            transcriber.set_vocabulary_prompt(
+                &glossary.prompt(whisper::transcriber::vocabulary_budget(&initial_settings.language)),
+            );
+
+            let active_model_id = initial_settings.active_model.clone();
+            let use_gpu = initial_settings.use_gpu;
+            let shortcut_config = shortcuts::ShortcutConfig::from_settings(&initial_settings);
 
-            tracing::info!("[shortcut] Loaded shortcut: {}", shortcut_setting);
+            tracing::info!(
+                "[shortcut] Loaded {} binding(s) (push-to-talk: {})",
+                shortcut_config.bindings.len(), shortcut_config.push_to_talk
+            );
 
             if !active_model_id.is_empty() {
                 if let Ok(path) = models::ModelDownloader::get_model_path_by_id(&app_handle, &active_model_id) {
@@ -255,24 +118,24 @@ pub fn run() {
                 tracing::info!("[model] No active model set, user needs to download one");
             }
 
-            let shortcut_arc = Arc::new(Mutex::new(shortcut_setting));
-            app.manage(ShortcutState(Arc::clone(&shortcut_arc)));
+            let shortcut_arc = Arc::new(Mutex::new(shortcut_config));
+            app.manage(shortcuts::ShortcutState(Arc::clone(&shortcut_arc)));
 
             let recorder = audio::recorder::AudioRecorder::new();
+            if !initial_settings.audio_device.is_empty() {
+                tracing::info!("[audio] Restoring saved device: {}", initial_settings.audio_device);
+                recorder.set_device(Some(initial_settings.audio_device.clone()));
+            }
+
+            let mut voice_commands =
+                commands::voice_commands::VoiceCommandProcessor::new(initial_settings.voice_commands);
+            voice_commands.set_language(&initial_settings.language);
             {
                 let db_state: tauri::State<'_, db::Database> = app.state();
                 let conn = db_state.0.lock().unwrap();
-                let saved_device: Option<String> = conn
-                    .query_row(
-                        "SELECT value FROM settings WHERE key = 'audio_device'",
-                        [],
-                        |row| row.get::<_, String>(0),
-                    )
-                    .ok()
-                    .filter(|v| !v.is_empty());
-                if let Some(ref device) = saved_device {
-                    tracing::info!("[audio] Restoring saved device: {}", device);
-                    recorder.set_device(Some(device.clone()));
+                match commands::voice_commands::load(&conn) {
+                    Ok(list) => voice_commands.set_commands(list),
+                    Err(e) => tracing::warn!("[voice_commands] Failed to load commands, using built-ins: {}", e),
                 }
             }
 
@@ -281,91 +144,27 @@ pub fn run() {
                 transcriber: Mutex::new(transcriber),
                 is_recording: Mutex::new(false),
                 is_processing: Mutex::new(false),
+                is_transcribing: AtomicBool::new(false),
                 streaming_active: Arc::new(AtomicBool::new(false)),
                 streaming_thread: Mutex::new(None),
                 accumulated_text: Arc::new(Mutex::new(Vec::new())),
                 vad: Arc::new(Mutex::new(audio::vad::AdaptiveVAD::new())),
                 last_processed_pos: Arc::new(Mutex::new(0)),
-                noise_suppressor: Mutex::new(audio::noise_suppressor::NoiseSuppressor::new(false)),
-                voice_commands: Mutex::new(commands::voice_commands::VoiceCommandProcessor::new(true)),
+                noise_suppressor: Mutex::new(audio::noise_suppressor::NoiseSuppressor::new(
+                    initial_settings.noise_suppression,
+                )),
+                voice_commands: Mutex::new(voice_commands),
+                vocabulary: Mutex::new(glossary),
+                auto_stop: Mutex::new(commands::dictation::AutoStopConfig::from_settings(&initial_settings)),
+                window: window::detect(),
+                session: Mutex::new(None),
+                live: Mutex::new(None),
+                last_insertion: Mutex::new(None),
             });
 
-            let handle = app.handle().clone();
-            let shortcut_for_thread = Arc::clone(&shortcut_arc);
-
-            std::thread::spawn(move || {
-                let last_press_time = Arc::new(Mutex::new(Instant::now()));
-                let first_press = Arc::new(Mutex::new(false));
-                let ctrl_held = Arc::new(Mutex::new(false));
-                let shift_held = Arc::new(Mutex::new(false));
-                let alt_held = Arc::new(Mutex::new(false));
-                let meta_held = Arc::new(Mutex::new(false));
-
-                let last_press_cb = Arc::clone(&last_press_time);
-                let first_press_cb = Arc::clone(&first_press);
-                let ctrl_cb = Arc::clone(&ctrl_held);
-                let shift_cb = Arc::clone(&shift_held);
-                let alt_cb = Arc::clone(&alt_held);
-                let meta_cb = Arc::clone(&meta_held);
-                let shortcut_cb = Arc::clone(&shortcut_for_thread);
-                let handle_cb = handle.clone();
-
-                rdev::listen(move |event| {
-                    match event.event_type {
-                        rdev::EventType::KeyPress(key) => {
-                            if is_ctrl_key(&key) { *ctrl_cb.lock().unwrap() = true; return; }
-                            if is_shift_key(&key) { *shift_cb.lock().unwrap() = true; return; }
-                            if is_alt_key(&key) { *alt_cb.lock().unwrap() = true; return; }
-                            if is_meta_key(&key) { *meta_cb.lock().unwrap() = true; return; }
+            settings::observers::register(&app_handle, &app.state::<settings::SettingsState>());
 
-                            if is_modifier_key(&key) { return; }
-
-                            let shortcut_str = shortcut_cb.lock().unwrap().clone();
-                            let shortcut_type = parse_shortcut(&shortcut_str);
-
-                            match shortcut_type {
-                                ShortcutType::DoubleTap(target) => {
-                                    if rdev_key_matches(&key, &target) {
-                                        let now = Instant::now();
-                                        let mut last = last_press_cb.lock().unwrap();
-                                        let mut first = first_press_cb.lock().unwrap();
-
-                                        if *first && now.duration_since(*last).as_millis() < 400 {
-                                            *first = false;
-                                            tracing::debug!("[shortcut] Double-{} detected! Toggling dictation...", target);
-                                            let _ = handle_cb.emit("toggle-dictation", ());
-                                        } else {
-                                            *first = true;
-                                            *last = now;
-                                        }
-                                    } else {
-                                        *first_press_cb.lock().unwrap() = false;
-                                    }
-                                }
-                                ShortcutType::ModifierCombo { ctrl, shift, alt, meta, key: target } => {
-                                    let ctrl_ok = !ctrl || *ctrl_cb.lock().unwrap();
-                                    let shift_ok = !shift || *shift_cb.lock().unwrap();
-                                    let alt_ok = !alt || *alt_cb.lock().unwrap();
-                                    let meta_ok = !meta || *meta_cb.lock().unwrap();
-
-                                    if ctrl_ok && shift_ok && alt_ok && meta_ok && rdev_key_matches(&key, &target) {
-                                        tracing::debug!("[shortcut] {} detected! Toggling dictation...", shortcut_str);
-                                        let _ = handle_cb.emit("toggle-dictation", ());
-                                    }
-                                }
-                            }
-                        }
-                        rdev::EventType::KeyRelease(key) => {
-                            if is_ctrl_key(&key) { *ctrl_cb.lock().unwrap() = false; }
-                            if is_shift_key(&key) { *shift_cb.lock().unwrap() = false; }
-                            if is_alt_key(&key) { *alt_cb.lock().unwrap() = false; }
-                            if is_meta_key(&key) { *meta_cb.lock().unwrap() = false; }
-                        }
-                        _ => {}
-                    }
-                })
-                .expect("Failed to start global key listener");
-            });
+            shortcuts::spawn_listener(app_handle.clone(), shortcut_arc);
 
             let show_hide = MenuItemBuilder::with_id("show_hide", "إظهار/إخفاء النافذة").build(app)?;
             let quit = MenuItemBuilder::with_id("quit", "إنهاء التطبيق").build(app)?;
@@ -441,11 +240,19 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             commands::dictation::start_dictation,
             commands::dictation::stop_dictation,
+            commands::dictation::cancel_dictation,
             commands::dictation::get_dictation_status,
+            commands::transcription::transcribe_file,
             commands::settings::get_all_settings,
             commands::settings::update_setting,
+            commands::settings::update_settings,
             commands::settings::get_setting,
+            commands::settings::get_typed_settings,
             commands::history::get_history,
+            commands::history::search_history,
+            commands::history::query_history,
+            commands::history::get_history_segments,
+            commands::history::update_history_item,
             commands::history::delete_history_item,
             commands::history::clear_history,
             commands::history::get_usage_stats,
@@ -466,9 +273,29 @@ pub fn run() {
             commands::ai::get_ai_providers,
             commands::ai::get_current_ai_provider,
             commands::ai::detect_gpu,
+            commands::export::export_subtitles,
+            commands::export::export_history,
             commands::backup::export_settings,
             commands::backup::import_settings,
-            update_shortcut,
+            commands::backup::export_backup,
+            commands::backup::import_backup,
+            commands::profiles::list_profiles,
+            commands::profiles::save_profile,
+            commands::profiles::delete_profile,
+            commands::profiles::get_active_window,
+            commands::voice_commands::list_voice_commands,
+            commands::voice_commands::save_voice_command,
+            commands::voice_commands::delete_voice_command,
+            commands::voice_commands::get_voice_command_conflicts,
+            commands::voice_commands::export_voice_commands,
+            commands::voice_commands::import_voice_commands,
+            commands::vocabulary::list_vocabulary,
+            commands::vocabulary::save_vocabulary_term,
+            commands::vocabulary::delete_vocabulary_term,
+            commands::vocabulary::preview_vocabulary_prompt,
+            commands::vocabulary::list_correction_suggestions,
+            commands::vocabulary::accept_correction_suggestion,
+            commands::vocabulary::dismiss_correction_suggestion,
             show_overlay,
             hide_overlay,
         ])
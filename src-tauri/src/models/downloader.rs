@@ -1,9 +1,10 @@
 use crate::constants::model;
 use crate::error::AppError;
+use crate::events::{EventSink, PipelineEvent, TaskStatus};
 use futures_util::StreamExt;
 use reqwest::Client;
-use std::path::PathBuf;
-use tauri::{Emitter, Manager};
+use std::path::{Path, PathBuf};
+use tauri::Manager;
 use tokio::fs::{self, File};
 use tokio::io::AsyncWriteExt;
 
@@ -49,24 +50,25 @@ impl ModelDownloader {
 
     pub async fn download_model_by_id(
         &self,
-        app: &tauri::AppHandle,
+        model_dir: &Path,
         model_id: &str,
+        sink: &dyn EventSink,
     ) -> Result<PathBuf, AppError> {
         let info = model::find_model(model_id)
             .ok_or_else(|| AppError::DownloadError(format!("Unknown model: {}", model_id)))?;
 
-        let model_dir = Self::get_model_dir(app)?;
         let model_path = model_dir.join(info.filename);
 
-        fs::create_dir_all(&model_dir)
+        fs::create_dir_all(model_dir)
             .await
             .map_err(|e| AppError::DownloadError(format!("Failed to create directory: {}", e)))?;
 
         let url = info.download_url();
-        let _ = app.emit(
-            "model-download-status",
-            serde_json::json!({ "status": "started", "model_id": model_id }),
-        );
+        sink.send(PipelineEvent::DownloadStatus {
+            status: TaskStatus::Started,
+            model_id: model_id.to_string(),
+            message: None,
+        });
 
         tracing::info!("[model] Starting download of '{}' from: {}", info.name, url);
 
@@ -79,10 +81,11 @@ impl ModelDownloader {
 
         if !response.status().is_success() {
             let status = response.status();
-            let _ = app.emit(
-                "model-download-status",
-                serde_json::json!({ "status": "error", "model_id": model_id, "message": format!("HTTP {}", status) }),
-            );
+            sink.send(PipelineEvent::DownloadStatus {
+                status: TaskStatus::Error,
+                model_id: model_id.to_string(),
+                message: Some(format!("HTTP {}", status)),
+            });
             return Err(AppError::DownloadError(format!(
                 "Failed to download model: HTTP {}",
                 status
@@ -109,15 +112,12 @@ impl ModelDownloader {
             downloaded += chunk.len() as u64;
             let progress = (downloaded as f64 / total_size as f64 * 100.0).min(100.0);
 
-            let _ = app.emit(
-                "model-download-progress",
-                serde_json::json!({
-                    "model_id": model_id,
-                    "progress": progress,
-                    "downloaded": downloaded,
-                    "total": total_size
-                }),
-            );
+            sink.send(PipelineEvent::DownloadProgress {
+                model_id: model_id.to_string(),
+                progress,
+                downloaded,
+                total: total_size,
+            });
 
             if downloaded % (10 * 1024 * 1024) < chunk.len() as u64 {
                 tracing::debug!(
@@ -140,10 +140,11 @@ impl ModelDownloader {
             .await
             .map_err(|e| AppError::DownloadError(format!("Failed to rename: {}", e)))?;
 
-        let _ = app.emit(
-            "model-download-status",
-            serde_json::json!({ "status": "completed", "model_id": model_id }),
-        );
+        sink.send(PipelineEvent::DownloadStatus {
+            status: TaskStatus::Completed,
+            model_id: model_id.to_string(),
+            message: None,
+        });
 
         tracing::info!("[model] Download completed: {:?}", model_path);
         Ok(model_path)
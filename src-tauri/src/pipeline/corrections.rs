@@ -0,0 +1,129 @@
+use crate::db::search::fold_char;
+
+// Longer replaced runs are usually rewrites rather than mis-heard words.
+const MAX_PHRASE_WORDS: usize = 3;
+// Size of the LCS table; edits bigger than this aren't worth learning from.
+const MAX_DIFF_CELLS: usize = 250_000;
+
+fn strip(word: &str) -> &str {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+}
+
+pub fn fold(text: &str) -> String {
+    text.split_whitespace()
+        .map(strip)
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .map(fold_char)
+        .collect()
+}
+
+fn phrase(words: &[&str]) -> String {
+    words.iter().map(|w| strip(w)).collect::<Vec<_>>().join(" ")
+}
+
+// Word-level substitutions between the text before and after a manual edit, as (heard, corrected)
+// pairs. Punctuation and case changes are ignored, as are pure insertions and deletions.
+pub fn substitutions(before: &str, after: &str) -> Vec<(String, String)> {
+    let old: Vec<&str> = before.split_whitespace().filter(|w| !strip(w).is_empty()).collect();
+    let new: Vec<&str> = after.split_whitespace().filter(|w| !strip(w).is_empty()).collect();
+    let old_keys: Vec<String> = old.iter().map(|w| fold(w)).collect();
+    let new_keys: Vec<String> = new.iter().map(|w| fold(w)).collect();
+
+    let prefix = old_keys.iter().zip(&new_keys).take_while(|(a, b)| a == b).count();
+    let suffix = old_keys[prefix..]
+        .iter()
+        .rev()
+        .zip(new_keys[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old_keys[prefix..old_keys.len() - suffix];
+    let b = &new_keys[prefix..new_keys.len() - suffix];
+    if a.len() * b.len() > MAX_DIFF_CELLS {
+        return Vec::new();
+    }
+
+    // lcs[i][j] is the common subsequence length of a[i..] and b[j..].
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let (mut run_i, mut run_j) = (0, 0);
+    loop {
+        let done = i == a.len() && j == b.len();
+        if done || (i < a.len() && j < b.len() && a[i] == b[j]) {
+            let removed = &old[prefix + run_i..prefix + i];
+            let added = &new[prefix + run_j..prefix + j];
+            if !removed.is_empty()
+                && !added.is_empty()
+                && removed.len() <= MAX_PHRASE_WORDS
+                && added.len() <= MAX_PHRASE_WORDS
+            {
+                result.push((phrase(removed), phrase(added)));
+            }
+            if done {
+                break;
+            }
+            i += 1;
+            j += 1;
+            run_i = i;
+            run_j = j;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expected(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(a, b)| (a.to_string(), b.to_string())).collect()
+    }
+
+    #[test]
+    fn ignores_insertions_and_deletions() {
+        assert!(substitutions("I like tea", "I really like tea").is_empty());
+        assert!(substitutions("I really like tea", "I like tea").is_empty());
+        assert!(substitutions("Hello, world.", "hello world").is_empty());
+    }
+
+    #[test]
+    fn finds_single_word_substitutions() {
+        assert_eq!(substitutions("I use rast.", "I use Rust."), expected(&[("rast", "Rust")]));
+        assert_eq!(
+            substitutions("a kat and a dag", "a cat and a dog"),
+            expected(&[("kat", "cat"), ("dag", "dog")])
+        );
+    }
+
+    #[test]
+    fn finds_phrase_substitutions() {
+        assert_eq!(substitutions("open kay tab now", "open Kateb now"), expected(&[("kay tab", "Kateb")]));
+        assert_eq!(substitutions("قال الذكاء الصناعي", "قال الذكاء الاصطناعي"), expected(&[("الصناعي", "الاصطناعي")]));
+        assert_eq!(
+            substitutions("one two three four", "uno dos tres four"),
+            expected(&[("one two three", "uno dos tres")])
+        );
+    }
+
+    #[test]
+    fn skips_long_rewrites() {
+        assert!(substitutions("one two three four five", "uno dos tres cuatro five").is_empty());
+    }
+}
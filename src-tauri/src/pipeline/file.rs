@@ -0,0 +1,108 @@
+use crate::audio::noise_suppressor::NoiseSuppressor;
+use crate::audio::preprocessor::AudioPreprocessor;
+use crate::constants::audio::{FILE_WINDOW_SAMPLES, SAMPLE_RATE};
+use crate::events::{EventSink, PipelineEvent};
+use crate::pipeline::hallucination::is_chunk_hallucination;
+use crate::whisper::transcriber::{TranscriptSegment, WhisperTranscriber};
+use std::sync::Mutex;
+
+fn denoise_window(ns: &NoiseSuppressor, window: &[f32]) -> Vec<f32> {
+    if !ns.is_enabled() {
+        return window.to_vec();
+    }
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| ns.suppress(window))) {
+        Ok(audio) => {
+            if audio.iter().any(|v| !v.is_finite()) {
+                tracing::warn!("[file] Noise suppression produced NaN/Inf, using raw audio");
+                window.to_vec()
+            } else {
+                audio
+            }
+        }
+        Err(e) => {
+            tracing::warn!("[file] Noise suppression panicked: {:?}, using raw audio", e);
+            window.to_vec()
+        }
+    }
+}
+
+pub fn segments_text(segments: &[TranscriptSegment]) -> String {
+    segments
+        .iter()
+        .map(|s| s.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn transcribe_samples(
+    transcriber: &Mutex<WhisperTranscriber>,
+    samples: &[f32],
+    noise_suppression: bool,
+    label: &str,
+    sink: &dyn EventSink,
+) -> Result<Vec<TranscriptSegment>, String> {
+    let total = samples.len();
+    let window_count = total.div_ceil(FILE_WINDOW_SAMPLES).max(1);
+
+    tracing::info!(
+        "[file] Transcribing '{}': {:.1}s in {} window(s)",
+        label,
+        total as f64 / SAMPLE_RATE as f64,
+        window_count
+    );
+
+    let ns = NoiseSuppressor::new(noise_suppression);
+    let mut segments: Vec<TranscriptSegment> = Vec::new();
+
+    for (index, window) in samples.chunks(FILE_WINDOW_SAMPLES).enumerate() {
+        let denoised = denoise_window(&ns, window);
+        let processed = AudioPreprocessor::process(&denoised);
+
+        if processed.iter().any(|v| !v.is_finite()) {
+            tracing::warn!("[file] Window {} has NaN/Inf values, skipping", index);
+        } else {
+            let result = {
+                let transcriber = transcriber.lock().map_err(|e| e.to_string())?;
+                transcriber.transcribe_segments(&processed)
+            };
+            match result {
+                Ok(window_segments) if !window_segments.is_empty() => {
+                    let window_text = segments_text(&window_segments);
+                    let window_duration = window.len() as f32 / SAMPLE_RATE as f32;
+                    if is_chunk_hallucination(&window_text, window_duration) {
+                        tracing::debug!("[file] Filtered window hallucination: '{}'", window_text);
+                    } else {
+                        let offset_ms = (index * FILE_WINDOW_SAMPLES) as i64 * 1000 / SAMPLE_RATE as i64;
+                        segments.extend(window_segments.into_iter().map(|s| TranscriptSegment {
+                            start_ms: s.start_ms + offset_ms,
+                            end_ms: s.end_ms + offset_ms,
+                            text: s.text,
+                        }));
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::error!("[file] Window {} transcription error: {}", index, e);
+                    return Err(e.to_string());
+                }
+            }
+        }
+
+        let processed_samples = ((index + 1) * FILE_WINDOW_SAMPLES).min(total);
+        let progress = if total == 0 {
+            100.0
+        } else {
+            (processed_samples as f64 / total as f64 * 100.0).min(100.0)
+        };
+        sink.send(PipelineEvent::FileProgress {
+            path: label.to_string(),
+            progress,
+            processed: processed_samples,
+            total,
+            window: index + 1,
+            windows: window_count,
+        });
+    }
+
+    Ok(segments)
+}
@@ -0,0 +1,84 @@
+use crate::constants::hallucination::{CONTAINS_PATTERNS, EXACT_PATTERNS};
+use crate::whisper::transcriber::TranscriptSegment;
+
+pub fn clean_trailing_hallucinations(text: &str) -> String {
+    let mut result = text.trim().to_string();
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+        let trimmed = result.trim_end();
+        let cleaned = trimmed
+            .trim_end_matches(|c: char| matches!(c, '.' | '،' | '؟' | '!' | '؛'))
+            .trim_end();
+
+        for pattern in CONTAINS_PATTERNS.iter().chain(EXACT_PATTERNS.iter()) {
+            if cleaned.ends_with(pattern) {
+                let prefix = &cleaned[..cleaned.len() - pattern.len()];
+                if prefix.is_empty() || prefix.ends_with(' ') || prefix.ends_with('\n') {
+                    result = prefix.trim_end().to_string();
+                    changed = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    result.trim().to_string()
+}
+
+// Cuts timed segments back to `cleaned`, the result of clean_trailing_hallucinations on their
+// joined text, so saved timings don't keep the words it dropped.
+pub fn trim_segments(segments: &mut Vec<TranscriptSegment>, cleaned: &str) {
+    let words: Vec<&str> = cleaned.split_whitespace().collect();
+    let mut used = 0;
+    segments.retain_mut(|segment| {
+        let count = segment.text.split_whitespace().count().min(words.len() - used);
+        if count == 0 {
+            return false;
+        }
+        segment.text = words[used..used + count].join(" ");
+        used += count;
+        true
+    });
+}
+
+pub fn is_chunk_hallucination(text: &str, audio_duration_secs: f32) -> bool {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return true;
+    }
+    let char_count = trimmed.chars().count();
+    if char_count < 2 {
+        return true;
+    }
+    if audio_duration_secs > 2.0 && char_count < 3 {
+        return true;
+    }
+    for pattern in CONTAINS_PATTERNS {
+        if trimmed.contains(pattern) {
+            return true;
+        }
+    }
+    for pattern in EXACT_PATTERNS {
+        if trimmed == *pattern {
+            return true;
+        }
+    }
+    let chars: Vec<char> = trimmed.chars().collect();
+    if chars.len() > 2 {
+        let first = chars[0];
+        if chars.iter().all(|&c| c == first || c == ' ') {
+            return true;
+        }
+    }
+    let words: Vec<&str> = trimmed.split_whitespace().collect();
+    if words.len() >= 4 {
+        let first_word = words[0];
+        let repeat_count = words.iter().filter(|&&w| w == first_word).count();
+        if repeat_count as f32 / words.len() as f32 > 0.5 {
+            return true;
+        }
+    }
+    false
+}
@@ -0,0 +1,7 @@
+pub mod corrections;
+pub mod file;
+pub mod hallucination;
+pub mod postprocess;
+pub mod refine;
+pub mod streaming;
+pub mod vocabulary;
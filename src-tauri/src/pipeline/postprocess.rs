@@ -0,0 +1,41 @@
+use crate::commands::voice_commands::VoiceCommandProcessor;
+use crate::pipeline::file::segments_text;
+use crate::pipeline::hallucination::{clean_trailing_hallucinations, trim_segments};
+use crate::pipeline::vocabulary::Glossary;
+use crate::whisper::transcriber::TranscriptSegment;
+
+// Clean-up run once on a finished transcript, before AI refinement, by dictation, file
+// transcription and the CLI alike. Segments are trimmed and corrected to match the text but keep
+// spoken commands as they were said.
+pub fn post_process(
+    segments: &mut Vec<TranscriptSegment>,
+    glossary: &Glossary,
+    voice_commands: Option<&VoiceCommandProcessor>,
+    language: &str,
+) -> String {
+    let raw = segments_text(segments);
+    let text = clean_trailing_hallucinations(&raw);
+    if text.len() != raw.trim().len() {
+        tracing::debug!("[pipeline] Cleaned trailing hallucinations: '{}' -> '{}'", raw.trim(), text);
+    }
+    trim_segments(segments, &text);
+
+    for segment in segments.iter_mut() {
+        segment.text = glossary.correct(&segment.text).0;
+    }
+    let (text, count) = glossary.correct(&text);
+    if count > 0 {
+        tracing::debug!("[vocabulary] Corrected {} term(s): '{}'", count, text);
+    }
+
+    match voice_commands {
+        Some(processor) => {
+            let result = processor.process_text(&text, language);
+            if result.had_commands {
+                tracing::debug!("[voice_commands] Processed: '{}' -> '{}'", text, result.text);
+            }
+            result.text
+        }
+        None => text,
+    }
+}
@@ -0,0 +1,37 @@
+use crate::ai::provider::AIRefiner;
+use crate::events::EventSink;
+
+pub const MAX_RETRIES: u32 = 2;
+
+pub async fn refine_with_retries(
+    refiner: &dyn AIRefiner,
+    text: &str,
+    language: &str,
+    instructions: &str,
+    sink: &dyn EventSink,
+) -> String {
+    let mut result = text.to_string();
+    for attempt in 0..=MAX_RETRIES {
+        match refiner.refine_streaming(text, language, instructions, sink).await {
+            Ok(refined) if !refined.trim().is_empty() => {
+                tracing::debug!("[ai] Refinement successful (attempt {})", attempt + 1);
+                result = refined;
+                break;
+            }
+            Ok(_) => {
+                tracing::warn!("[ai] Refinement returned empty, using original");
+                break;
+            }
+            Err(e) => {
+                if attempt < MAX_RETRIES {
+                    let delay = std::time::Duration::from_millis(500 * 2u64.pow(attempt));
+                    tracing::warn!("[ai] Attempt {} failed: {}, retrying in {:?}...", attempt + 1, e, delay);
+                    tokio::time::sleep(delay).await;
+                } else {
+                    tracing::error!("[ai] All {} attempts failed, using original: {}", MAX_RETRIES + 1, e);
+                }
+            }
+        }
+    }
+    result
+}
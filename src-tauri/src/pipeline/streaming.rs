@@ -0,0 +1,244 @@
+use crate::constants::audio::{OVERLAP_SAMPLES, SAMPLE_RATE};
+use crate::db::search::normalize_arabic;
+use crate::pipeline::vocabulary::estimate_tokens;
+
+// The last whole words of the accumulated chunks that fit in the token budget.
+pub fn context_tail(parts: &[String], budget: usize) -> String {
+    let mut words: Vec<&str> = Vec::new();
+    let mut used = 0;
+    for word in parts.iter().rev().flat_map(|p| p.split_whitespace().rev()) {
+        let cost = estimate_tokens(word) + usize::from(!words.is_empty());
+        if used + cost > budget {
+            break;
+        }
+        used += cost;
+        words.push(word);
+    }
+    words.reverse();
+    words.join(" ")
+}
+
+pub const MAX_OVERLAP_WORDS: usize = 8;
+
+// Words that fit in the re-fed audio at the previous chunk's speaking rate, plus slack.
+pub fn overlap_word_limit(previous: &str, duration_secs: f32) -> usize {
+    if duration_secs <= 0.0 {
+        return MAX_OVERLAP_WORDS;
+    }
+    let rate = previous.split_whitespace().count() as f32 / duration_secs;
+    let overlap_secs = OVERLAP_SAMPLES as f32 / SAMPLE_RATE as f32;
+    ((rate * overlap_secs).ceil() as usize + 2).clamp(2, MAX_OVERLAP_WORDS)
+}
+
+fn word_key(word: &str) -> Vec<char> {
+    normalize_arabic(word.trim_matches(|c: char| !c.is_alphanumeric()))
+        .chars()
+        .collect()
+}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (diagonal + usize::from(ca != cb))
+                .min(row[j] + 1)
+                .min(above + 1);
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+const MIN_CUT_CHARS: usize = 3;
+// Right after a matching word even a two-letter cut is reliable ("store to" / "store today").
+const MIN_ANCHORED_CUT_CHARS: usize = 2;
+
+// A word cut off at the end of a chunk comes back as a prefix of the full word.
+fn is_cut(short: &[char], long: &[char], min_chars: usize) -> bool {
+    short.len() >= min_chars && short.len() < long.len() && long.starts_with(short)
+}
+
+fn min_cut_chars(prev_span: usize, next_span: usize) -> usize {
+    if prev_span > 1 && next_span > 1 {
+        MIN_ANCHORED_CUT_CHARS
+    } else {
+        MIN_CUT_CHARS
+    }
+}
+
+// Spelling variants Whisper produces for the same word in two decodes, or the word's
+// cut-off start or end at a chunk boundary.
+fn similar(a: &[char], b: &[char]) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if a == b || is_cut(short, long, MIN_CUT_CHARS) || (short.len() >= 3 && long.ends_with(short)) {
+        return true;
+    }
+    let longest = a.len().max(b.len());
+    let allowed = match longest {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    };
+    allowed > 0 && edit_distance(a, b) <= allowed
+}
+
+fn aligned(a: &[Vec<char>], b: &[Vec<char>]) -> usize {
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in 0..a.len() {
+        for j in 0..b.len() {
+            lcs[i + 1][j + 1] = if similar(&a[i], &b[j]) {
+                lcs[i][j] + 1
+            } else {
+                lcs[i][j + 1].max(lcs[i + 1][j])
+            };
+        }
+    }
+    lcs[a.len()][b.len()]
+}
+
+pub struct Overlap {
+    // When the previous chunk's last word was cut off: how many of its trailing words to replace,
+    // and the next chunk's version of them.
+    pub repair: Option<(usize, String)>,
+    // The next chunk without the words it repeats.
+    pub rest: String,
+    pub words: usize,
+}
+
+// Aligns the end of the previous chunk with the start of the next one. Both ends of the span must
+// line up and at least three quarters of it must match, allowing a word dropped or added on one side.
+pub fn find_overlap(previous: &str, next: &str, max_words: usize) -> Option<Overlap> {
+    let prev_words: Vec<&str> = previous
+        .split_whitespace()
+        .filter(|w| !word_key(w).is_empty())
+        .collect();
+    let next_words: Vec<&str> = next
+        .split_whitespace()
+        .filter(|w| !word_key(w).is_empty())
+        .collect();
+    let prev_keys: Vec<Vec<char>> = prev_words.iter().map(|w| word_key(w)).collect();
+    let next_keys: Vec<Vec<char>> = next_words.iter().map(|w| word_key(w)).collect();
+    let last = prev_keys.last()?;
+
+    // (matched, prev span, next span)
+    let mut best: Option<(usize, usize, usize)> = None;
+    for kp in 1..=max_words.min(prev_keys.len()) {
+        let prev_span = &prev_keys[prev_keys.len() - kp..];
+        for kn in kp.saturating_sub(1).max(1)..=(kp + 1).min(max_words).min(next_keys.len()) {
+            let next_span = &next_keys[..kn];
+            let tail = &next_span[kn - 1];
+            if !similar(&prev_span[0], &next_span[0])
+                || !(similar(last, tail) || is_cut(last, tail, min_cut_chars(kp, kn)))
+            {
+                continue;
+            }
+            // The ends are paired, so only the words before them are aligned.
+            let matched = aligned(&prev_span[..kp - 1], &next_span[..kn - 1]) + 1;
+            if matched * 4 < kp.max(kn) * 3 {
+                continue;
+            }
+            if best.is_none_or(|(m, p, n)| (matched, kp == kn, kn) > (m, p == n, n)) {
+                best = Some((matched, kp, kn));
+            }
+        }
+    }
+
+    let (_, kp, kn) = best?;
+    let repair = is_cut(last, &next_keys[kn - 1], min_cut_chars(kp, kn))
+        .then(|| (kp, next_words[..kn].join(" ")));
+    Some(Overlap {
+        repair,
+        rest: next_words[kn..].join(" "),
+        words: kn,
+    })
+}
+
+// Swaps the last `count` words of `text`, or None if it has fewer words than that.
+pub fn replace_tail(text: &str, count: usize, replacement: &str) -> Option<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let keep = words.len().checked_sub(count)?;
+    Some(
+        words[..keep]
+            .iter()
+            .copied()
+            .chain(std::iter::once(replacement))
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drops_repeated_words() {
+        let overlap = find_overlap(
+            "I said hello world",
+            "hello world how are you",
+            MAX_OVERLAP_WORDS,
+        )
+        .unwrap();
+        assert_eq!(overlap.repair, None);
+        assert_eq!(overlap.rest, "how are you");
+        assert_eq!(overlap.words, 2);
+    }
+
+    #[test]
+    fn matches_arabic_spelling_variants() {
+        let overlap =
+            find_overlap("ذهبت إلى المدرسة", "الى المدرسه صباحا", MAX_OVERLAP_WORDS).unwrap();
+        assert_eq!(overlap.repair, None);
+        assert_eq!(overlap.rest, "صباحا");
+    }
+
+    #[test]
+    fn repairs_cut_word() {
+        let overlap =
+            find_overlap("we met yester", "yesterday at noon", MAX_OVERLAP_WORDS).unwrap();
+        assert_eq!(overlap.repair, Some((1, "yesterday".to_string())));
+        assert_eq!(overlap.rest, "at noon");
+    }
+
+    #[test]
+    fn repairs_two_letter_cut_after_matching_word() {
+        let overlap = find_overlap(
+            "we went to the store to",
+            "store today and bought milk",
+            MAX_OVERLAP_WORDS,
+        )
+        .unwrap();
+        assert_eq!(overlap.repair, Some((2, "store today".to_string())));
+        assert_eq!(overlap.rest, "and bought milk");
+    }
+
+    #[test]
+    fn ignores_lone_two_letter_prefix() {
+        assert!(find_overlap("we went to", "today we left", MAX_OVERLAP_WORDS).is_none());
+    }
+
+    #[test]
+    fn no_overlap() {
+        assert!(find_overlap("good morning", "see you later", MAX_OVERLAP_WORDS).is_none());
+        assert!(find_overlap("", "see you later", MAX_OVERLAP_WORDS).is_none());
+    }
+
+    #[test]
+    fn respects_word_limit() {
+        assert!(find_overlap("one two three", "one two three four", 2).is_none());
+        assert!(find_overlap("one two three", "one two three four", 3).is_some());
+    }
+
+    #[test]
+    fn replaces_tail_words() {
+        assert_eq!(
+            replace_tail("we went to the store to", 2, "store today").as_deref(),
+            Some("we went to the store today")
+        );
+        assert_eq!(replace_tail("to", 1, "today").as_deref(), Some("today"));
+        assert_eq!(replace_tail("to", 2, "store today"), None);
+    }
+}
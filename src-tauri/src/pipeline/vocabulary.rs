@@ -0,0 +1,296 @@
+use crate::db::models::VocabularyTerm;
+use crate::db::search::fold_char;
+
+// Whisper's tokenizer isn't available here, so this overestimates: Arabic and other
+// non-ASCII text often costs a token per character, Latin text about one per three.
+pub fn estimate_tokens(text: &str) -> usize {
+    let ascii = text.chars().filter(|c| c.is_ascii()).count();
+    let other = text.chars().count() - ascii;
+    other + ascii.div_ceil(3)
+}
+
+// Splits the free-text vocabulary field (commas, Arabic commas, semicolons or new lines).
+pub fn split_terms(text: &str) -> Vec<String> {
+    text.split([',', '،', ';', '؛', '\n'])
+        .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+fn key(text: &str) -> Vec<char> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    words.join(" ").chars().map(fold_char).collect()
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric()
+}
+
+fn words(folded: &[char]) -> Vec<String> {
+    folded
+        .split(|c| !is_word_char(*c))
+        .filter(|w| !w.is_empty())
+        .map(|w| w.iter().collect())
+        .collect()
+}
+
+pub struct Glossary {
+    terms: Vec<VocabularyTerm>,
+    language: String,
+    extra: Vec<String>,
+    // Folded spelling -> canonical term, longest first.
+    replacements: Vec<(Vec<char>, String)>,
+}
+
+impl Glossary {
+    pub fn new() -> Self {
+        Self {
+            terms: Vec::new(),
+            language: "ar".to_string(),
+            extra: Vec::new(),
+            replacements: Vec::new(),
+        }
+    }
+
+    pub fn set_terms(&mut self, terms: Vec<VocabularyTerm>) {
+        self.terms = terms;
+        self.rebuild();
+    }
+
+    pub fn set_language(&mut self, language: &str) {
+        self.language = language.to_string();
+        self.rebuild();
+    }
+
+    // Terms from the free-text field (global or app profile); they go into the prompt first.
+    pub fn set_extra_terms(&mut self, text: &str) {
+        self.extra = split_terms(text);
+    }
+
+    fn applies(&self, term: &VocabularyTerm) -> bool {
+        term.enabled && term.language.as_deref().is_none_or(|l| l == self.language)
+    }
+
+    fn rebuild(&mut self) {
+        let mut replacements: Vec<(Vec<char>, String)> = Vec::new();
+        for term in self.terms.iter().filter(|t| self.applies(t)) {
+            // The term itself is included so case and Arabic spelling variants get normalized too.
+            for spelling in std::iter::once(&term.term).chain(&term.heard_as) {
+                let k = key(spelling);
+                if !k.is_empty() && !replacements.iter().any(|(existing, _)| *existing == k) {
+                    replacements.push((k, term.term.clone()));
+                }
+            }
+        }
+        replacements.sort_by_key(|r| std::cmp::Reverse(r.0.len()));
+        self.replacements = replacements;
+    }
+
+    // Profile terms first, then the glossary by priority, exact language match and how often
+    // each term has actually come up in dictations.
+    fn ranked(&self) -> Vec<&str> {
+        let mut terms: Vec<&VocabularyTerm> = self.terms.iter().filter(|t| self.applies(t)).collect();
+        terms.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then_with(|| b.language.is_some().cmp(&a.language.is_some()))
+                .then_with(|| b.hits.cmp(&a.hits))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        self.extra
+            .iter()
+            .map(String::as_str)
+            .chain(terms.into_iter().map(|t| t.term.as_str()))
+            .collect()
+    }
+
+    pub fn prompt(&self, budget: usize) -> String {
+        self.prompt_with_stats(budget).0
+    }
+
+    // Terms that don't fit are skipped rather than truncating, so shorter ones further down still get in.
+    // Returns the prompt with how many distinct terms made it in out of how many apply.
+    pub fn prompt_with_stats(&self, budget: usize) -> (String, usize, usize) {
+        let separator = if self.language == "ar" { "، " } else { ", " };
+        let mut seen: Vec<Vec<char>> = Vec::new();
+        let mut skipped: Vec<Vec<char>> = Vec::new();
+        let mut prompt = String::new();
+        let mut used = 0;
+
+        for term in self.ranked() {
+            let k = key(term);
+            if seen.contains(&k) {
+                continue;
+            }
+            let cost = estimate_tokens(term) + if prompt.is_empty() { 0 } else { estimate_tokens(separator) };
+            if used + cost > budget {
+                if !skipped.contains(&k) {
+                    skipped.push(k);
+                }
+                continue;
+            }
+            if !prompt.is_empty() {
+                prompt.push_str(separator);
+            }
+            prompt.push_str(term);
+            used += cost;
+            seen.push(k);
+        }
+        let total = seen.len() + skipped.iter().filter(|k| !seen.contains(k)).count();
+        (prompt, seen.len(), total)
+    }
+
+    pub fn correct(&self, text: &str) -> (String, usize) {
+        if self.replacements.is_empty() {
+            return (text.to_string(), 0);
+        }
+
+        let chars: Vec<char> = text.chars().collect();
+        let folded: Vec<char> = chars.iter().map(|c| fold_char(*c)).collect();
+        let mut result = String::with_capacity(text.len());
+        let mut count = 0;
+        let mut pos = 0;
+
+        while pos < chars.len() {
+            let at_boundary = pos == 0 || !is_word_char(folded[pos - 1]);
+            let found = at_boundary
+                .then(|| {
+                    self.replacements.iter().find(|(k, _)| {
+                        let end = pos + k.len();
+                        end <= folded.len()
+                            && folded[pos..end] == k[..]
+                            && (end == folded.len() || !is_word_char(folded[end]))
+                    })
+                })
+                .flatten();
+
+            match found {
+                Some((k, term)) => {
+                    let original: String = chars[pos..pos + k.len()].iter().collect();
+                    if original != *term {
+                        count += 1;
+                    }
+                    result.push_str(term);
+                    pos += k.len();
+                }
+                None => {
+                    result.push(chars[pos]);
+                    pos += 1;
+                }
+            }
+        }
+        (result, count)
+    }
+
+    pub fn record_hits(&mut self, ids: &[i64]) {
+        for term in self.terms.iter_mut().filter(|t| ids.contains(&t.id)) {
+            term.hits += 1;
+        }
+    }
+
+    // Glossary terms that appear in the final text, used to rank the prompt.
+    pub fn matched_ids(&self, text: &str) -> Vec<i64> {
+        let folded: Vec<char> = text.chars().map(fold_char).collect();
+        let text_words = words(&folded);
+        self.terms
+            .iter()
+            .filter(|t| self.applies(t))
+            .filter(|t| {
+                let term_words = words(&key(&t.term));
+                !term_words.is_empty() && text_words.windows(term_words.len()).any(|w| w == term_words.as_slice())
+            })
+            .map(|t| t.id)
+            .collect()
+    }
+}
+
+impl Default for Glossary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(id: i64, text: &str, heard_as: &[&str], priority: i64) -> VocabularyTerm {
+        VocabularyTerm {
+            id,
+            term: text.to_string(),
+            heard_as: heard_as.iter().map(|h| h.to_string()).collect(),
+            language: None,
+            priority,
+            enabled: true,
+            hits: 0,
+        }
+    }
+
+    fn glossary(language: &str, terms: Vec<VocabularyTerm>) -> Glossary {
+        let mut glossary = Glossary::new();
+        glossary.set_language(language);
+        glossary.set_terms(terms);
+        glossary
+    }
+
+    #[test]
+    fn estimates_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abc"), 1);
+        assert_eq!(estimate_tokens("abcd"), 2);
+        assert_eq!(estimate_tokens("مرحبا"), 5);
+        assert_eq!(estimate_tokens("Kateb كاتب"), 6);
+    }
+
+    #[test]
+    fn splits_terms() {
+        assert_eq!(
+            split_terms("Tauri، Rust;  Whisper   AI\n\n,؛x"),
+            vec!["Tauri", "Rust", "Whisper AI", "x"]
+        );
+    }
+
+    #[test]
+    fn corrects_case_and_spacing() {
+        let glossary = glossary("en", vec![term(1, "Kateb", &["  kay   tab "], 0)]);
+        assert_eq!(glossary.correct("open KAY tab now"), ("open Kateb now".to_string(), 1));
+        assert_eq!(glossary.correct("open kateb"), ("open Kateb".to_string(), 1));
+        assert_eq!(glossary.correct("open Kateb"), ("open Kateb".to_string(), 0));
+        assert_eq!(glossary.correct("kay tabs"), ("kay tabs".to_string(), 0));
+    }
+
+    #[test]
+    fn corrects_arabic_spelling_variants() {
+        let glossary = glossary("ar", vec![term(1, "إسلام", &[], 0)]);
+        assert_eq!(glossary.correct("اسم اسلام"), ("اسم إسلام".to_string(), 1));
+    }
+
+    #[test]
+    fn skips_disabled_and_other_language_terms() {
+        let mut disabled = term(1, "Kateb", &["kay tab"], 0);
+        disabled.enabled = false;
+        let mut arabic = term(2, "Rust", &["rast"], 0);
+        arabic.language = Some("ar".to_string());
+        let glossary = glossary("en", vec![disabled, arabic]);
+        assert_eq!(glossary.correct("kay tab rast").1, 0);
+        assert_eq!(glossary.prompt_with_stats(100), (String::new(), 0, 0));
+    }
+
+    #[test]
+    fn skips_terms_over_budget() {
+        let glossary = glossary(
+            "en",
+            vec![term(1, "Kubernetes", &[], 2), term(2, "كاتب", &[], 1), term(3, "Rust", &[], 0)],
+        );
+        assert_eq!(glossary.prompt_with_stats(7), ("Kubernetes, Rust".to_string(), 2, 3));
+        assert_eq!(glossary.prompt_with_stats(0), (String::new(), 0, 3));
+        assert_eq!(glossary.prompt(100), "Kubernetes, كاتب, Rust");
+    }
+
+    #[test]
+    fn counts_repeated_terms_once() {
+        let mut glossary = glossary("ar", vec![term(1, "Kubernetes", &[], 0), term(2, "Rust", &[], 0)]);
+        glossary.set_extra_terms("kubernetes، Tauri");
+        assert_eq!(glossary.prompt_with_stats(100), ("kubernetes، Tauri، Rust".to_string(), 3, 3));
+    }
+}
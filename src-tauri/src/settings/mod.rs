@@ -0,0 +1,413 @@
+pub mod observers;
+pub mod profiles;
+pub mod schema;
+
+use crate::db::Database;
+use crate::security::keychain;
+use crate::shortcuts::{self, ShortcutAction};
+use crate::whisper::transcriber::{Decoding, DEFAULT_BEAM_SIZE};
+use schema::{SettingSpec, SettingType, SCHEMA};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, RwLock};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Settings {
+    pub shortcut: String,
+    pub shortcut_bindings: BTreeMap<ShortcutAction, String>,
+    pub shortcut_mode: String,
+    pub push_to_talk_min_hold_ms: u64,
+    pub language: String,
+    pub auto_punctuation: bool,
+    pub sound_notifications: bool,
+    pub auto_start: bool,
+    pub max_recording_duration: u32,
+    pub auto_type: bool,
+    pub insertion_method: String,
+    pub insertion_fallback: String,
+    pub paste_chord: String,
+    pub paste_delay_ms: u64,
+    pub live_typing: bool,
+    pub undo_method: String,
+    pub undo_restore_clipboard: bool,
+    pub use_gpu: bool,
+    pub decoding_profile: String,
+    pub beam_size: i32,
+    pub ai_refinement: bool,
+    pub ai_provider: String,
+    pub ai_instructions: String,
+    pub auto_stop_silence: bool,
+    pub auto_stop_seconds: f32,
+    pub custom_vocabulary: String,
+    pub noise_suppression: bool,
+    pub voice_commands: bool,
+    pub active_model: String,
+    pub audio_device: String,
+    pub api_urls: HashMap<String, String>,
+    #[serde(skip)]
+    api_keys: HashMap<String, String>,
+}
+
+// Missing or invalid stored values fall back to the schema default.
+fn typed_value(values: &HashMap<String, String>, spec: &SettingSpec) -> String {
+    let stored = values
+        .get(spec.key)
+        .and_then(|v| spec.normalize(v).ok())
+        .unwrap_or_else(|| spec.stored_default());
+    spec.unquote(&stored)
+}
+
+impl Settings {
+    pub fn from_values(values: &HashMap<String, String>) -> Self {
+        let mut typed: HashMap<&str, String> = SCHEMA
+            .iter()
+            .map(|spec| (spec.key, typed_value(values, spec)))
+            .collect();
+        let mut take = |key: &str| typed.remove(key).unwrap_or_default();
+
+        let api_urls = schema::AI_PROVIDERS
+            .iter()
+            .map(|p| (p.to_string(), values.get(&format!("{}_api_url", p)).cloned().unwrap_or_default()))
+            .collect();
+        let api_keys = schema::AI_PROVIDERS
+            .iter()
+            .map(|p| (p.to_string(), values.get(&format!("{}_api_key", p)).cloned().unwrap_or_default()))
+            .collect();
+
+        Settings {
+            auto_punctuation: take("auto_punctuation") == "true",
+            sound_notifications: take("sound_notifications") == "true",
+            auto_start: take("auto_start") == "true",
+            auto_type: take("auto_type") == "true",
+            live_typing: take("live_typing") == "true",
+            undo_restore_clipboard: take("undo_restore_clipboard") == "true",
+            use_gpu: take("use_gpu") == "true",
+            ai_refinement: take("ai_refinement") == "true",
+            auto_stop_silence: take("auto_stop_silence") == "true",
+            noise_suppression: take("noise_suppression") == "true",
+            voice_commands: take("voice_commands") == "true",
+            shortcut: take("shortcut"),
+            insertion_method: take("insertion_method"),
+            insertion_fallback: take("insertion_fallback"),
+            paste_chord: take("paste_chord"),
+            paste_delay_ms: take("paste_delay_ms").parse().unwrap_or(100),
+            undo_method: take("undo_method"),
+            decoding_profile: take("decoding_profile"),
+            beam_size: take("beam_size").parse().unwrap_or(DEFAULT_BEAM_SIZE),
+            shortcut_bindings: shortcuts::parse_bindings(&take("shortcut_bindings")),
+            shortcut_mode: take("shortcut_mode"),
+            push_to_talk_min_hold_ms: take("push_to_talk_min_hold_ms").parse().unwrap_or(300),
+            language: take("language"),
+            max_recording_duration: take("max_recording_duration").parse().unwrap_or(300),
+            ai_provider: take("ai_provider"),
+            ai_instructions: take("ai_instructions"),
+            auto_stop_seconds: take("auto_stop_seconds").parse().unwrap_or(10.0),
+            custom_vocabulary: take("custom_vocabulary"),
+            active_model: take("active_model"),
+            audio_device: take("audio_device"),
+            api_urls,
+            api_keys,
+        }
+    }
+
+    // The dictation toggle lives in `shortcut`; every other action in `shortcut_bindings`.
+    pub fn bindings(&self) -> Vec<(ShortcutAction, String)> {
+        let mut bindings = vec![(ShortcutAction::ToggleDictation, self.shortcut.clone())];
+        bindings.extend(self.shortcut_bindings.iter().map(|(a, s)| (*a, s.clone())));
+        bindings
+    }
+
+    // decoding_profile is validated against DECODING_PROFILES on write.
+    pub fn decoding(&self) -> Option<Decoding> {
+        Decoding::parse(&self.decoding_profile, self.beam_size).ok().flatten()
+    }
+
+    pub fn api_url(&self, provider: &str) -> Option<String> {
+        self.api_urls.get(provider).filter(|u| !u.is_empty()).cloned()
+    }
+
+    pub fn api_key(&self, provider: &str) -> Option<String> {
+        let key_name = format!("{}_api_key", provider);
+        if keychain::is_available() {
+            if let Some(val) = keychain::retrieve_api_key(&key_name) {
+                return Some(val);
+            }
+        }
+        self.api_keys.get(provider).filter(|k| !k.is_empty()).cloned()
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::from_values(&HashMap::new())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingChange {
+    pub key: String,
+    pub value: String,
+}
+
+impl SettingChange {
+    pub fn is_secret(&self) -> bool {
+        schema::find(&self.key).is_some_and(|s| s.ty == SettingType::Secret)
+    }
+}
+
+pub fn touches(changes: &[SettingChange], keys: &[&str]) -> bool {
+    changes.iter().any(|c| keys.contains(&c.key.as_str()))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingFailure {
+    pub observer: String,
+    pub error: String,
+}
+
+impl std::fmt::Display for SettingFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.observer, self.error)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SettingsUpdate {
+    pub changes: Vec<SettingChange>,
+    pub failures: Vec<SettingFailure>,
+}
+
+impl SettingsUpdate {
+    pub fn failure_message(&self) -> Option<String> {
+        if self.failures.is_empty() {
+            return None;
+        }
+        let details: Vec<String> = self.failures.iter().map(|f| f.to_string()).collect();
+        Some(format!("تم حفظ الإعداد لكن تعذّر تطبيقه: {}", details.join("؛ ")))
+    }
+}
+
+type Observer = Box<dyn Fn(&[SettingChange], &Settings) -> Result<(), String> + Send + Sync>;
+
+pub struct SettingsState {
+    values: RwLock<HashMap<String, String>>,
+    current: RwLock<Arc<Settings>>,
+    observers: Mutex<Vec<(&'static str, Observer)>>,
+    // Serializes writers from reading `values` to swapping in the result, so concurrent
+    // updates can't validate against or overwrite each other's state.
+    write_lock: Mutex<()>,
+}
+
+fn read_values(conn: &rusqlite::Connection) -> rusqlite::Result<HashMap<String, String>> {
+    let mut stmt = conn.prepare("SELECT key, value FROM settings")?;
+    let values = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(values)
+}
+
+impl SettingsState {
+    pub fn load(conn: &rusqlite::Connection) -> rusqlite::Result<Self> {
+        let values = read_values(conn)?;
+        let settings = Settings::from_values(&values);
+        Ok(Self {
+            values: RwLock::new(values),
+            current: RwLock::new(Arc::new(settings)),
+            observers: Mutex::new(Vec::new()),
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    pub fn get(&self) -> Arc<Settings> {
+        self.current
+            .read()
+            .map(|s| Arc::clone(&*s))
+            .unwrap_or_else(|e| Arc::clone(&*e.into_inner()))
+    }
+
+    // Observers run once per batch of changes, after the database lock is released.
+    pub fn subscribe(
+        &self,
+        name: &'static str,
+        observer: impl Fn(&[SettingChange], &Settings) -> Result<(), String> + Send + Sync + 'static,
+    ) {
+        if let Ok(mut observers) = self.observers.lock() {
+            observers.push((name, Box::new(observer)));
+        }
+    }
+
+    pub fn set(&self, db: &Database, key: &str, raw: &str) -> Result<SettingsUpdate, String> {
+        self.set_many(db, &[(key.to_string(), raw.to_string())])
+    }
+
+    // Validates every entry before writing any, so related keys (e.g. swapped shortcuts) land together.
+    pub fn set_many(&self, db: &Database, entries: &[(String, String)]) -> Result<SettingsUpdate, String> {
+        let guard = self.write_lock.lock().map_err(|e| e.to_string())?;
+        let mut values = self.values.read().map_err(|e| e.to_string())?.clone();
+        let mut stored = Vec::with_capacity(entries.len());
+        for (key, raw) in entries {
+            let value = schema::validate(key, raw)?;
+            values.insert(key.clone(), value.clone());
+            stored.push((key.as_str(), value));
+        }
+
+        if stored.iter().any(|(k, _)| *k == "shortcut" || *k == "shortcut_bindings") {
+            shortcuts::check_conflicts(&Settings::from_values(&values).bindings())?;
+        }
+
+        {
+            let mut conn = db.0.lock().map_err(|e| e.to_string())?;
+            let tx = conn.transaction().map_err(|e| e.to_string())?;
+            for (key, value) in &stored {
+                tx.execute(
+                    "INSERT INTO settings (key, value, updated_at) VALUES (?1, ?2, datetime('now'))
+                     ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = datetime('now')",
+                    [*key, value.as_str()],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            tx.commit().map_err(|e| e.to_string())?;
+        }
+
+        let (changes, settings) = self.swap(values);
+        drop(guard);
+        Ok(self.notify(changes, &settings))
+    }
+
+    // Picks up rows written in bulk (imports, restores) and notifies only what changed.
+    pub fn reload(&self, db: &Database) -> Result<SettingsUpdate, String> {
+        let guard = self.write_lock.lock().map_err(|e| e.to_string())?;
+        let values = {
+            let conn = db.0.lock().map_err(|e| e.to_string())?;
+            read_values(&conn).map_err(|e| e.to_string())?
+        };
+        let (changes, settings) = self.swap(values);
+        drop(guard);
+        Ok(self.notify(changes, &settings))
+    }
+
+    fn swap(&self, values: HashMap<String, String>) -> (Vec<SettingChange>, Arc<Settings>) {
+        let changes: Vec<SettingChange> = {
+            let old = self.values.read().unwrap_or_else(|e| e.into_inner());
+            values
+                .iter()
+                .filter(|(k, v)| old.get(*k) != Some(*v))
+                .map(|(k, v)| SettingChange {
+                    key: k.clone(),
+                    value: v.clone(),
+                })
+                .collect()
+        };
+
+        let settings = Arc::new(Settings::from_values(&values));
+        *self.values.write().unwrap_or_else(|e| e.into_inner()) = values;
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::clone(&settings);
+        (changes, settings)
+    }
+
+    // Observers run outside the write lock so they may read or change settings themselves.
+    fn notify(&self, changes: Vec<SettingChange>, settings: &Settings) -> SettingsUpdate {
+        let mut failures = Vec::new();
+        if changes.is_empty() {
+            return SettingsUpdate { changes, failures };
+        }
+
+        let keys: Vec<&str> = changes.iter().map(|c| c.key.as_str()).collect();
+        tracing::debug!("[settings] Changed: {}", keys.join(", "));
+
+        if let Ok(observers) = self.observers.lock() {
+            for (name, observer) in observers.iter() {
+                if let Err(error) = observer(&changes, settings) {
+                    tracing::error!("[settings] Observer '{}' failed: {}", name, error);
+                    failures.push(SettingFailure {
+                        observer: name.to_string(),
+                        error,
+                    });
+                }
+            }
+        }
+        SettingsUpdate { changes, failures }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{migrations, search};
+    use rusqlite::Connection;
+
+    fn open() -> (Database, SettingsState) {
+        let mut conn = Connection::open_in_memory().unwrap();
+        search::register_functions(&conn).unwrap();
+        migrations::run_migrations(&mut conn).unwrap();
+        migrations::seed_default_settings(&conn).unwrap();
+        let state = SettingsState::load(&conn).unwrap();
+        (Database(Mutex::new(conn)), state)
+    }
+
+    fn entry(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn concurrent_writes_keep_every_change() {
+        let (db, state) = open();
+        let writes = [
+            [entry("beam_size", "7"), entry("auto_start", "true")],
+            [entry("paste_delay_ms", "250"), entry("live_typing", "true")],
+            [entry("max_recording_duration", "120"), entry("use_gpu", "true")],
+            [entry("push_to_talk_min_hold_ms", "500"), entry("noise_suppression", "true")],
+        ];
+        std::thread::scope(|s| {
+            for entries in &writes {
+                s.spawn(|| state.set_many(&db, entries).unwrap());
+            }
+        });
+
+        let reloaded = SettingsState::load(&db.0.lock().unwrap()).unwrap().get();
+        for settings in [state.get(), reloaded] {
+            assert_eq!(settings.beam_size, 7);
+            assert_eq!(settings.paste_delay_ms, 250);
+            assert_eq!(settings.max_recording_duration, 120);
+            assert_eq!(settings.push_to_talk_min_hold_ms, 500);
+            assert!(settings.auto_start && settings.live_typing);
+            assert!(settings.use_gpu && settings.noise_suppression);
+        }
+    }
+
+    #[test]
+    fn invalid_entry_writes_nothing() {
+        let (db, state) = open();
+        let result = state.set_many(&db, &[entry("beam_size", "7"), entry("auto_start", "maybe")]);
+        assert!(result.is_err());
+        assert_eq!(state.get().beam_size, 5);
+        assert_eq!(SettingsState::load(&db.0.lock().unwrap()).unwrap().get().beam_size, 5);
+    }
+
+    #[test]
+    fn notifies_only_changed_keys() {
+        let (db, state) = open();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        state.subscribe("test", move |changes, _| {
+            sink.lock().unwrap().extend(changes.iter().map(|c| c.key.clone()));
+            Ok(())
+        });
+
+        state.set(&db, "beam_size", "8").unwrap();
+        let unchanged = state.set(&db, "beam_size", "8").unwrap();
+        assert!(unchanged.changes.is_empty());
+        assert_eq!(*seen.lock().unwrap(), vec!["beam_size".to_string()]);
+    }
+
+    #[test]
+    fn reports_observer_failures() {
+        let (db, state) = open();
+        state.subscribe("broken", |_, _| Err("boom".to_string()));
+        let update = state.set(&db, "auto_start", "true").unwrap();
+        assert_eq!(update.failures.len(), 1);
+        assert!(update.failure_message().unwrap().contains("broken: boom"));
+        assert!(state.get().auto_start);
+    }
+}
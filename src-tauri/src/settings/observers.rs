@@ -0,0 +1,149 @@
+use super::{touches, SettingChange, Settings, SettingsState};
+use crate::commands::dictation::{AutoStopConfig, DictationState};
+use crate::commands::models::load_active_model;
+use crate::commands::vocabulary;
+use crate::events::{EventSink, PipelineEvent};
+use crate::shortcuts::{ShortcutConfig, ShortcutState};
+use tauri::Manager;
+use tauri_plugin_autostart::ManagerExt;
+
+fn dictation_state(app: &tauri::AppHandle) -> Result<tauri::State<'_, DictationState>, String> {
+    app.try_state::<DictationState>()
+        .ok_or_else(|| "حالة الإملاء غير جاهزة".to_string())
+}
+
+fn notify_frontend(app: &tauri::AppHandle, changes: &[SettingChange]) {
+    for change in changes {
+        app.send(PipelineEvent::SettingChanged {
+            key: change.key.clone(),
+            value: if change.is_secret() { None } else { Some(change.value.clone()) },
+        });
+    }
+}
+
+fn apply_transcriber(
+    app: &tauri::AppHandle,
+    changes: &[SettingChange],
+    settings: &Settings,
+) -> Result<(), String> {
+    let decoding = touches(changes, &["language", "custom_vocabulary", "decoding_profile", "beam_size"]);
+    let model = touches(changes, &["active_model", "use_gpu"]) && !settings.active_model.is_empty();
+    if !decoding && !model {
+        return Ok(());
+    }
+    let state = dictation_state(app)?;
+
+    if decoding {
+        {
+            let mut transcriber = state.transcriber.lock().map_err(|e| e.to_string())?;
+            transcriber.set_language(&settings.language);
+            transcriber.set_decoding(settings.decoding());
+        }
+        vocabulary::apply(&state, settings)?;
+    }
+
+    if model {
+        load_active_model(app, &state, settings)?;
+    }
+    Ok(())
+}
+
+fn apply_pipeline(
+    app: &tauri::AppHandle,
+    changes: &[SettingChange],
+    settings: &Settings,
+) -> Result<(), String> {
+    const KEYS: &[&str] = &[
+        "noise_suppression",
+        "voice_commands",
+        "language",
+        "auto_stop_silence",
+        "auto_stop_seconds",
+        "audio_device",
+    ];
+    if !touches(changes, KEYS) {
+        return Ok(());
+    }
+    let state = dictation_state(app)?;
+
+    if touches(changes, &["noise_suppression"]) {
+        let mut ns = state.noise_suppressor.lock().map_err(|e| e.to_string())?;
+        ns.set_enabled(settings.noise_suppression);
+    }
+
+    if touches(changes, &["voice_commands", "language"]) {
+        let mut vc = state.voice_commands.lock().map_err(|e| e.to_string())?;
+        vc.set_enabled(settings.voice_commands);
+        vc.set_language(&settings.language);
+    }
+
+    if touches(changes, &["auto_stop_silence", "auto_stop_seconds"]) {
+        let mut auto_stop = state.auto_stop.lock().map_err(|e| e.to_string())?;
+        *auto_stop = AutoStopConfig::from_settings(settings);
+    }
+
+    if touches(changes, &["audio_device"]) {
+        let recorder = state.recorder.lock().map_err(|e| e.to_string())?;
+        let device = Some(settings.audio_device.clone()).filter(|d| !d.is_empty());
+        recorder.set_device(device);
+    }
+    Ok(())
+}
+
+fn apply_shortcut(app: &tauri::AppHandle, settings: &Settings) -> Result<(), String> {
+    let state = app
+        .try_state::<ShortcutState>()
+        .ok_or_else(|| "حالة الاختصار غير جاهزة".to_string())?;
+    let mut current = state.0.lock().map_err(|e| e.to_string())?;
+    *current = ShortcutConfig::from_settings(settings);
+    tracing::info!(
+        "[shortcut] Updated {} binding(s) (push-to-talk: {})",
+        current.bindings.len(), current.push_to_talk
+    );
+    Ok(())
+}
+
+fn apply_autostart(app: &tauri::AppHandle, settings: &Settings) -> Result<(), String> {
+    let autolaunch = app.autolaunch();
+    let result = if settings.auto_start {
+        autolaunch.enable()
+    } else {
+        autolaunch.disable()
+    };
+    result.map_err(|e| format!("تعذّر تحديث التشغيل التلقائي: {}", e))
+}
+
+// Registered after DictationState and ShortcutState are managed.
+pub fn register(app: &tauri::AppHandle, settings: &SettingsState) {
+    let handle = app.clone();
+    settings.subscribe("events", move |changes, _| {
+        notify_frontend(&handle, changes);
+        Ok(())
+    });
+
+    let handle = app.clone();
+    settings.subscribe("transcriber", move |changes, settings| {
+        apply_transcriber(&handle, changes, settings)
+    });
+
+    let handle = app.clone();
+    settings.subscribe("pipeline", move |changes, settings| {
+        apply_pipeline(&handle, changes, settings)
+    });
+
+    let handle = app.clone();
+    settings.subscribe("shortcut", move |changes, settings| {
+        if !touches(changes, &["shortcut", "shortcut_bindings", "shortcut_mode", "push_to_talk_min_hold_ms"]) {
+            return Ok(());
+        }
+        apply_shortcut(&handle, settings)
+    });
+
+    let handle = app.clone();
+    settings.subscribe("autostart", move |changes, settings| {
+        if !touches(changes, &["auto_start"]) {
+            return Ok(());
+        }
+        apply_autostart(&handle, settings)
+    });
+}
@@ -0,0 +1,174 @@
+use super::schema::{AI_PROVIDERS, DECODING_PROFILES, INSERTION_METHODS, LANGUAGES, PASTE_CHORDS};
+use super::Settings;
+use crate::db::models::AppProfile;
+use crate::window::ActiveWindow;
+use rusqlite::{Connection, Row};
+
+const COLUMNS: &str = "id, name, match_class, match_title, enabled, priority, language, custom_vocabulary, \
+     ai_refinement, ai_provider, ai_instructions, voice_commands, insertion_method, paste_chord, decoding_profile";
+
+fn from_row(row: &Row) -> rusqlite::Result<AppProfile> {
+    Ok(AppProfile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        match_class: row.get(2)?,
+        match_title: row.get(3)?,
+        enabled: row.get(4)?,
+        priority: row.get(5)?,
+        language: row.get(6)?,
+        custom_vocabulary: row.get(7)?,
+        ai_refinement: row.get(8)?,
+        ai_provider: row.get(9)?,
+        ai_instructions: row.get(10)?,
+        voice_commands: row.get(11)?,
+        insertion_method: row.get(12)?,
+        paste_chord: row.get(13)?,
+        decoding_profile: row.get(14)?,
+    })
+}
+
+// Ordered so the first match is the one that applies.
+pub fn load(conn: &Connection) -> rusqlite::Result<Vec<AppProfile>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM app_profiles ORDER BY priority DESC, id",
+        COLUMNS
+    ))?;
+    let rows = stmt.query_map([], from_row)?;
+    rows.collect()
+}
+
+pub fn save(conn: &Connection, profile: &AppProfile) -> rusqlite::Result<i64> {
+    if profile.id > 0 {
+        conn.execute(
+            "UPDATE app_profiles SET name = ?1, match_class = ?2, match_title = ?3, enabled = ?4, priority = ?5,
+                language = ?6, custom_vocabulary = ?7, ai_refinement = ?8, ai_provider = ?9, ai_instructions = ?10,
+                voice_commands = ?11, insertion_method = ?12, paste_chord = ?13, decoding_profile = ?14,
+                updated_at = datetime('now')
+             WHERE id = ?15",
+            rusqlite::params![
+                profile.name.trim(),
+                profile.match_class.trim(),
+                profile.match_title.trim(),
+                profile.enabled,
+                profile.priority,
+                profile.language,
+                profile.custom_vocabulary,
+                profile.ai_refinement,
+                profile.ai_provider,
+                profile.ai_instructions,
+                profile.voice_commands,
+                profile.insertion_method,
+                profile.paste_chord,
+                profile.decoding_profile,
+                profile.id,
+            ],
+        )?;
+        return Ok(profile.id);
+    }
+
+    conn.execute(
+        "INSERT INTO app_profiles (name, match_class, match_title, enabled, priority, language, custom_vocabulary,
+            ai_refinement, ai_provider, ai_instructions, voice_commands, insertion_method, paste_chord, decoding_profile)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        rusqlite::params![
+            profile.name.trim(),
+            profile.match_class.trim(),
+            profile.match_title.trim(),
+            profile.enabled,
+            profile.priority,
+            profile.language,
+            profile.custom_vocabulary,
+            profile.ai_refinement,
+            profile.ai_provider,
+            profile.ai_instructions,
+            profile.voice_commands,
+            profile.insertion_method,
+            profile.paste_chord,
+            profile.decoding_profile,
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn validate(profile: &AppProfile) -> Result<(), String> {
+    if profile.name.trim().is_empty() {
+        return Err("اسم الملف الشخصي مطلوب".to_string());
+    }
+    if profile.match_class.trim().is_empty() && profile.match_title.trim().is_empty() {
+        return Err("يجب تحديد فئة النافذة أو عنوانها للمطابقة".to_string());
+    }
+
+    let check = |field: &str, value: &Option<String>, options: &[&str]| match value {
+        Some(v) if !options.contains(&v.as_str()) => Err(format!(
+            "قيمة غير صالحة للحقل {}: {} (المسموح: {})",
+            field,
+            v,
+            options.join(", ")
+        )),
+        _ => Ok(()),
+    };
+    check("language", &profile.language, LANGUAGES)?;
+    check("ai_provider", &profile.ai_provider, AI_PROVIDERS)?;
+    // "none" turns typing off for the app instead of picking a backend.
+    check("insertion_method", &profile.insertion_method, &[INSERTION_METHODS, &["none"]].concat())?;
+    check("paste_chord", &profile.paste_chord, PASTE_CHORDS)?;
+    check("decoding_profile", &profile.decoding_profile, DECODING_PROFILES)
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+// Empty patterns are wildcards, but a profile must set at least one to match anything.
+pub fn matches(profile: &AppProfile, window: &ActiveWindow) -> bool {
+    let class = profile.match_class.trim();
+    let title = profile.match_title.trim();
+    if !profile.enabled || (class.is_empty() && title.is_empty()) {
+        return false;
+    }
+    (class.is_empty() || contains_ignore_case(&window.class, class))
+        && (title.is_empty() || contains_ignore_case(&window.title, title))
+}
+
+pub fn find<'a>(profiles: &'a [AppProfile], window: &ActiveWindow) -> Option<&'a AppProfile> {
+    profiles.iter().find(|p| matches(p, window))
+}
+
+impl Settings {
+    pub fn with_profile(&self, profile: &AppProfile) -> Settings {
+        let mut settings = self.clone();
+        if let Some(language) = &profile.language {
+            settings.language = language.clone();
+        }
+        if let Some(vocabulary) = &profile.custom_vocabulary {
+            settings.custom_vocabulary = vocabulary.clone();
+        }
+        if let Some(enabled) = profile.ai_refinement {
+            settings.ai_refinement = enabled;
+        }
+        if let Some(provider) = &profile.ai_provider {
+            settings.ai_provider = provider.clone();
+        }
+        if let Some(instructions) = &profile.ai_instructions {
+            settings.ai_instructions = instructions.clone();
+        }
+        if let Some(enabled) = profile.voice_commands {
+            settings.voice_commands = enabled;
+        }
+        match profile.insertion_method.as_deref() {
+            Some("none") => settings.auto_type = false,
+            Some(method) => {
+                settings.auto_type = true;
+                settings.insertion_method = method.to_string();
+            }
+            None => {}
+        }
+        if let Some(chord) = &profile.paste_chord {
+            settings.paste_chord = chord.clone();
+        }
+        if let Some(decoding) = &profile.decoding_profile {
+            settings.decoding_profile = decoding.clone();
+        }
+        settings
+    }
+}
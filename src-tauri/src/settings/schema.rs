@@ -0,0 +1,231 @@
+use serde_json::Value;
+
+pub const LANGUAGES: &[&str] = &["ar", "en"];
+pub const SHORTCUT_MODES: &[&str] = &["toggle", "push_to_talk"];
+pub const AI_PROVIDERS: &[&str] = &["local", "claude", "openai", "gemini", "grok"];
+pub const INSERTION_METHODS: &[&str] = &["paste", "type", "primary", "clipboard"];
+pub const INSERTION_FALLBACKS: &[&str] = &["none", "paste", "type", "primary", "clipboard"];
+pub const PASTE_CHORDS: &[&str] = &["ctrl+v", "ctrl+shift+v", "shift+insert"];
+pub const UNDO_METHODS: &[&str] = &["backspace", "select"];
+// "auto" lets each transcription call pick its own default.
+pub const DECODING_PROFILES: &[&str] = &["auto", "fast", "accurate", "fallback"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettingType {
+    Bool,
+    Integer { min: i64, max: i64 },
+    Float { min: f64, max: f64 },
+    Choice(&'static [&'static str]),
+    Text,
+    Secret,
+    ShortcutMap,
+}
+
+#[derive(Debug)]
+pub struct SettingSpec {
+    pub key: &'static str,
+    pub ty: SettingType,
+    pub default: &'static str,
+    // Stored JSON-quoted in the settings table (e.g. "\"ar\"").
+    pub quoted: bool,
+}
+
+const fn plain(key: &'static str, ty: SettingType, default: &'static str) -> SettingSpec {
+    SettingSpec {
+        key,
+        ty,
+        default,
+        quoted: false,
+    }
+}
+
+const fn quoted(key: &'static str, ty: SettingType, default: &'static str) -> SettingSpec {
+    SettingSpec {
+        key,
+        ty,
+        default,
+        quoted: true,
+    }
+}
+
+pub const SCHEMA: &[SettingSpec] = &[
+    quoted("shortcut", SettingType::Text, "Ctrl+Shift+D"),
+    plain("shortcut_bindings", SettingType::ShortcutMap, "{}"),
+    plain("shortcut_mode", SettingType::Choice(SHORTCUT_MODES), "toggle"),
+    plain("push_to_talk_min_hold_ms", SettingType::Integer { min: 0, max: 2000 }, "300"),
+    quoted("language", SettingType::Choice(LANGUAGES), "ar"),
+    plain("auto_punctuation", SettingType::Bool, "true"),
+    plain("sound_notifications", SettingType::Bool, "true"),
+    plain("auto_start", SettingType::Bool, "false"),
+    quoted("whisper_model", SettingType::Text, "large-v3"),
+    plain("max_recording_duration", SettingType::Integer { min: 10, max: 3600 }, "300"),
+    plain("auto_type", SettingType::Bool, "true"),
+    plain("insertion_method", SettingType::Choice(INSERTION_METHODS), "paste"),
+    plain("insertion_fallback", SettingType::Choice(INSERTION_FALLBACKS), "clipboard"),
+    plain("paste_chord", SettingType::Choice(PASTE_CHORDS), "ctrl+v"),
+    plain("paste_delay_ms", SettingType::Integer { min: 20, max: 2000 }, "100"),
+    plain("live_typing", SettingType::Bool, "false"),
+    plain("undo_method", SettingType::Choice(UNDO_METHODS), "backspace"),
+    plain("undo_restore_clipboard", SettingType::Bool, "true"),
+    plain("use_gpu", SettingType::Bool, "false"),
+    plain("decoding_profile", SettingType::Choice(DECODING_PROFILES), "auto"),
+    plain("beam_size", SettingType::Integer { min: 2, max: 10 }, "5"),
+    plain("ai_refinement", SettingType::Bool, "false"),
+    plain("ai_provider", SettingType::Choice(AI_PROVIDERS), "local"),
+    plain("ai_instructions", SettingType::Text, ""),
+    plain("claude_api_key", SettingType::Secret, ""),
+    plain("openai_api_key", SettingType::Secret, ""),
+    plain("gemini_api_key", SettingType::Secret, ""),
+    plain("grok_api_key", SettingType::Secret, ""),
+    plain("local_api_key", SettingType::Secret, ""),
+    plain("claude_api_url", SettingType::Text, ""),
+    plain("openai_api_url", SettingType::Text, ""),
+    plain("gemini_api_url", SettingType::Text, ""),
+    plain("grok_api_url", SettingType::Text, ""),
+    plain("local_api_url", SettingType::Text, ""),
+    plain("auto_stop_silence", SettingType::Bool, "false"),
+    plain("auto_stop_seconds", SettingType::Float { min: 1.0, max: 120.0 }, "10"),
+    plain("custom_vocabulary", SettingType::Text, ""),
+    plain("noise_suppression", SettingType::Bool, "false"),
+    plain("voice_commands", SettingType::Bool, "true"),
+    plain("active_model", SettingType::Text, ""),
+    plain("audio_device", SettingType::Text, ""),
+];
+
+pub fn find(key: &str) -> Option<&'static SettingSpec> {
+    SCHEMA.iter().find(|s| s.key == key)
+}
+
+pub fn validate(key: &str, raw: &str) -> Result<String, String> {
+    find(key)
+        .ok_or_else(|| format!("إعداد غير معروف: {}", key))?
+        .normalize(raw)
+}
+
+pub fn validate_json(key: &str, value: &Value) -> Result<String, String> {
+    let spec = find(key).ok_or_else(|| format!("إعداد غير معروف: {}", key))?;
+    match value {
+        Value::String(s) => spec.normalize(s),
+        Value::Bool(_) | Value::Number(_) | Value::Object(_) => spec.normalize(&value.to_string()),
+        _ => Err(format!("قيمة غير صالحة للإعداد {}: {}", key, value)),
+    }
+}
+
+impl SettingSpec {
+    pub fn stored_default(&self) -> String {
+        if self.quoted {
+            serde_json::to_string(self.default).unwrap_or_default()
+        } else {
+            self.default.to_string()
+        }
+    }
+
+    // Older builds and the settings page write quoted keys both with and without quotes.
+    pub fn unquote(&self, stored: &str) -> String {
+        if self.quoted {
+            serde_json::from_str::<String>(stored).unwrap_or_else(|_| stored.to_string())
+        } else {
+            stored.to_string()
+        }
+    }
+
+    pub fn normalize(&self, raw: &str) -> Result<String, String> {
+        let mut value = self.unquote(raw);
+        let invalid = || format!("قيمة غير صالحة للإعداد {}: {}", self.key, raw);
+
+        match self.ty {
+            SettingType::Bool => {
+                if value != "true" && value != "false" {
+                    return Err(invalid());
+                }
+            }
+            SettingType::Integer { min, max } => {
+                let n: i64 = value.trim().parse().map_err(|_| invalid())?;
+                if n < min || n > max {
+                    return Err(format!(
+                        "قيمة الإعداد {} يجب أن تكون بين {} و {}",
+                        self.key, min, max
+                    ));
+                }
+                value = n.to_string();
+            }
+            SettingType::Float { min, max } => {
+                let n: f64 = value.trim().parse().map_err(|_| invalid())?;
+                if !n.is_finite() || n < min || n > max {
+                    return Err(format!(
+                        "قيمة الإعداد {} يجب أن تكون بين {} و {}",
+                        self.key, min, max
+                    ));
+                }
+                value = value.trim().to_string();
+            }
+            SettingType::Choice(options) => {
+                if !options.contains(&value.as_str()) {
+                    return Err(format!(
+                        "قيمة غير صالحة للإعداد {}: {} (المسموح: {})",
+                        self.key,
+                        value,
+                        options.join(", ")
+                    ));
+                }
+            }
+            SettingType::ShortcutMap => value = crate::shortcuts::normalize_bindings(&value)?,
+            SettingType::Text | SettingType::Secret => {}
+        }
+
+        if self.quoted {
+            serde_json::to_string(&value).map_err(|e| e.to_string())
+        } else {
+            Ok(value)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn rejects_wrong_types() {
+        assert!(validate("auto_start", "yes").is_err());
+        assert!(validate("beam_size", "five").is_err());
+        assert!(validate("beam_size", "11").is_err());
+        assert!(validate("auto_stop_seconds", "NaN").is_err());
+        assert!(validate("auto_stop_seconds", "inf").is_err());
+        assert!(validate("insertion_method", "telepathy").is_err());
+        assert!(validate("no_such_key", "1").is_err());
+    }
+
+    #[test]
+    fn normalizes_numbers() {
+        assert_eq!(validate("beam_size", " 7 ").unwrap(), "7");
+        assert_eq!(validate("auto_stop_seconds", " 2.5 ").unwrap(), "2.5");
+    }
+
+    #[test]
+    fn validates_json_values() {
+        assert_eq!(validate_json("auto_start", &json!(true)).unwrap(), "true");
+        assert_eq!(validate_json("beam_size", &json!(6)).unwrap(), "6");
+        assert_eq!(validate_json("language", &json!("en")).unwrap(), "\"en\"");
+        assert!(validate_json("auto_start", &json!(1)).is_err());
+        assert!(validate_json("beam_size", &json!(true)).is_err());
+        assert!(validate_json("beam_size", &json!(null)).is_err());
+        assert!(validate_json("language", &json!(["ar"])).is_err());
+    }
+
+    #[test]
+    fn accepts_quoted_and_bare_values() {
+        let quoted = validate("language", "\"en\"").unwrap();
+        assert_eq!(quoted, validate("language", "en").unwrap());
+        assert_eq!(find("language").unwrap().unquote(&quoted), "en");
+    }
+
+    #[test]
+    fn defaults_round_trip() {
+        for spec in SCHEMA {
+            let stored = spec.stored_default();
+            assert_eq!(spec.normalize(&stored).as_ref(), Ok(&stored), "{}", spec.key);
+        }
+    }
+}
@@ -0,0 +1,620 @@
+use crate::commands::dictation::{active_profile, cancel_recording, DictationState};
+use crate::db::Database;
+use crate::commands::insertion::{insert_and_record, undo_insertion};
+use crate::settings::schema::LANGUAGES;
+use crate::settings::{Settings, SettingsState};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+
+const DOUBLE_TAP_WINDOW_MS: u128 = 400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutAction {
+    ToggleDictation,
+    CancelDictation,
+    RetypeLast,
+    ToggleRefinement,
+    SwitchLanguage,
+    PasteLastRaw,
+    UndoInsertion,
+    ReinsertRaw,
+}
+
+impl ShortcutAction {
+    pub const ALL: &'static [ShortcutAction] = &[
+        ShortcutAction::ToggleDictation,
+        ShortcutAction::CancelDictation,
+        ShortcutAction::RetypeLast,
+        ShortcutAction::ToggleRefinement,
+        ShortcutAction::SwitchLanguage,
+        ShortcutAction::PasteLastRaw,
+        ShortcutAction::UndoInsertion,
+        ShortcutAction::ReinsertRaw,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            ShortcutAction::ToggleDictation => "toggle_dictation",
+            ShortcutAction::CancelDictation => "cancel_dictation",
+            ShortcutAction::RetypeLast => "retype_last",
+            ShortcutAction::ToggleRefinement => "toggle_refinement",
+            ShortcutAction::SwitchLanguage => "switch_language",
+            ShortcutAction::PasteLastRaw => "paste_last_raw",
+            ShortcutAction::UndoInsertion => "undo_insertion",
+            ShortcutAction::ReinsertRaw => "reinsert_raw",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|a| a.id() == id)
+    }
+
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            ShortcutAction::ToggleDictation => "toggle-dictation",
+            ShortcutAction::CancelDictation => "cancel-dictation",
+            ShortcutAction::RetypeLast => "retype-last",
+            ShortcutAction::ToggleRefinement => "toggle-ai-refinement",
+            ShortcutAction::SwitchLanguage => "switch-language",
+            ShortcutAction::PasteLastRaw => "paste-last-raw",
+            ShortcutAction::UndoInsertion => "undo-insertion",
+            ShortcutAction::ReinsertRaw => "reinsert-raw",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ShortcutConfig {
+    pub bindings: Vec<(ShortcutAction, String)>,
+    pub push_to_talk: bool,
+    pub min_hold_ms: u64,
+}
+
+impl ShortcutConfig {
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            bindings: settings.bindings(),
+            push_to_talk: settings.shortcut_mode == "push_to_talk",
+            min_hold_ms: settings.push_to_talk_min_hold_ms,
+        }
+    }
+
+    fn parsed(&self) -> Vec<(ShortcutAction, ShortcutType)> {
+        self.bindings
+            .iter()
+            .filter_map(|(action, combo)| {
+                // Push-to-talk only applies to the dictation binding.
+                let push_to_talk = self.push_to_talk && *action == ShortcutAction::ToggleDictation;
+                parse_shortcut(combo, push_to_talk).map(|ty| (*action, ty))
+            })
+            .collect()
+    }
+}
+
+pub struct ShortcutState(pub Arc<Mutex<ShortcutConfig>>);
+
+#[derive(Debug, Clone, PartialEq)]
+enum ShortcutType {
+    DoubleTap(String),
+    ModifierCombo(Modifiers, String),
+    PushToTalk(Modifiers, String),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Modifiers {
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+    meta: bool,
+}
+
+impl Modifiers {
+    fn count(&self) -> usize {
+        [self.ctrl, self.shift, self.alt, self.meta].iter().filter(|m| **m).count()
+    }
+
+    fn held_by(&self, held: &Modifiers) -> bool {
+        (!self.ctrl || held.ctrl)
+            && (!self.shift || held.shift)
+            && (!self.alt || held.alt)
+            && (!self.meta || held.meta)
+    }
+
+    fn releases(&self, key: &rdev::Key) -> bool {
+        (self.ctrl && is_ctrl_key(key))
+            || (self.shift && is_shift_key(key))
+            || (self.alt && is_alt_key(key))
+            || (self.meta && is_meta_key(key))
+    }
+}
+
+fn parse_shortcut(shortcut: &str, push_to_talk: bool) -> Option<ShortcutType> {
+    let parts: Vec<&str> = shortcut.split('+').map(|s| s.trim()).collect();
+
+    if parts.len() == 2 && parts[0] == parts[1] && parts[0].len() == 1 {
+        return Some(ShortcutType::DoubleTap(parts[0].to_uppercase()));
+    }
+
+    let mut modifiers = Modifiers::default();
+    let mut key = String::new();
+
+    for part in &parts {
+        match part.to_lowercase().as_str() {
+            "ctrl" | "control" => modifiers.ctrl = true,
+            "shift" => modifiers.shift = true,
+            "alt" => modifiers.alt = true,
+            "meta" | "super" | "cmd" => modifiers.meta = true,
+            _ => key = part.to_string(),
+        }
+    }
+
+    if key.is_empty() {
+        return None;
+    }
+
+    if push_to_talk {
+        Some(ShortcutType::PushToTalk(modifiers, key))
+    } else {
+        Some(ShortcutType::ModifierCombo(modifiers, key))
+    }
+}
+
+// "shift+ctrl+d" and "Ctrl+Shift+D" describe the same binding.
+pub fn canonical_shortcut(shortcut: &str) -> Option<String> {
+    match parse_shortcut(shortcut, false)? {
+        ShortcutType::DoubleTap(key) => Some(format!("{}+{}", key, key)),
+        ShortcutType::ModifierCombo(m, key) | ShortcutType::PushToTalk(m, key) => {
+            let mut parts = Vec::new();
+            if m.ctrl {
+                parts.push("Ctrl".to_string());
+            }
+            if m.shift {
+                parts.push("Shift".to_string());
+            }
+            if m.alt {
+                parts.push("Alt".to_string());
+            }
+            if m.meta {
+                parts.push("Meta".to_string());
+            }
+            parts.push(if key.chars().count() == 1 { key.to_uppercase() } else { key });
+            Some(parts.join("+"))
+        }
+    }
+}
+
+pub fn check_conflicts(bindings: &[(ShortcutAction, String)]) -> Result<(), String> {
+    let mut seen: Vec<(ShortcutAction, String)> = Vec::new();
+    for (action, shortcut) in bindings {
+        let canonical = canonical_shortcut(shortcut)
+            .ok_or_else(|| format!("اختصار غير صالح للإجراء {}: {}", action.id(), shortcut))?;
+        if let Some((other, _)) = seen.iter().find(|(_, c)| *c == canonical) {
+            return Err(format!(
+                "الاختصار {} مستخدم بالفعل للإجراء {}",
+                canonical,
+                other.id()
+            ));
+        }
+        seen.push((*action, canonical));
+    }
+    Ok(())
+}
+
+// Validates the stored `shortcut_bindings` map. The dictation toggle stays in the `shortcut` key.
+pub fn normalize_bindings(raw: &str) -> Result<String, String> {
+    let parsed: BTreeMap<String, String> = serde_json::from_str(raw)
+        .map_err(|e| format!("قيمة غير صالحة لاختصارات الإجراءات: {}", e))?;
+
+    let mut bindings = Vec::new();
+    for (id, shortcut) in parsed {
+        let action = ShortcutAction::from_id(&id)
+            .filter(|a| *a != ShortcutAction::ToggleDictation)
+            .ok_or_else(|| format!("إجراء اختصار غير معروف: {}", id))?;
+        if shortcut.trim().is_empty() {
+            continue;
+        }
+        let canonical = canonical_shortcut(&shortcut)
+            .ok_or_else(|| format!("اختصار غير صالح للإجراء {}: {}", id, shortcut))?;
+        bindings.push((action, canonical));
+    }
+    check_conflicts(&bindings)?;
+
+    let map: BTreeMap<&str, &str> = bindings.iter().map(|(a, s)| (a.id(), s.as_str())).collect();
+    serde_json::to_string(&map).map_err(|e| e.to_string())
+}
+
+pub fn parse_bindings(stored: &str) -> BTreeMap<ShortcutAction, String> {
+    serde_json::from_str::<BTreeMap<String, String>>(stored)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(id, shortcut)| ShortcutAction::from_id(&id).map(|a| (a, shortcut)))
+        .filter(|(a, s)| *a != ShortcutAction::ToggleDictation && !s.is_empty())
+        .collect()
+}
+
+fn rdev_key_matches(rdev_key: &rdev::Key, target: &str) -> bool {
+    let target_upper = target.to_uppercase();
+    match rdev_key {
+        rdev::Key::KeyA => target_upper == "A",
+        rdev::Key::KeyB => target_upper == "B",
+        rdev::Key::KeyC => target_upper == "C",
+        rdev::Key::KeyD => target_upper == "D",
+        rdev::Key::KeyE => target_upper == "E",
+        rdev::Key::KeyF => target_upper == "F",
+        rdev::Key::KeyG => target_upper == "G",
+        rdev::Key::KeyH => target_upper == "H",
+        rdev::Key::KeyI => target_upper == "I",
+        rdev::Key::KeyJ => target_upper == "J",
+        rdev::Key::KeyK => target_upper == "K",
+        rdev::Key::KeyL => target_upper == "L",
+        rdev::Key::KeyM => target_upper == "M",
+        rdev::Key::KeyN => target_upper == "N",
+        rdev::Key::KeyO => target_upper == "O",
+        rdev::Key::KeyP => target_upper == "P",
+        rdev::Key::KeyQ => target_upper == "Q",
+        rdev::Key::KeyR => target_upper == "R",
+        rdev::Key::KeyS => target_upper == "S",
+        rdev::Key::KeyT => target_upper == "T",
+        rdev::Key::KeyU => target_upper == "U",
+        rdev::Key::KeyV => target_upper == "V",
+        rdev::Key::KeyW => target_upper == "W",
+        rdev::Key::KeyX => target_upper == "X",
+        rdev::Key::KeyY => target_upper == "Y",
+        rdev::Key::KeyZ => target_upper == "Z",
+        rdev::Key::Num0 => target_upper == "0",
+        rdev::Key::Num1 => target_upper == "1",
+        rdev::Key::Num2 => target_upper == "2",
+        rdev::Key::Num3 => target_upper == "3",
+        rdev::Key::Num4 => target_upper == "4",
+        rdev::Key::Num5 => target_upper == "5",
+        rdev::Key::Num6 => target_upper == "6",
+        rdev::Key::Num7 => target_upper == "7",
+        rdev::Key::Num8 => target_upper == "8",
+        rdev::Key::Num9 => target_upper == "9",
+        rdev::Key::F1 => target_upper == "F1",
+        rdev::Key::F2 => target_upper == "F2",
+        rdev::Key::F3 => target_upper == "F3",
+        rdev::Key::F4 => target_upper == "F4",
+        rdev::Key::F5 => target_upper == "F5",
+        rdev::Key::F6 => target_upper == "F6",
+        rdev::Key::F7 => target_upper == "F7",
+        rdev::Key::F8 => target_upper == "F8",
+        rdev::Key::F9 => target_upper == "F9",
+        rdev::Key::F10 => target_upper == "F10",
+        rdev::Key::F11 => target_upper == "F11",
+        rdev::Key::F12 => target_upper == "F12",
+        rdev::Key::Space => target_upper == "SPACE",
+        rdev::Key::Tab => target_upper == "TAB",
+        rdev::Key::Escape => target_upper == "ESCAPE" || target_upper == "ESC",
+        _ => false,
+    }
+}
+
+fn is_modifier_key(key: &rdev::Key) -> bool {
+    matches!(
+        key,
+        rdev::Key::ControlLeft
+            | rdev::Key::ControlRight
+            | rdev::Key::ShiftLeft
+            | rdev::Key::ShiftRight
+            | rdev::Key::Alt
+            | rdev::Key::AltGr
+            | rdev::Key::MetaLeft
+            | rdev::Key::MetaRight
+    )
+}
+
+fn is_ctrl_key(key: &rdev::Key) -> bool {
+    matches!(key, rdev::Key::ControlLeft | rdev::Key::ControlRight)
+}
+
+fn is_shift_key(key: &rdev::Key) -> bool {
+    matches!(key, rdev::Key::ShiftLeft | rdev::Key::ShiftRight)
+}
+
+fn is_alt_key(key: &rdev::Key) -> bool {
+    matches!(key, rdev::Key::Alt | rdev::Key::AltGr)
+}
+
+fn is_meta_key(key: &rdev::Key) -> bool {
+    matches!(key, rdev::Key::MetaLeft | rdev::Key::MetaRight)
+}
+
+fn last_dictation(db: &Database) -> Result<(String, String), String> {
+    let conn = db.0.lock().map_err(|e| e.to_string())?;
+    conn.query_row(
+        "SELECT text, raw_text FROM dictation_history WHERE source = 'dictation' ORDER BY id DESC LIMIT 1",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .map_err(|_| "لا يوجد إملاء سابق".to_string())
+}
+
+// Settings of the profile matching the focused window, if any.
+fn window_settings(app: &tauri::AppHandle) -> Arc<Settings> {
+    let settings = app.state::<SettingsState>().get();
+    let profile = active_profile(&app.state::<DictationState>(), &app.state::<Database>())
+        .unwrap_or_else(|e| {
+            tracing::warn!("[shortcut] Failed to match app profile: {}", e);
+            None
+        });
+    match profile {
+        Some(profile) => Arc::new(settings.with_profile(&profile)),
+        None => settings,
+    }
+}
+
+fn type_text(app: &tauri::AppHandle, text: &str, raw_text: &str) -> Result<(), String> {
+    let state = app.state::<DictationState>();
+    insert_and_record(&state, &window_settings(app), text, raw_text).map(|_| ())
+}
+
+// Everything but start/stop runs here; the frontend owns the recording toggle.
+fn run_action(app: &tauri::AppHandle, action: ShortcutAction) -> Result<String, String> {
+    let db = app.state::<Database>();
+    let settings = app.state::<SettingsState>();
+
+    match action {
+        ShortcutAction::CancelDictation => {
+            let state = app.state::<DictationState>();
+            tauri::async_runtime::block_on(cancel_recording(&state, app))?;
+            Ok(String::new())
+        }
+        ShortcutAction::RetypeLast => {
+            let (text, raw_text) = last_dictation(&db)?;
+            type_text(app, &text, &raw_text)?;
+            Ok(text)
+        }
+        ShortcutAction::PasteLastRaw => {
+            let (text, raw_text) = last_dictation(&db)?;
+            let raw = if raw_text.is_empty() { text } else { raw_text };
+            type_text(app, &raw, &raw)?;
+            Ok(raw)
+        }
+        ShortcutAction::ToggleRefinement => {
+            let enabled = !settings.get().ai_refinement;
+            settings.set(&db, "ai_refinement", &enabled.to_string())?;
+            Ok(enabled.to_string())
+        }
+        ShortcutAction::SwitchLanguage => {
+            let current = settings.get().language.clone();
+            let index = LANGUAGES.iter().position(|l| *l == current).unwrap_or(0);
+            let next = LANGUAGES[(index + 1) % LANGUAGES.len()];
+            settings.set(&db, "language", next)?;
+            Ok(next.to_string())
+        }
+        ShortcutAction::UndoInsertion | ShortcutAction::ReinsertRaw => {
+            let state = app.state::<DictationState>();
+            let reinsert_raw = action == ShortcutAction::ReinsertRaw;
+            undo_insertion(&state, &window_settings(app), reinsert_raw)
+        }
+        ShortcutAction::ToggleDictation => Ok(String::new()),
+    }
+}
+
+fn dispatch(app: &tauri::AppHandle, action: ShortcutAction) {
+    tracing::debug!("[shortcut] Action '{}' triggered", action.id());
+
+    if action == ShortcutAction::ToggleDictation {
+        let _ = app.emit(action.event_name(), ());
+        return;
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let payload = match run_action(&app, action) {
+            Ok(value) => serde_json::json!({ "success": true, "value": value }),
+            Err(e) => {
+                tracing::warn!("[shortcut] Action '{}' failed: {}", action.id(), e);
+                serde_json::json!({ "success": false, "message": e })
+            }
+        };
+        let _ = app.emit(action.event_name(), payload);
+    });
+}
+
+// A tap shorter than the minimum hold discards the recording instead of typing it.
+fn push_to_talk_release(held: Duration, min_hold_ms: u64) -> &'static str {
+    if held >= Duration::from_millis(min_hold_ms) {
+        "up"
+    } else {
+        "tap"
+    }
+}
+
+struct KeyListener {
+    app: tauri::AppHandle,
+    config: Arc<Mutex<ShortcutConfig>>,
+    held: Modifiers,
+    last_tap: Option<(String, Instant)>,
+    // When the push-to-talk combo went down, while it is held.
+    push_to_talk: Option<Instant>,
+}
+
+impl KeyListener {
+    fn on_press(&mut self, key: rdev::Key) {
+        if is_ctrl_key(&key) { self.held.ctrl = true; return; }
+        if is_shift_key(&key) { self.held.shift = true; return; }
+        if is_alt_key(&key) { self.held.alt = true; return; }
+        if is_meta_key(&key) { self.held.meta = true; return; }
+
+        if is_modifier_key(&key) { return; }
+
+        let config = self.config.lock().unwrap().clone();
+        let parsed = config.parsed();
+
+        let double_tap = parsed.iter().find_map(|(action, ty)| match ty {
+            ShortcutType::DoubleTap(target) if rdev_key_matches(&key, target) => Some((*action, target.clone())),
+            _ => None,
+        });
+        match double_tap {
+            Some((action, target)) => {
+                let now = Instant::now();
+                let repeated = self.last_tap.as_ref().is_some_and(|(last, at)| {
+                    *last == target && now.duration_since(*at).as_millis() < DOUBLE_TAP_WINDOW_MS
+                });
+                if repeated {
+                    self.last_tap = None;
+                    tracing::debug!("[shortcut] Double-{} detected", target);
+                    dispatch(&self.app, action);
+                } else {
+                    self.last_tap = Some((target, now));
+                }
+            }
+            None => self.last_tap = None,
+        }
+
+        // With overlapping combos (Ctrl+D and Ctrl+Shift+D) the most specific one wins.
+        let combo = parsed
+            .iter()
+            .filter_map(|(action, ty)| match ty {
+                ShortcutType::ModifierCombo(m, target) | ShortcutType::PushToTalk(m, target)
+                    if m.held_by(&self.held) && rdev_key_matches(&key, target) =>
+                {
+                    Some((*action, ty, m.count()))
+                }
+                _ => None,
+            })
+            .max_by_key(|(_, _, count)| *count);
+
+        match combo {
+            Some((_, ShortcutType::PushToTalk(..), _)) => self.press_push_to_talk(),
+            Some((action, _, _)) => dispatch(&self.app, action),
+            None => {}
+        }
+    }
+
+    fn on_release(&mut self, key: rdev::Key) {
+        if is_ctrl_key(&key) { self.held.ctrl = false; }
+        if is_shift_key(&key) { self.held.shift = false; }
+        if is_alt_key(&key) { self.held.alt = false; }
+        if is_meta_key(&key) { self.held.meta = false; }
+
+        let config = self.config.lock().unwrap().clone();
+        let releases_combo = config.parsed().iter().any(|(_, ty)| match ty {
+            ShortcutType::PushToTalk(m, target) => rdev_key_matches(&key, target) || m.releases(&key),
+            _ => false,
+        });
+        if !releases_combo {
+            return;
+        }
+
+        let Some(pressed_at) = self.push_to_talk.take() else {
+            return;
+        };
+        let event = push_to_talk_release(pressed_at.elapsed(), config.min_hold_ms);
+        if event == "up" {
+            tracing::debug!("[shortcut] Push-to-talk released, stopping dictation...");
+        } else {
+            tracing::debug!("[shortcut] Push-to-talk released before {}ms, cancelling", config.min_hold_ms);
+        }
+        let _ = self.app.emit("push-to-talk", event);
+    }
+
+    // Recording starts on key-down so the first words aren't lost to the hold threshold.
+    fn press_push_to_talk(&mut self) {
+        // Holding the key auto-repeats KeyPress; only the first one counts.
+        if self.push_to_talk.is_some() {
+            return;
+        }
+        self.push_to_talk = Some(Instant::now());
+        tracing::debug!("[shortcut] Push-to-talk pressed, starting dictation...");
+        let _ = self.app.emit("push-to-talk", "down");
+    }
+}
+
+pub fn spawn_listener(app: tauri::AppHandle, config: Arc<Mutex<ShortcutConfig>>) {
+    std::thread::spawn(move || {
+        let mut listener = KeyListener {
+            app,
+            config,
+            held: Modifiers::default(),
+            last_tap: None,
+            push_to_talk: None,
+        };
+
+        rdev::listen(move |event| match event.event_type {
+            rdev::EventType::KeyPress(key) => listener.on_press(key),
+            rdev::EventType::KeyRelease(key) => listener.on_release(key),
+            _ => {}
+        })
+        .expect("Failed to start global key listener");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(raw: &str) -> Result<BTreeMap<String, String>, String> {
+        normalize_bindings(raw).map(|s| serde_json::from_str(&s).unwrap())
+    }
+
+    #[test]
+    fn canonicalizes_shortcuts() {
+        assert_eq!(canonical_shortcut("shift+ctrl+d").as_deref(), Some("Ctrl+Shift+D"));
+        assert_eq!(canonical_shortcut(" Meta + alt + x ").as_deref(), Some("Alt+Meta+X"));
+        assert_eq!(canonical_shortcut("control+F5").as_deref(), Some("Ctrl+F5"));
+        assert_eq!(canonical_shortcut("z+z").as_deref(), Some("Z+Z"));
+        assert_eq!(canonical_shortcut("ctrl+shift"), None);
+    }
+
+    #[test]
+    fn detects_conflicts_after_canonicalizing() {
+        let bindings = vec![
+            (ShortcutAction::ToggleDictation, "Ctrl+Shift+D".to_string()),
+            (ShortcutAction::RetypeLast, "shift+ctrl+d".to_string()),
+        ];
+        let error = check_conflicts(&bindings).unwrap_err();
+        assert!(error.contains("toggle_dictation"));
+
+        let distinct = vec![
+            (ShortcutAction::ToggleDictation, "Ctrl+Shift+D".to_string()),
+            (ShortcutAction::RetypeLast, "Ctrl+D".to_string()),
+        ];
+        assert!(check_conflicts(&distinct).is_ok());
+    }
+
+    #[test]
+    fn normalizes_bindings() {
+        let normalized = bindings(r#"{"retype_last":"alt+ctrl+r","undo_insertion":"  "}"#).unwrap();
+        assert_eq!(normalized.len(), 1);
+        assert_eq!(normalized["retype_last"], "Ctrl+Alt+R");
+    }
+
+    #[test]
+    fn rejects_invalid_bindings() {
+        assert!(bindings(r#"{"fly_away":"Ctrl+F"}"#).is_err());
+        assert!(bindings(r#"{"toggle_dictation":"Ctrl+Shift+D"}"#).is_err());
+        assert!(bindings(r#"{"retype_last":"ctrl+shift"}"#).is_err());
+        assert!(bindings(r#"{"retype_last":"Ctrl+R","undo_insertion":"r+ctrl"}"#).is_err());
+        assert!(bindings("[]").is_err());
+    }
+
+    #[test]
+    fn push_to_talk_applies_to_dictation_only() {
+        let config = ShortcutConfig {
+            bindings: vec![
+                (ShortcutAction::ToggleDictation, "Ctrl+Shift+D".to_string()),
+                (ShortcutAction::CancelDictation, "Ctrl+Shift+X".to_string()),
+            ],
+            push_to_talk: true,
+            min_hold_ms: 300,
+        };
+        let parsed = config.parsed();
+        assert!(matches!(parsed[0], (ShortcutAction::ToggleDictation, ShortcutType::PushToTalk(..))));
+        assert!(matches!(parsed[1], (ShortcutAction::CancelDictation, ShortcutType::ModifierCombo(..))));
+    }
+
+    #[test]
+    fn short_taps_cancel_push_to_talk() {
+        assert_eq!(push_to_talk_release(Duration::from_millis(299), 300), "tap");
+        assert_eq!(push_to_talk_release(Duration::from_millis(300), 300), "up");
+        assert_eq!(push_to_talk_release(Duration::from_secs(5), 300), "up");
+        assert_eq!(push_to_talk_release(Duration::ZERO, 0), "up");
+    }
+}
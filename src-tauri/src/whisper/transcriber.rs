@@ -1,10 +1,85 @@
+use crate::pipeline::vocabulary::estimate_tokens;
+use serde::Serialize;
 use std::path::PathBuf;
 use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
 
+// whisper.cpp keeps only the last n_text_ctx / 2 prompt tokens, so an overlong
+// vocabulary silently pushes the base prompt out.
+pub const PROMPT_TOKEN_LIMIT: usize = 224;
+const PROMPT_TOKEN_MARGIN: usize = 8;
+// Reserved after the vocabulary for the previous chunk's words while streaming.
+pub const CONTEXT_TOKEN_BUDGET: usize = 40;
+
+pub fn base_prompt(language: &str) -> &'static str {
+    match language {
+        "en" => "Voice dictation in English. The text contains complete sentences with proper punctuation. No songs, music, or subtitles.",
+        _ => "إملاء صوتي باللغة العربية الفصحى والعامية. النص يحتوي على جمل كاملة مع علامات ترقيم صحيحة، ولا يحتوي على أناشيد أو موسيقى أو ترجمات.",
+    }
+}
+
+pub fn vocabulary_budget(language: &str) -> usize {
+    PROMPT_TOKEN_LIMIT
+        .saturating_sub(estimate_tokens(base_prompt(language)) + CONTEXT_TOKEN_BUDGET + PROMPT_TOKEN_MARGIN)
+}
+
+pub const DEFAULT_BEAM_SIZE: i32 = 5;
+// Same step as whisper.cpp's default: retries at 0.2, 0.4, ... up to 1.0.
+const TEMPERATURE_INC: f32 = 0.2;
+const FALLBACK_BEST_OF: i32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decoding {
+    // One greedy pass with no retries; lowest latency.
+    Fast,
+    // Beam search, retried at rising temperatures like Fallback.
+    Accurate { beam_size: i32 },
+    // Greedy, re-decoded at rising temperatures when the entropy or logprob thresholds fail.
+    Fallback,
+}
+
+impl Decoding {
+    // "auto" gives None, which leaves each call on its own default.
+    pub fn parse(name: &str, beam_size: i32) -> Result<Option<Self>, String> {
+        match name {
+            "auto" => Ok(None),
+            "fast" => Ok(Some(Self::Fast)),
+            "accurate" => Ok(Some(Self::Accurate { beam_size })),
+            "fallback" => Ok(Some(Self::Fallback)),
+            _ => Err(format!("ملف فك الترميز غير معروف: {}", name)),
+        }
+    }
+
+    fn params<'a, 'b>(self) -> FullParams<'a, 'b> {
+        let strategy = match self {
+            Self::Fast => SamplingStrategy::Greedy { best_of: 1 },
+            Self::Accurate { beam_size } => SamplingStrategy::BeamSearch {
+                beam_size: beam_size.max(1),
+                patience: -1.0,
+            },
+            Self::Fallback => SamplingStrategy::Greedy {
+                best_of: FALLBACK_BEST_OF,
+            },
+        };
+        let mut params = FullParams::new(strategy);
+        params.set_temperature(0.0);
+        params.set_temperature_inc(if self == Self::Fast { 0.0 } else { TEMPERATURE_INC });
+        params
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TranscriptSegment {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+}
+
 pub struct WhisperTranscriber {
     ctx: Option<WhisperContext>,
     language: String,
-    custom_vocabulary: String,
+    vocabulary_prompt: String,
+    // From settings or the app profile; None keeps each call's default.
+    decoding: Option<Decoding>,
 }
 
 impl WhisperTranscriber {
@@ -12,20 +87,30 @@ impl WhisperTranscriber {
         Self {
             ctx: None,
             language: "ar".to_string(),
-            custom_vocabulary: String::new(),
+            vocabulary_prompt: String::new(),
+            decoding: None,
         }
     }
 
     pub fn load_model(&mut self, model_path: &PathBuf, use_gpu: bool) -> Result<(), anyhow::Error> {
+        self.set_model(Self::open_model(model_path, use_gpu)?);
+        Ok(())
+    }
+
+    // Loading takes seconds, so callers sharing the transcriber open the model without holding
+    // its lock and only lock for set_model().
+    pub fn open_model(model_path: &PathBuf, use_gpu: bool) -> Result<WhisperContext, anyhow::Error> {
         let mut params = WhisperContextParameters::default();
         params.use_gpu(use_gpu);
-        let ctx = WhisperContext::new_with_params(
+        WhisperContext::new_with_params(
             model_path.to_str().ok_or_else(|| anyhow::anyhow!("Invalid model path"))?,
             params,
         )
-        .map_err(|e| anyhow::anyhow!("فشل تحميل نموذج Whisper: {}", e))?;
+        .map_err(|e| anyhow::anyhow!("فشل تحميل نموذج Whisper: {}", e))
+    }
+
+    pub fn set_model(&mut self, ctx: WhisperContext) {
         self.ctx = Some(ctx);
-        Ok(())
     }
 
     pub fn get_language(&self) -> String {
@@ -36,31 +121,36 @@ impl WhisperTranscriber {
         self.language = lang.to_string();
     }
 
-    pub fn set_custom_vocabulary(&mut self, vocab: &str) {
-        self.custom_vocabulary = vocab.to_string();
+    // Expects a prompt already fitted to vocabulary_budget().
+    pub fn set_vocabulary_prompt(&mut self, prompt: &str) {
+        self.vocabulary_prompt = prompt.to_string();
     }
 
-    fn apply_anti_hallucination(params: &mut FullParams, language: &str, custom_vocab: &str) {
+    pub fn set_decoding(&mut self, decoding: Option<Decoding>) {
+        self.decoding = decoding;
+    }
+
+    // The thresholds only take effect when the decoding profile allows temperature fallback.
+    fn apply_anti_hallucination(params: &mut FullParams, language: &str, vocabulary: &str, context: &str) {
         params.set_suppress_blank(true);
         params.set_suppress_nst(true);
         params.set_no_speech_thold(0.6);
         params.set_entropy_thold(2.4);
         params.set_logprob_thold(-1.0);
-        params.set_temperature(0.0);
-        params.set_temperature_inc(0.0);
-        let base_prompt = match language {
-            "en" => "Voice dictation in English. The text contains complete sentences with proper punctuation. No songs, music, or subtitles.",
-            _ => "إملاء صوتي باللغة العربية الفصحى والعامية. النص يحتوي على جمل كاملة مع علامات ترقيم صحيحة، ولا يحتوي على أناشيد أو موسيقى أو ترجمات.",
-        };
-        let initial_prompt = if custom_vocab.trim().is_empty() {
-            base_prompt.to_string()
-        } else {
-            format!("{} {}", base_prompt, custom_vocab.trim())
-        };
+        // Context goes last: Whisper weighs the tokens nearest the audio most.
+        let initial_prompt = [base_prompt(language), vocabulary.trim(), context.trim()]
+            .into_iter()
+            .filter(|p| !p.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
         params.set_initial_prompt(&initial_prompt);
     }
 
     pub fn transcribe(&self, audio_data: &[f32]) -> Result<String, anyhow::Error> {
+        self.transcribe_with(audio_data, self.decoding.unwrap_or(Decoding::Fallback))
+    }
+
+    pub fn transcribe_with(&self, audio_data: &[f32], decoding: Decoding) -> Result<String, anyhow::Error> {
         tracing::debug!("[whisper] transcribe called with {} samples ({:.1}s of audio)", audio_data.len(), audio_data.len() as f64 / 16000.0);
 
         let ctx = self
@@ -72,16 +162,16 @@ impl WhisperTranscriber {
         let mut state = ctx.create_state()
             .map_err(|e| anyhow::anyhow!("فشل إنشاء حالة Whisper: {}", e))?;
 
-        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        let mut params = decoding.params();
         params.set_language(Some(&self.language));
         params.set_translate(false);
         params.set_no_timestamps(true);
         params.set_print_progress(true);
         params.set_print_realtime(false);
         params.set_print_special(false);
-        Self::apply_anti_hallucination(&mut params, &self.language, &self.custom_vocabulary);
+        Self::apply_anti_hallucination(&mut params, &self.language, &self.vocabulary_prompt, "");
 
-        tracing::debug!("[whisper] Running transcription (language: {})...", self.language);
+        tracing::debug!("[whisper] Running transcription (language: {}, decoding: {:?})...", self.language, decoding);
         let start = std::time::Instant::now();
         state
             .full(params, audio_data)
@@ -105,7 +195,72 @@ impl WhisperTranscriber {
         Ok(text.trim().to_string())
     }
 
-    pub fn transcribe_chunk(&self, audio_chunk: &[f32]) -> Result<String, anyhow::Error> {
+    pub fn transcribe_segments(&self, audio_data: &[f32]) -> Result<Vec<TranscriptSegment>, anyhow::Error> {
+        self.transcribe_segments_with(audio_data, self.decoding.unwrap_or(Decoding::Fallback))
+    }
+
+    pub fn transcribe_segments_with(
+        &self,
+        audio_data: &[f32],
+        decoding: Decoding,
+    ) -> Result<Vec<TranscriptSegment>, anyhow::Error> {
+        let ctx = self
+            .ctx
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("النموذج غير محمّل"))?;
+
+        let mut state = ctx.create_state()
+            .map_err(|e| anyhow::anyhow!("فشل إنشاء حالة Whisper: {}", e))?;
+
+        let mut params = decoding.params();
+        params.set_language(Some(&self.language));
+        params.set_translate(false);
+        params.set_no_timestamps(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_special(false);
+        Self::apply_anti_hallucination(&mut params, &self.language, &self.vocabulary_prompt, "");
+
+        let start = std::time::Instant::now();
+        state
+            .full(params, audio_data)
+            .map_err(|e| anyhow::anyhow!("فشل التحويل: {}", e))?;
+        tracing::debug!("[whisper] Segment transcription took {:.1}s", start.elapsed().as_secs_f64());
+
+        let num_segments = state.full_n_segments();
+        let mut segments = Vec::new();
+        for i in 0..num_segments {
+            if let Some(segment) = state.get_segment(i) {
+                if let Ok(seg_text) = segment.to_str() {
+                    let text = seg_text.trim();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    segments.push(TranscriptSegment {
+                        start_ms: segment.start_timestamp() * 10,
+                        end_ms: segment.end_timestamp() * 10,
+                        text: text.to_string(),
+                    });
+                }
+            }
+        }
+
+        tracing::debug!("[whisper] Got {} timed segments", segments.len());
+        Ok(segments)
+    }
+
+    // Chunks are decoded while the user is still speaking, so they default to the fastest profile.
+    // `context` is the tail of the text so far, fitted to CONTEXT_TOKEN_BUDGET.
+    pub fn transcribe_chunk(&self, audio_chunk: &[f32], context: &str) -> Result<String, anyhow::Error> {
+        self.transcribe_chunk_with(audio_chunk, context, self.decoding.unwrap_or(Decoding::Fast))
+    }
+
+    pub fn transcribe_chunk_with(
+        &self,
+        audio_chunk: &[f32],
+        context: &str,
+        decoding: Decoding,
+    ) -> Result<String, anyhow::Error> {
         let ctx = self
             .ctx
             .as_ref()
@@ -114,7 +269,7 @@ impl WhisperTranscriber {
         let mut state = ctx.create_state()
             .map_err(|e| anyhow::anyhow!("فشل إنشاء حالة Whisper: {}", e))?;
 
-        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        let mut params = decoding.params();
         params.set_language(Some(&self.language));
         params.set_translate(false);
         params.set_no_timestamps(true);
@@ -123,7 +278,7 @@ impl WhisperTranscriber {
         params.set_print_realtime(false);
         params.set_print_special(false);
         params.set_no_context(true);
-        Self::apply_anti_hallucination(&mut params, &self.language, &self.custom_vocabulary);
+        Self::apply_anti_hallucination(&mut params, &self.language, &self.vocabulary_prompt, context);
 
         state
             .full(params, audio_chunk)
@@ -0,0 +1,140 @@
+use serde::Serialize;
+use std::process::Command;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ActiveWindow {
+    pub class: String,
+    pub title: String,
+}
+
+pub trait ActiveWindowProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn active_window(&self) -> Option<ActiveWindow>;
+}
+
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+// xprop prints `WM_CLASS(STRING) = "code", "Code"` and `_NET_WM_NAME(UTF8_STRING) = "main.rs - kateb"`.
+fn xprop_strings(output: &str, property: &str) -> Vec<String> {
+    let Some(line) = output.lines().find(|l| l.starts_with(property)) else {
+        return Vec::new();
+    };
+    let Some((_, values)) = line.split_once(" = ") else {
+        return Vec::new();
+    };
+    values
+        .split("\", \"")
+        .map(|v| {
+            let v = v.trim();
+            let v = v.strip_prefix('"').unwrap_or(v);
+            let v = v.strip_suffix('"').unwrap_or(v);
+            v.replace("\\\"", "\"")
+        })
+        .collect()
+}
+
+pub struct X11Window;
+
+impl ActiveWindowProvider for X11Window {
+    fn name(&self) -> &'static str {
+        "x11"
+    }
+
+    fn active_window(&self) -> Option<ActiveWindow> {
+        let root = run("xprop", &["-root", "_NET_ACTIVE_WINDOW"])?;
+        let id = root.split_whitespace().last()?.trim_end_matches(',');
+        if id == "0x0" {
+            return None;
+        }
+
+        let props = run("xprop", &["-id", id, "WM_CLASS", "_NET_WM_NAME", "WM_NAME"])?;
+        let class = xprop_strings(&props, "WM_CLASS").pop().unwrap_or_default();
+        let title = xprop_strings(&props, "_NET_WM_NAME")
+            .pop()
+            .or_else(|| xprop_strings(&props, "WM_NAME").pop())
+            .unwrap_or_default();
+        Some(ActiveWindow { class, title })
+    }
+}
+
+pub struct HyprlandWindow;
+
+impl ActiveWindowProvider for HyprlandWindow {
+    fn name(&self) -> &'static str {
+        "hyprland"
+    }
+
+    fn active_window(&self) -> Option<ActiveWindow> {
+        let json: serde_json::Value = serde_json::from_str(&run("hyprctl", &["activewindow", "-j"])?).ok()?;
+        Some(ActiveWindow {
+            class: json["class"].as_str()?.to_string(),
+            title: json["title"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+fn sway_focused(node: &serde_json::Value) -> Option<&serde_json::Value> {
+    if node["focused"].as_bool() == Some(true) {
+        return Some(node);
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[*key].as_array())
+        .flatten()
+        .find_map(sway_focused)
+}
+
+pub struct SwayWindow;
+
+impl ActiveWindowProvider for SwayWindow {
+    fn name(&self) -> &'static str {
+        "sway"
+    }
+
+    fn active_window(&self) -> Option<ActiveWindow> {
+        let tree: serde_json::Value = serde_json::from_str(&run("swaymsg", &["-t", "get_tree"])?).ok()?;
+        let node = sway_focused(&tree)?;
+        // Native Wayland clients report app_id; XWayland ones only window_properties.class.
+        let class = node["app_id"]
+            .as_str()
+            .or_else(|| node["window_properties"]["class"].as_str())?;
+        Some(ActiveWindow {
+            class: class.to_string(),
+            title: node["name"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+// Stands in where the compositor exposes no focused-window query (e.g. GNOME on Wayland).
+pub struct FixedWindow(pub Option<ActiveWindow>);
+
+impl ActiveWindowProvider for FixedWindow {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn active_window(&self) -> Option<ActiveWindow> {
+        self.0.clone()
+    }
+}
+
+pub fn detect() -> Box<dyn ActiveWindowProvider> {
+    let has = |var: &str| std::env::var_os(var).is_some_and(|v| !v.is_empty());
+    let provider: Box<dyn ActiveWindowProvider> = if has("HYPRLAND_INSTANCE_SIGNATURE") {
+        Box::new(HyprlandWindow)
+    } else if has("SWAYSOCK") {
+        Box::new(SwayWindow)
+    } else if has("DISPLAY") {
+        Box::new(X11Window)
+    } else {
+        Box::new(FixedWindow(None))
+    };
+    tracing::info!("[window] Using '{}' active window provider", provider.name());
+    provider
+}